@@ -0,0 +1,203 @@
+//! Parsing from an [`AsyncRead`]er.
+
+use std::{future::poll_fn, io::ErrorKind, pin::Pin};
+
+use futures_io::AsyncRead;
+
+use crate::{parser::ParseResult, IncrementalParser, ParserLimits, Progress, ScanError, Warning};
+
+/// The size of the buffer of bytes read from the [`AsyncRead`]er.
+const READ_BUFFER_LEN: usize = 8 * 1024;
+
+/// A parser that pulls UTF-8 encoded bytes from an [`AsyncRead`]er.
+///
+/// Events are fetched with [`Self::next_event_async`], which awaits more bytes when the ones read
+/// so far are not enough to parse the next event. The events are the same as those of a
+/// [`Parser`] given the whole input. Bytes are read 8KiB at a time and fed to an
+/// [`IncrementalParser`], whose caveats apply.
+///
+/// An event that still needs more input after a read is only parsed again once twice as many
+/// bytes as that read fed were read, or the input ended. An event spanning `n` bytes is thus
+/// parsed `O(log n)` times rather than once per read. The parser may however wait for more bytes
+/// than are left in the event, which it would otherwise have resolved earlier.
+///
+/// Invalid UTF-8 and read failures are reported as in [`ReaderInput`].
+///
+/// This requires the `async` feature.
+///
+/// ```
+/// # use saphyr_parser::{AsyncParser, Event, TScalarStyle};
+/// # futures::executor::block_on(async {
+/// let input: &[u8] = b"key: value";
+/// let mut parser = AsyncParser::new(input);
+/// let mut events = vec![];
+/// while let Some(event) = parser.next_event_async().await {
+///     events.push(event.unwrap().0);
+/// }
+/// assert_eq!(events.len(), 8);
+/// assert_eq!(events[3], Event::Scalar("key".into(), TScalarStyle::Plain, 0, None));
+/// # });
+/// ```
+///
+/// [`Parser`]: crate::Parser
+/// [`ReaderInput`]: crate::ReaderInput
+pub struct AsyncParser<R> {
+    /// The source of bytes.
+    reader: R,
+    /// The buffer bytes are read into.
+    buffer: Box<[u8]>,
+    /// The parser the bytes are fed to.
+    inner: IncrementalParser,
+}
+
+impl<R: AsyncRead + Unpin> AsyncParser<R> {
+    /// Create a new parser reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; READ_BUFFER_LEN].into_boxed_slice(),
+            inner: IncrementalParser::new(),
+        }
+    }
+
+    /// Whether to keep tags across multiple documents when parsing.
+    ///
+    /// See [`Parser::keep_tags`](crate::Parser::keep_tags).
+    #[must_use]
+    pub fn keep_tags(mut self, value: bool) -> Self {
+        self.inner = self.inner.keep_tags(value);
+        self
+    }
+
+    /// Whether to emit comments as events.
+    ///
+    /// See [`Parser::keep_comments`](crate::Parser::keep_comments).
+    #[must_use]
+    pub fn keep_comments(mut self, value: bool) -> Self {
+        self.inner = self.inner.keep_comments(value);
+        self
+    }
+
+    /// Set the limits the input is checked against.
+    ///
+    /// See [`Parser::limits`](crate::Parser::limits).
+    #[must_use]
+    pub fn limits(mut self, limits: ParserLimits) -> Self {
+        self.inner = self.inner.limits(limits);
+        self
+    }
+
+    /// Whether to keep parsing after an error.
+    ///
+    /// See [`Parser::recover_errors`](crate::Parser::recover_errors).
+    #[must_use]
+    pub fn recover_errors(mut self, value: bool) -> Self {
+        self.inner = self.inner.recover_errors(value);
+        self
+    }
+
+    /// Whether to collect warnings.
+    ///
+    /// See [`Parser::collect_warnings`](crate::Parser::collect_warnings).
+    #[must_use]
+    pub fn collect_warnings(mut self, value: bool) -> Self {
+        self.inner = self.inner.collect_warnings(value);
+        self
+    }
+
+    /// Return the errors the parser has recovered from so far, leaving none in their place.
+    ///
+    /// See [`Parser::take_errors`](crate::Parser::take_errors).
+    pub fn take_errors(&mut self) -> Vec<ScanError> {
+        self.inner.take_errors()
+    }
+
+    /// Return the warnings found so far, leaving none in their place.
+    ///
+    /// See [`Parser::take_warnings`](crate::Parser::take_warnings).
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.inner.take_warnings()
+    }
+
+    /// Return the name of the anchor with the given ID.
+    ///
+    /// See [`Parser::anchor_name`](crate::Parser::anchor_name).
+    #[must_use]
+    pub fn anchor_name(&self, id: usize) -> Option<&str> {
+        self.inner.anchor_name(id)
+    }
+
+    /// Fetch the next event, reading from the reader as needed.
+    ///
+    /// Returns `None` once the stream has ended.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading the next event fails, including when the input is not
+    /// valid UTF-8 or reading fails.
+    pub async fn next_event_async(&mut self) -> Option<ParseResult<'static>> {
+        // The number of bytes to read before parsing the event again. It doubles each time the
+        // event needs more input, lest a long event be parsed again once per read.
+        let mut wanted = 1;
+        loop {
+            match self.inner.next_event() {
+                Ok(Progress::Event(event, span)) => return Some(Ok((event, span))),
+                Ok(Progress::NeedMoreInput) => {
+                    wanted = self.read(wanted).await.saturating_mul(2);
+                }
+                Ok(Progress::Finished) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Read from the reader and feed the bytes to the parser until at least `wanted` bytes were
+    /// fed or the input ended.
+    ///
+    /// Returns the number of bytes fed.
+    async fn read(&mut self, wanted: usize) -> usize {
+        let mut fed = 0;
+        while fed < wanted && !self.inner.is_finished() {
+            let reader = &mut self.reader;
+            let buffer = &mut self.buffer;
+            match poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, buffer)).await {
+                Ok(0) => self.inner.finish(),
+                Ok(n) => {
+                    self.inner.feed_bytes(&self.buffer[..n]);
+                    fed += n;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => self.inner.fail_read(&e),
+            }
+        }
+        fed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+
+    use super::AsyncParser;
+
+    #[test]
+    fn test_long_scalar_is_parsed_again_few_times() {
+        // The scalar spans 128 reads of 8KiB.
+        let s = format!("key: {}\n", "a".repeat(1 << 20));
+        let mut parser = AsyncParser::new(s.as_bytes());
+        let n_events = block_on(async {
+            let mut n_events = 0;
+            while let Some(event) = parser.next_event_async().await {
+                event.unwrap();
+                n_events += 1;
+            }
+            n_events
+        });
+        assert_eq!(n_events, 8);
+        // Reading twice as many bytes each time, the scalar is resolved after 8 reads.
+        assert!(
+            parser.inner.rollbacks <= 10,
+            "rolled back {} times",
+            parser.inner.rollbacks
+        );
+    }
+}
@@ -0,0 +1,951 @@
+//! A lossless concrete syntax tree (CST).
+//!
+//! Unlike events, the tree keeps every byte of the input: indentation, comments, quoting styles,
+//! the choice of block or flow collections and directives. Its text is always the input it was
+//! built from, which makes it suitable for format-preserving edits and linters.
+//!
+//! The tree is made of two layers, in the style of `rowan`:
+//!   - The green tree ([`GreenNode`], [`GreenToken`]) is immutable and holds the text. Green
+//!     nodes know their length but not their position, so that they can be shared and reused
+//!     when editing.
+//!   - The red tree ([`SyntaxNode`], [`SyntaxToken`]) is a view over the green tree with parent
+//!     pointers and offsets. It is built on the fly while navigating.
+//!
+//! Edits are made by building new green nodes and replacing old ones with
+//! [`SyntaxNode::replace_with`], which returns the new green root.
+//!
+//! ```
+//! # use saphyr_parser::cst::{parse, SyntaxKind};
+//! let input = "# config\nkey: 'value'  # trailing\n";
+//! let root = parse(input).unwrap();
+//! assert_eq!(root.to_string(), input);
+//!
+//! let scalars = root
+//!     .descendants_with_tokens()
+//!     .filter_map(|element| element.into_token())
+//!     .filter(|token| token.kind() == SyntaxKind::SingleQuotedScalar)
+//!     .map(|token| token.text().to_owned())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(scalars, ["'value'"]);
+//! ```
+
+use alloc::{rc::Rc, string::String, sync::Arc, vec, vec::Vec};
+use core::{fmt, iter, ops::Range};
+
+use crate::{LexemeKind, Lexer, ScanError, TScalarStyle, TokenType};
+
+/// The kind of a node or token of the tree.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SyntaxKind {
+    /// The whole input, at the root of the tree.
+    ///
+    /// Its children are documents and the trivia between them.
+    Stream,
+    /// A document, from its first directive or `---` to its `...`, if any.
+    Document,
+    /// A block sequence, made of [`SyntaxKind::SequenceEntry`] nodes.
+    ///
+    /// This includes sequences that are not indented under their mapping key.
+    BlockSequence,
+    /// A block mapping, made of [`SyntaxKind::MappingEntry`] nodes.
+    BlockMapping,
+    /// A flow sequence, from its `[` to its `]`.
+    ///
+    /// Its entries are nodes (or [`SyntaxKind::MappingEntry`] nodes for single pair mappings)
+    /// separated by [`SyntaxKind::FlowEntry`] tokens.
+    FlowSequence,
+    /// A flow mapping, from its `{` to its `}`.
+    ///
+    /// Its entries are [`SyntaxKind::MappingEntry`] nodes separated by [`SyntaxKind::FlowEntry`]
+    /// tokens.
+    FlowMapping,
+    /// An entry of a block sequence: the `-` and the node that follows.
+    SequenceEntry,
+    /// An entry of a mapping: the `?` if the key is explicit, the key, the `:` and the value.
+    ///
+    /// The key and the value may be missing.
+    MappingEntry,
+    /// A scalar node: its anchor and tag, if any, and the scalar token.
+    ///
+    /// Nodes that have properties but no content (e.g. `!!null`) are scalar nodes without a
+    /// scalar token.
+    Scalar,
+
+    /// A byte order mark.
+    Bom,
+    /// A run of spaces and tabs.
+    Whitespace,
+    /// A line break.
+    LineBreak,
+    /// A comment, from its `#` to the end of its line.
+    Comment,
+    /// A `%YAML` directive.
+    VersionDirective,
+    /// A `%TAG` directive.
+    TagDirective,
+    /// A directive other than `%YAML` and `%TAG`.
+    ReservedDirective,
+    /// `---`.
+    DocumentStart,
+    /// `...`.
+    DocumentEnd,
+    /// `-`, in a block sequence.
+    BlockEntry,
+    /// `,`, in a flow collection.
+    FlowEntry,
+    /// `?`, before an explicit key.
+    Key,
+    /// `:`, before a value.
+    Value,
+    /// `[`.
+    FlowSequenceStart,
+    /// `]`.
+    FlowSequenceEnd,
+    /// `{`.
+    FlowMappingStart,
+    /// `}`.
+    FlowMappingEnd,
+    /// An anchor (`&name`).
+    Anchor,
+    /// A tag (`!tag`).
+    Tag,
+    /// An alias (`*name`).
+    Alias,
+    /// A plain scalar.
+    PlainScalar,
+    /// A single-quoted scalar, quotes included.
+    SingleQuotedScalar,
+    /// A double-quoted scalar, quotes included.
+    DoubleQuotedScalar,
+    /// A literal block scalar, from its header to its last line.
+    LiteralScalar,
+    /// A folded block scalar, from its header to its last line.
+    FoldedScalar,
+}
+
+impl SyntaxKind {
+    /// Return whether the kind is that of a token rather than a node.
+    #[must_use]
+    pub fn is_token(self) -> bool {
+        !matches!(
+            self,
+            SyntaxKind::Stream
+                | SyntaxKind::Document
+                | SyntaxKind::BlockSequence
+                | SyntaxKind::BlockMapping
+                | SyntaxKind::FlowSequence
+                | SyntaxKind::FlowMapping
+                | SyntaxKind::SequenceEntry
+                | SyntaxKind::MappingEntry
+                | SyntaxKind::Scalar
+        )
+    }
+
+    /// Return whether the kind is that of trivia, i.e. of a token that has no meaning in YAML.
+    #[must_use]
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Bom | SyntaxKind::Whitespace | SyntaxKind::LineBreak | SyntaxKind::Comment
+        )
+    }
+}
+
+/// A token of the green tree: a kind and its text.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct GreenToken {
+    /// The kind of the token.
+    kind: SyntaxKind,
+    /// The text of the token.
+    text: String,
+}
+
+impl GreenToken {
+    /// Create a new token.
+    #[must_use]
+    pub fn new(kind: SyntaxKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    /// Return the kind of the token.
+    #[must_use]
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// Return the text of the token.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for GreenToken {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.text)
+    }
+}
+
+/// A node of the green tree: a kind and its children.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct GreenNode {
+    /// The kind of the node.
+    kind: SyntaxKind,
+    /// The length, in bytes, of the text of the node.
+    text_len: usize,
+    /// The children of the node.
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    /// Create a new node.
+    #[must_use]
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        Self {
+            kind,
+            text_len: children.iter().map(GreenElement::text_len).sum(),
+            children,
+        }
+    }
+
+    /// Return the kind of the node.
+    #[must_use]
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// Return the length, in bytes, of the text of the node.
+    #[must_use]
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    /// Return the children of the node.
+    #[must_use]
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// Return a copy of the node with the child at `index` replaced.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    #[must_use]
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        let mut children = self.children.clone();
+        children[index] = child;
+        GreenNode::new(self.kind, children)
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.children
+            .iter()
+            .try_for_each(|child| write!(formatter, "{child}"))
+    }
+}
+
+/// A child of a [`GreenNode`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum GreenElement {
+    /// A node.
+    Node(Arc<GreenNode>),
+    /// A token.
+    Token(Arc<GreenToken>),
+}
+
+impl GreenElement {
+    /// Return the kind of the element.
+    #[must_use]
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind(),
+            GreenElement::Token(token) => token.kind(),
+        }
+    }
+
+    /// Return the length, in bytes, of the text of the element.
+    #[must_use]
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len(),
+            GreenElement::Token(token) => token.text().len(),
+        }
+    }
+}
+
+impl From<GreenNode> for GreenElement {
+    fn from(node: GreenNode) -> Self {
+        GreenElement::Node(Arc::new(node))
+    }
+}
+
+impl From<GreenToken> for GreenElement {
+    fn from(token: GreenToken) -> Self {
+        GreenElement::Token(Arc::new(token))
+    }
+}
+
+impl fmt::Display for GreenElement {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GreenElement::Node(node) => write!(formatter, "{node}"),
+            GreenElement::Token(token) => write!(formatter, "{token}"),
+        }
+    }
+}
+
+/// A node of the red tree.
+///
+/// Nodes are cheap to clone. Two nodes are equal if they are the same node of the same tree.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+/// The data of a [`SyntaxNode`].
+struct NodeData {
+    /// The green node this is a view of.
+    green: Arc<GreenNode>,
+    /// The parent of the node and the index of the node in it, unless the node is the root.
+    parent: Option<(SyntaxNode, usize)>,
+    /// The offset, in bytes, of the node in the text of the tree.
+    offset: usize,
+}
+
+impl SyntaxNode {
+    /// Create the root of a red tree.
+    #[must_use]
+    pub fn new_root(green: GreenNode) -> Self {
+        Self::new(Arc::new(green), None, 0)
+    }
+
+    /// Create a node.
+    fn new(green: Arc<GreenNode>, parent: Option<(SyntaxNode, usize)>, offset: usize) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent,
+            offset,
+        }))
+    }
+
+    /// Return the kind of the node.
+    #[must_use]
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    /// Return the green node this is a view of.
+    #[must_use]
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    /// Return the range of bytes the node covers in the text of the tree.
+    #[must_use]
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.text_len()
+    }
+
+    /// Return the parent of the node, unless it is the root.
+    #[must_use]
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.as_ref().map(|(parent, _)| parent.clone())
+    }
+
+    /// Return an iterator over the node and its ancestors, from the node up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    /// Return an iterator over the child nodes of the node.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// Return an iterator over the child nodes and tokens of the node.
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let parent = self.clone();
+        let mut offset = self.0.offset;
+        (0..self.0.green.children().len()).map(move |index| {
+            let child = &parent.0.green.children()[index];
+            let element = match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode::new(
+                    green.clone(),
+                    Some((parent.clone(), index)),
+                    offset,
+                )),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: parent.clone(),
+                    index,
+                    offset,
+                }),
+            };
+            offset += child.text_len();
+            element
+        })
+    }
+
+    /// Return an iterator over the node and all the nodes below it, in preorder.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.descendants_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// Return an iterator over the node and all the nodes and tokens below it, in preorder.
+    ///
+    /// Tokens are thus yielded in the order of the text.
+    pub fn descendants_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let mut stack = vec![SyntaxElement::Node(self.clone())];
+        iter::from_fn(move || {
+            let element = stack.pop()?;
+            if let SyntaxElement::Node(node) = &element {
+                let children = node.children_with_tokens().collect::<Vec<_>>();
+                stack.extend(children.into_iter().rev());
+            }
+            Some(element)
+        })
+    }
+
+    /// Replace the node with `replacement` and return the green node of the new root.
+    ///
+    /// The tree the node belongs to is left untouched.
+    #[must_use]
+    pub fn replace_with(&self, replacement: GreenNode) -> GreenNode {
+        match &self.0.parent {
+            Some((parent, index)) => {
+                parent.replace_with(parent.green().replace_child(*index, replacement.into()))
+            }
+            None => replacement,
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{:?}@{:?}", self.kind(), self.text_range())
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.0.green)
+    }
+}
+
+/// A token of the red tree.
+#[derive(Clone)]
+pub struct SyntaxToken {
+    /// The green token this is a view of.
+    green: Arc<GreenToken>,
+    /// The parent of the token.
+    parent: SyntaxNode,
+    /// The index of the token in its parent.
+    index: usize,
+    /// The offset, in bytes, of the token in the text of the tree.
+    offset: usize,
+}
+
+impl SyntaxToken {
+    /// Return the kind of the token.
+    #[must_use]
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    /// Return the text of the token.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    /// Return the green token this is a view of.
+    #[must_use]
+    pub fn green(&self) -> &GreenToken {
+        &self.green
+    }
+
+    /// Return the range of bytes the token covers in the text of the tree.
+    #[must_use]
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text().len()
+    }
+
+    /// Return the parent of the token.
+    #[must_use]
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// Replace the token with `replacement` and return the green node of the new root.
+    ///
+    /// The tree the token belongs to is left untouched.
+    #[must_use]
+    pub fn replace_with(&self, replacement: GreenToken) -> GreenNode {
+        self.parent.replace_with(
+            self.parent
+                .green()
+                .replace_child(self.index, replacement.into()),
+        )
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl Eq for SyntaxToken {}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{:?}@{:?} {:?}",
+            self.kind(),
+            self.text_range(),
+            self.text()
+        )
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.text())
+    }
+}
+
+/// A node or a token of the red tree.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SyntaxElement {
+    /// A node.
+    Node(SyntaxNode),
+    /// A token.
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    /// Return the kind of the element.
+    #[must_use]
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    /// Return the range of bytes the element covers in the text of the tree.
+    #[must_use]
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+
+    /// Return the element if it is a node.
+    #[must_use]
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    /// Return the element if it is a token.
+    #[must_use]
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
+
+/// Build the tree of the given input.
+///
+/// The tree is built from the tokens of the [`Lexer`]. Input that the scanner accepts but the
+/// [`Parser`] rejects (e.g. a sequence entry in a mapping) still has a tree. Use a parser to
+/// validate the input.
+///
+/// # Errors
+/// Returns an error if the input cannot be scanned.
+///
+/// [`Parser`]: crate::Parser
+pub fn parse(source: &str) -> Result<SyntaxNode, ScanError> {
+    let mut builder = Builder::new();
+    for lexeme in Lexer::new(source) {
+        let lexeme = lexeme?;
+        let kind = match lexeme.kind {
+            LexemeKind::Token(token) => token,
+            LexemeKind::Whitespace => {
+                builder.trivia(GreenToken::new(SyntaxKind::Whitespace, lexeme.text));
+                continue;
+            }
+            LexemeKind::LineBreak => {
+                builder.trivia(GreenToken::new(SyntaxKind::LineBreak, lexeme.text));
+                continue;
+            }
+            LexemeKind::Comment => {
+                builder.trivia(GreenToken::new(SyntaxKind::Comment, lexeme.text));
+                continue;
+            }
+            LexemeKind::Bom => {
+                builder.trivia(GreenToken::new(SyntaxKind::Bom, lexeme.text));
+                continue;
+            }
+        };
+        builder.token(&kind, lexeme.text);
+    }
+    Ok(SyntaxNode::new_root(builder.finish()))
+}
+
+/// A node of the green tree that is being built.
+struct Frame {
+    /// The kind of the node.
+    kind: SyntaxKind,
+    /// The children of the node so far.
+    children: Vec<GreenElement>,
+    /// Whether the node is a block collection that ends with a [`TokenType::BlockEnd`].
+    ///
+    /// Block sequences that are not indented under their mapping key have no such token.
+    block_end: bool,
+}
+
+/// Builds the green tree out of tokens, which delimit its nodes.
+struct Builder {
+    /// The nodes that are being built, from the root to the innermost one.
+    stack: Vec<Frame>,
+    /// The properties (anchor and tag) of the next node and the trivia in between them.
+    ///
+    /// Properties come before the token that tells which node they belong to.
+    properties: Vec<GreenElement>,
+    /// Whether the current document has a `---`.
+    explicit_start: bool,
+    /// Whether the current document has content.
+    has_content: bool,
+}
+
+impl Builder {
+    fn new() -> Self {
+        Self {
+            stack: vec![Frame {
+                kind: SyntaxKind::Stream,
+                children: vec![],
+                block_end: false,
+            }],
+            properties: vec![],
+            explicit_start: false,
+            has_content: false,
+        }
+    }
+
+    /// Return the kind of the innermost node.
+    fn top(&self) -> SyntaxKind {
+        self.stack.last().unwrap().kind
+    }
+
+    /// Add an element to the innermost node.
+    fn push(&mut self, element: impl Into<GreenElement>) {
+        self.stack.last_mut().unwrap().children.push(element.into());
+    }
+
+    /// Start a node as a child of the innermost node.
+    fn start(&mut self, kind: SyntaxKind, block_end: bool) {
+        self.stack.push(Frame {
+            kind,
+            children: vec![],
+            block_end,
+        });
+    }
+
+    /// Finish the innermost node, unless it is the root.
+    fn finish_node(&mut self) {
+        if self.stack.len() > 1 {
+            let frame = self.stack.pop().unwrap();
+            self.push(GreenNode::new(frame.kind, frame.children));
+        }
+    }
+
+    /// Finish nodes until the innermost node matches `stop` or is a document.
+    fn finish_until(&mut self, stop: impl Fn(SyntaxKind) -> bool) {
+        while !stop(self.top()) && !matches!(self.top(), SyntaxKind::Document | SyntaxKind::Stream)
+        {
+            self.finish_node();
+        }
+    }
+
+    /// Move the pending properties into the innermost node.
+    fn take_properties(&mut self) {
+        let properties = core::mem::take(&mut self.properties);
+        self.stack.last_mut().unwrap().children.extend(properties);
+    }
+
+    /// Make a scalar node out of pending properties, whose node turned out to have no content.
+    fn flush_properties(&mut self) {
+        let Some(last) = self
+            .properties
+            .iter()
+            .rposition(|element| !element.kind().is_trivia())
+        else {
+            self.take_properties();
+            return;
+        };
+        let trailing = self.properties.split_off(last + 1);
+        self.start(SyntaxKind::Scalar, false);
+        self.take_properties();
+        self.finish_node();
+        self.stack.last_mut().unwrap().children.extend(trailing);
+    }
+
+    /// Add trivia to the tree.
+    fn trivia(&mut self, token: GreenToken) {
+        if self.properties.is_empty() {
+            self.push(token);
+        } else {
+            self.properties.push(token.into());
+        }
+    }
+
+    /// Start a document if there is none.
+    fn start_document(&mut self) {
+        if self.stack.len() == 1 {
+            self.start(SyntaxKind::Document, false);
+            self.explicit_start = false;
+            self.has_content = false;
+        }
+    }
+
+    /// Finish the current document, if any.
+    fn finish_document(&mut self) {
+        self.flush_properties();
+        while self.stack.len() > 1 {
+            self.finish_node();
+        }
+    }
+
+    /// Note that the current document has content, starting it if needed.
+    fn content(&mut self) {
+        self.start_document();
+        self.has_content = true;
+    }
+
+    /// Add a token to the tree.
+    #[allow(clippy::too_many_lines)]
+    fn token(&mut self, token: &TokenType, text: &str) {
+        let kind = match token {
+            TokenType::StreamStart(_) => return,
+            TokenType::StreamEnd => {
+                self.finish_document();
+                return;
+            }
+            TokenType::VersionDirective(..) | TokenType::TagDirective(..) => {
+                if self.explicit_start || self.has_content {
+                    self.finish_document();
+                }
+                self.start_document();
+                match token {
+                    TokenType::VersionDirective(..) => SyntaxKind::VersionDirective,
+                    TokenType::TagDirective(handle, _) if handle.is_empty() => {
+                        SyntaxKind::ReservedDirective
+                    }
+                    _ => SyntaxKind::TagDirective,
+                }
+            }
+            TokenType::DocumentStart => {
+                if self.explicit_start || self.has_content {
+                    self.finish_document();
+                }
+                self.start_document();
+                self.explicit_start = true;
+                SyntaxKind::DocumentStart
+            }
+            TokenType::DocumentEnd => {
+                if self.stack.len() == 1 {
+                    self.push(GreenToken::new(SyntaxKind::DocumentEnd, text));
+                } else {
+                    self.flush_properties();
+                    self.finish_until(|_| false);
+                    self.push(GreenToken::new(SyntaxKind::DocumentEnd, text));
+                    self.finish_document();
+                }
+                return;
+            }
+            TokenType::BlockSequenceStart | TokenType::BlockMappingStart => {
+                self.content();
+                self.start(
+                    if *token == TokenType::BlockSequenceStart {
+                        SyntaxKind::BlockSequence
+                    } else {
+                        SyntaxKind::BlockMapping
+                    },
+                    true,
+                );
+                self.take_properties();
+                return;
+            }
+            TokenType::BlockEnd => {
+                self.flush_properties();
+                if self.stack.iter().any(|frame| frame.block_end) {
+                    while !self.stack.last().unwrap().block_end {
+                        self.finish_node();
+                    }
+                    self.finish_node();
+                }
+                return;
+            }
+            // The scanner delimits single pair mappings in flow sequences (`[a: b]`) with a
+            // `{` and a `}` that have no text. Their entry is a child of the sequence.
+            TokenType::FlowMappingStart if text.is_empty() => return,
+            TokenType::FlowMappingEnd if text.is_empty() => {
+                self.flush_properties();
+                self.finish_until(|kind| {
+                    matches!(kind, SyntaxKind::FlowSequence | SyntaxKind::FlowMapping)
+                });
+                return;
+            }
+            TokenType::FlowSequenceStart | TokenType::FlowMappingStart => {
+                self.content();
+                let (node, kind) = if *token == TokenType::FlowSequenceStart {
+                    (SyntaxKind::FlowSequence, SyntaxKind::FlowSequenceStart)
+                } else {
+                    (SyntaxKind::FlowMapping, SyntaxKind::FlowMappingStart)
+                };
+                self.start(node, false);
+                self.take_properties();
+                kind
+            }
+            TokenType::FlowSequenceEnd | TokenType::FlowMappingEnd => {
+                self.flush_properties();
+                self.finish_until(|kind| {
+                    matches!(kind, SyntaxKind::FlowSequence | SyntaxKind::FlowMapping)
+                });
+                self.push(GreenToken::new(
+                    if *token == TokenType::FlowSequenceEnd {
+                        SyntaxKind::FlowSequenceEnd
+                    } else {
+                        SyntaxKind::FlowMappingEnd
+                    },
+                    text,
+                ));
+                if matches!(
+                    self.top(),
+                    SyntaxKind::FlowSequence | SyntaxKind::FlowMapping
+                ) {
+                    self.finish_node();
+                }
+                return;
+            }
+            TokenType::BlockEntry => {
+                self.content();
+                self.flush_properties();
+                self.finish_until(|kind| {
+                    matches!(kind, SyntaxKind::BlockSequence | SyntaxKind::MappingEntry)
+                });
+                if self.top() != SyntaxKind::BlockSequence {
+                    // A sequence that is not indented under its mapping key.
+                    self.start(SyntaxKind::BlockSequence, false);
+                }
+                self.start(SyntaxKind::SequenceEntry, false);
+                SyntaxKind::BlockEntry
+            }
+            TokenType::Key => {
+                self.content();
+                self.flush_properties();
+                self.finish_until(|kind| {
+                    matches!(
+                        kind,
+                        SyntaxKind::BlockMapping
+                            | SyntaxKind::FlowMapping
+                            | SyntaxKind::FlowSequence
+                    )
+                });
+                self.start(SyntaxKind::MappingEntry, false);
+                SyntaxKind::Key
+            }
+            TokenType::Value => {
+                self.content();
+                self.flush_properties();
+                self.finish_until(|kind| {
+                    matches!(
+                        kind,
+                        SyntaxKind::MappingEntry
+                            | SyntaxKind::BlockMapping
+                            | SyntaxKind::FlowMapping
+                            | SyntaxKind::FlowSequence
+                    )
+                });
+                if self.top() != SyntaxKind::MappingEntry {
+                    self.start(SyntaxKind::MappingEntry, false);
+                }
+                SyntaxKind::Value
+            }
+            TokenType::FlowEntry => {
+                self.content();
+                self.flush_properties();
+                self.finish_until(|kind| {
+                    matches!(kind, SyntaxKind::FlowSequence | SyntaxKind::FlowMapping)
+                });
+                SyntaxKind::FlowEntry
+            }
+            TokenType::Anchor(_) | TokenType::Tag(..) => {
+                self.content();
+                let kind = if matches!(token, TokenType::Anchor(_)) {
+                    SyntaxKind::Anchor
+                } else {
+                    SyntaxKind::Tag
+                };
+                self.properties.push(GreenToken::new(kind, text).into());
+                return;
+            }
+            TokenType::Alias(_) => {
+                self.content();
+                self.flush_properties();
+                SyntaxKind::Alias
+            }
+            TokenType::Scalar(style, _) => {
+                self.content();
+                self.start(SyntaxKind::Scalar, false);
+                self.take_properties();
+                self.push(GreenToken::new(
+                    match style {
+                        TScalarStyle::Plain => SyntaxKind::PlainScalar,
+                        TScalarStyle::SingleQuoted => SyntaxKind::SingleQuotedScalar,
+                        TScalarStyle::DoubleQuoted => SyntaxKind::DoubleQuotedScalar,
+                        TScalarStyle::Literal => SyntaxKind::LiteralScalar,
+                        TScalarStyle::Folded => SyntaxKind::FoldedScalar,
+                    },
+                    text,
+                ));
+                self.finish_node();
+                return;
+            }
+            // The lexer reports comments as trivia.
+            TokenType::Comment(..) => SyntaxKind::Comment,
+        };
+        // Implicit keys have no text.
+        if !text.is_empty() {
+            self.push(GreenToken::new(kind, text));
+        }
+    }
+
+    /// Finish all nodes and return the root.
+    fn finish(mut self) -> GreenNode {
+        self.finish_document();
+        let root = self.stack.pop().unwrap();
+        GreenNode::new(root.kind, root.children)
+    }
+}
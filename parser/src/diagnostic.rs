@@ -0,0 +1,284 @@
+//! Rendering of errors as annotated source snippets.
+//!
+//! A [`Diagnostic`] holds a message, an optional location in the source and notes. It renders to
+//! a snippet in the style of `rustc`:
+//!
+//! ```text
+//! error[E210]: while parsing a flow mapping, did not find expected ',' or '}'
+//!  --> config.yaml:2:2
+//!   |
+//! 2 | d: e
+//!   |  ^
+//!   = note: expected one of ',', '}'
+//! ```
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::scanner::{Marker, ScanError, ScanErrorKind, Span};
+
+/// How to render a [`Diagnostic`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
+pub enum RenderStyle {
+    /// Plain text.
+    #[default]
+    Plain,
+    /// Text with ANSI escape sequences for colors, for use in terminals.
+    Ansi,
+}
+
+/// An error message, along with the location it refers to in the source.
+///
+/// A [`Diagnostic`] can be created from a [`ScanError`]:
+///
+/// ```
+/// # use saphyr_parser::{Diagnostic, Parser, RenderStyle};
+/// let source = "a: {b: c\nd: e";
+/// let error = Parser::new_from_str(source).find_map(Result::err).unwrap();
+/// let rendered = Diagnostic::from(&error)
+///     .with_origin("config.yaml")
+///     .render(source, RenderStyle::Plain);
+/// assert_eq!(
+///     rendered,
+///     "\
+/// error[E210]: while parsing a flow mapping, did not find expected ',' or '}'
+///  --> config.yaml:2:2
+///   |
+/// 2 | d: e
+///   |  ^
+///   = note: expected one of ',', '}'
+/// "
+/// );
+/// ```
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct Diagnostic {
+    /// The main message.
+    message: String,
+    /// The code of the error, if any.
+    code: Option<&'static str>,
+    /// The location the diagnostic refers to, if any.
+    span: Option<Span>,
+    /// The name of the source (e.g. a file name), if any.
+    origin: Option<String>,
+    /// Additional notes, rendered after the snippet.
+    notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic with the given message and no location.
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            code: None,
+            span: None,
+            origin: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Set the location in the source the diagnostic refers to (builder-style).
+    ///
+    /// An empty span is rendered with a caret (`^`). Other spans are underlined.
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Set the code of the diagnostic (builder-style).
+    #[must_use]
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Set the name of the source the diagnostic refers to, usually a file name (builder-style).
+    #[must_use]
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Add a note to the diagnostic (builder-style).
+    #[must_use]
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Render the diagnostic as an annotated snippet of `source`.
+    ///
+    /// `source` must be the input that was given to the parser. The output ends with a newline.
+    #[must_use]
+    pub fn render(&self, source: &str, style: RenderStyle) -> String {
+        let paint = Paint(style);
+        let mut out = String::new();
+
+        out.push_str(&paint.error("error"));
+        if let Some(code) = self.code {
+            out.push_str(&paint.error(&format!("[{code}]")));
+        }
+        let _ = writeln!(out, "{}", paint.bold(&format!(": {}", self.message)));
+
+        let gutter_width = self.span.map_or(0, |span| {
+            span.end.line().max(span.start.line()).to_string().len()
+        });
+        let gutter = " ".repeat(gutter_width);
+
+        if let Some(span) = self.span {
+            let _ = write!(out, "{gutter}{} ", paint.gutter("-->"));
+            if let Some(origin) = &self.origin {
+                let _ = write!(out, "{origin}:");
+            }
+            let _ = writeln!(out, "{}:{}", span.start.line(), span.start.col() + 1);
+            let _ = writeln!(out, "{gutter} {}", paint.gutter("|"));
+            render_snippet(&mut out, source, span, gutter_width, paint);
+        } else if let Some(origin) = &self.origin {
+            let _ = writeln!(out, "{gutter}{} {origin}", paint.gutter("-->"));
+        }
+
+        for note in &self.notes {
+            let _ = writeln!(out, "{gutter} {} {note}", paint.bold("= note:"));
+        }
+        out
+    }
+}
+
+impl From<&ScanError> for Diagnostic {
+    fn from(error: &ScanError) -> Self {
+        let mut diagnostic = Diagnostic::new(error.info()).with_span(Span::empty(*error.marker()));
+        let kind = error.kind();
+        if kind != ScanErrorKind::Other {
+            diagnostic = diagnostic.with_code(kind.code());
+        }
+        match kind.expected() {
+            [] => {}
+            [token] => diagnostic = diagnostic.with_note(format!("expected {token}")),
+            tokens => {
+                diagnostic = diagnostic.with_note(format!("expected one of {}", tokens.join(", ")));
+            }
+        }
+        diagnostic
+    }
+}
+
+impl From<ScanError> for Diagnostic {
+    fn from(error: ScanError) -> Self {
+        Self::from(&error)
+    }
+}
+
+/// Render the lines of `source` covered by `span`, underlined.
+///
+/// Spans covering more than two lines only have their first and last lines rendered.
+fn render_snippet(out: &mut String, source: &str, span: Span, gutter_width: usize, paint: Paint) {
+    let start = span.start;
+    let end = if span.end.byte_offset() < start.byte_offset() {
+        start
+    } else {
+        span.end
+    };
+
+    if start.line() == end.line() {
+        let (line, col) = line_at(source, start);
+        let width = (end.col() - start.col()).max(1);
+        render_line(out, start.line(), line, col, width, gutter_width, paint);
+        return;
+    }
+
+    let (first, col) = line_at(source, start);
+    let width = first.chars().count().saturating_sub(col).max(1);
+    render_line(out, start.line(), first, col, width, gutter_width, paint);
+    if end.line() > start.line() + 1 {
+        let _ = writeln!(out, "{}", paint.gutter("..."));
+    }
+    let (last, col) = line_at(source, end);
+    render_line(out, end.line(), last, 0, col.max(1), gutter_width, paint);
+}
+
+/// Return the line of `source` that contains `mark`, along with the column of `mark` within it.
+///
+/// The line is returned without its line break. If `mark` is past the end of `source`, the last
+/// line is returned.
+fn line_at(source: &str, mark: Marker) -> (&str, usize) {
+    let mut offset = mark.byte_offset().min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line_start = source[..offset]
+        .rfind(['\r', '\n'])
+        .map_or(0, |idx| idx + 1);
+    let line_end = source[offset..]
+        .find(['\r', '\n'])
+        .map_or(source.len(), |idx| offset + idx);
+    let line = &source[line_start..line_end];
+    (line, source[line_start..offset].chars().count())
+}
+
+/// Render a line of source, with `width` characters underlined from `col`.
+fn render_line(
+    out: &mut String,
+    line_number: usize,
+    line: &str,
+    col: usize,
+    width: usize,
+    gutter_width: usize,
+    paint: Paint,
+) {
+    // Tabs are rendered as a single space so that the underline is aligned.
+    let line = line.replace('\t', " ");
+    let _ = writeln!(
+        out,
+        "{} {}",
+        paint.gutter(&format!("{line_number:>gutter_width$} |")),
+        line
+    );
+    let marker = if width == 1 { "^" } else { "~" };
+    let underline = if width == 1 {
+        marker.to_owned()
+    } else {
+        format!("^{}", marker.repeat(width - 1))
+    };
+    let _ = writeln!(
+        out,
+        "{} {}{}",
+        paint.gutter(&format!("{:gutter_width$} |", "")),
+        " ".repeat(col),
+        paint.error(&underline)
+    );
+}
+
+/// Applies the colors of a [`RenderStyle`] to text.
+#[derive(Clone, Copy)]
+struct Paint(RenderStyle);
+
+impl Paint {
+    /// Wrap `text` in the given ANSI escape sequence, if colors are enabled.
+    fn paint(self, code: &str, text: &str) -> String {
+        match self.0 {
+            RenderStyle::Plain => text.to_owned(),
+            RenderStyle::Ansi => format!("\x1B[{code}m{text}\x1B[0m"),
+        }
+    }
+
+    /// Format `text` as an error (bold red).
+    fn error(self, text: &str) -> String {
+        self.paint("1;31", text)
+    }
+
+    /// Format `text` as part of the gutter (bold blue).
+    fn gutter(self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+
+    /// Format `text` in bold.
+    fn bold(self, text: &str) -> String {
+        self.paint("1", text)
+    }
+}
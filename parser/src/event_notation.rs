@@ -0,0 +1,365 @@
+//! The event notation of the [yaml-test-suite](https://github.com/yaml/yaml-test-suite).
+//!
+//! The yaml-test-suite describes the events of each test as a tree, with one event per line:
+//!
+//! ```text
+//! +STR
+//!  +DOC
+//!   +MAP
+//!    =VAL :key
+//!    =VAL 'value
+//!   -MAP
+//!  -DOC
+//! -STR
+//! ```
+//!
+//! [`format_events`] renders events in this notation and [`parse_events`] reads them back.
+//!
+//! The notation does not hold everything events do, nor do events hold everything the notation
+//! does:
+//!   - Directives are not part of the notation. Parsed [`Event::DocumentStart`]s have none.
+//!   - Events only hold the ID of anchors. When rendering, anchors are named by a lookup such as
+//!     [`Parser::anchor_name`], falling back to the ID (`&1`, `*1`) for unknown names. When
+//!     parsing, anchors are given IDs in the order they appear in, starting from 1, as the
+//!     [`Parser`] does.
+//!   - Tags are rendered in full (`<tag:yaml.org,2002:str>`). When parsing, tags are split into a
+//!     [`Tag`] the way the [`Parser`] splits them by default: `tag:yaml.org,2002:` and `!`
+//!     are handles, other tags have an empty handle.
+//!   - Whether a document end is explicit (`-DOC ...`) and the style of flow collections
+//!     (`+SEQ []`, `+MAP {}`) are not part of events. They are ignored when parsing.
+//!
+//! [`Parser`]: crate::Parser
+//! [`Parser::anchor_name`]: crate::Parser::anchor_name
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{Directives, Event, Span, TScalarStyle, Tag};
+
+/// The handle of tags from the YAML core schema.
+const CORE_SCHEMA_HANDLE: &str = "tag:yaml.org,2002:";
+
+/// Render an event as a line of the notation, without indentation nor a trailing line break.
+///
+/// `anchor_name` returns the name of the anchor with the given ID, as [`Parser::anchor_name`]
+/// does. Anchors it has no name for are rendered with their ID.
+///
+/// Returns `None` for events that are not part of the notation ([`Event::Nothing`] and
+/// [`Event::Comment`]).
+///
+/// ```
+/// # use saphyr_parser::{event_notation::format_event, Event, TScalarStyle};
+/// let event = Event::Scalar("a\nb".into(), TScalarStyle::DoubleQuoted, 1, None);
+/// assert_eq!(format_event(&event, |_| Some("x")).unwrap(), r#"=VAL &x "a\nb"#);
+/// assert_eq!(format_event(&event, |_| None).unwrap(), r#"=VAL &1 "a\nb"#);
+/// ```
+///
+/// [`Parser::anchor_name`]: crate::Parser::anchor_name
+#[must_use]
+pub fn format_event<'a, F>(event: &Event<'_>, anchor_name: F) -> Option<String>
+where
+    F: Fn(usize) -> Option<&'a str>,
+{
+    let line = match event {
+        Event::StreamStart => "+STR".into(),
+        Event::StreamEnd => "-STR".into(),
+        Event::DocumentStart(true, _) => "+DOC ---".into(),
+        Event::DocumentStart(false, _) => "+DOC".into(),
+        Event::DocumentEnd => "-DOC".into(),
+        Event::SequenceStart(id, tag) => format!(
+            "+SEQ{}{}",
+            format_anchor(*id, anchor_name),
+            format_tag(tag.as_ref())
+        ),
+        Event::SequenceEnd => "-SEQ".into(),
+        Event::MappingStart(id, tag) => format!(
+            "+MAP{}{}",
+            format_anchor(*id, anchor_name),
+            format_tag(tag.as_ref())
+        ),
+        Event::MappingEnd => "-MAP".into(),
+        Event::Scalar(value, style, id, tag) => {
+            let style = match style {
+                TScalarStyle::Plain => ':',
+                TScalarStyle::SingleQuoted => '\'',
+                TScalarStyle::DoubleQuoted => '"',
+                TScalarStyle::Literal => '|',
+                TScalarStyle::Folded => '>',
+            };
+            format!(
+                "=VAL{}{} {style}{}",
+                format_anchor(*id, anchor_name),
+                format_tag(tag.as_ref()),
+                escape(value)
+            )
+        }
+        Event::Alias(id) => match anchor_name(*id) {
+            Some(name) => format!("=ALI *{name}"),
+            None => format!("=ALI *{id}"),
+        },
+        Event::Nothing | Event::Comment(..) => return None,
+    };
+    Some(line)
+}
+
+/// Render events in the notation, one per line.
+///
+/// Events are indented by one space per enclosing stream, document and collection, as in the
+/// yaml-test-suite. Anchors are named with `anchor_name`, as in [`format_event`]. Spans are
+/// ignored.
+///
+/// ```
+/// # use saphyr_parser::{event_notation::format_events, Parser};
+/// let mut parser = Parser::new_from_str("--- [a, &x b, *x]");
+/// let events: Vec<_> = parser.by_ref().map(Result::unwrap).collect();
+/// assert_eq!(
+///     format_events(events, |id| parser.anchor_name(id)),
+///     "+STR\n +DOC ---\n  +SEQ\n   =VAL :a\n   =VAL &x :b\n   =ALI *x\n  -SEQ\n -DOC\n-STR\n"
+/// );
+/// ```
+pub fn format_events<'input, 'a, I, F>(events: I, anchor_name: F) -> String
+where
+    I: IntoIterator<Item = (Event<'input>, Span)>,
+    F: Fn(usize) -> Option<&'a str>,
+{
+    let mut out = String::new();
+    let mut depth = 0usize;
+    for (event, _) in events {
+        let Some(line) = format_event(&event, &anchor_name) else {
+            continue;
+        };
+        if line.starts_with('-') {
+            depth = depth.saturating_sub(1);
+        }
+        for _ in 0..depth {
+            out.push(' ');
+        }
+        if line.starts_with('+') {
+            depth += 1;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Parse events written in the notation.
+///
+/// Lines may be indented and empty lines are ignored.
+///
+/// ```
+/// # use saphyr_parser::{event_notation::parse_events, Event, TScalarStyle};
+/// let events = parse_events("+STR\n +DOC\n  =VAL &a :b\n -DOC\n-STR\n").unwrap();
+/// assert_eq!(events[2], Event::Scalar("b".into(), TScalarStyle::Plain, 1, None));
+/// ```
+///
+/// # Errors
+/// Returns a [`NotationError`] if a line is not a valid event or if an alias refers to an
+/// unknown anchor.
+pub fn parse_events(notation: &str) -> Result<Vec<Event<'static>>, NotationError> {
+    let mut anchors = Anchors::default();
+    let mut events = vec![];
+    for (idx, line) in notation.lines().enumerate() {
+        let line = line.trim_start();
+        if line.is_empty() {
+            continue;
+        }
+        let event = parse_event(line, &mut anchors).ok_or_else(|| NotationError {
+            line: idx + 1,
+            info: format!("invalid event `{line}`"),
+        })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// An error encountered while parsing the event notation.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct NotationError {
+    /// The line of the error, starting from 1.
+    line: usize,
+    /// Human-readable details about the error.
+    info: String,
+}
+
+impl NotationError {
+    /// Return the line of the error, starting from 1.
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the information string describing the error.
+    #[must_use]
+    pub fn info(&self) -> &str {
+        &self.info
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotationError {}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} at line {}", self.info, self.line)
+    }
+}
+
+/// The anchors encountered while parsing the notation.
+#[derive(Default)]
+struct Anchors {
+    /// The ID of the latest anchor with each name.
+    ids: BTreeMap<String, usize>,
+    /// The number of anchors encountered, which is also the ID of the latest anchor.
+    count: usize,
+}
+
+/// Parse a line of the notation.
+fn parse_event(line: &str, anchors: &mut Anchors) -> Option<Event<'static>> {
+    let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let event = match (kind, rest) {
+        ("+STR", "") => Event::StreamStart,
+        ("-STR", "") => Event::StreamEnd,
+        ("+DOC", "") => Event::DocumentStart(false, Directives::default()),
+        ("+DOC", "---") => Event::DocumentStart(true, Directives::default()),
+        ("-DOC", "" | "...") => Event::DocumentEnd,
+        ("+SEQ", rest) => {
+            let rest = rest.strip_prefix("[]").unwrap_or(rest).trim_start();
+            let (id, tag, rest) = parse_properties(rest, anchors)?;
+            if !rest.is_empty() {
+                return None;
+            }
+            Event::SequenceStart(id, tag)
+        }
+        ("-SEQ", "") => Event::SequenceEnd,
+        ("+MAP", rest) => {
+            let rest = rest.strip_prefix("{}").unwrap_or(rest).trim_start();
+            let (id, tag, rest) = parse_properties(rest, anchors)?;
+            if !rest.is_empty() {
+                return None;
+            }
+            Event::MappingStart(id, tag)
+        }
+        ("-MAP", "") => Event::MappingEnd,
+        ("=VAL", rest) => {
+            let (id, tag, rest) = parse_properties(rest, anchors)?;
+            let mut chars = rest.chars();
+            let style = match chars.next()? {
+                ':' => TScalarStyle::Plain,
+                '\'' => TScalarStyle::SingleQuoted,
+                '"' => TScalarStyle::DoubleQuoted,
+                '|' => TScalarStyle::Literal,
+                '>' => TScalarStyle::Folded,
+                _ => return None,
+            };
+            Event::Scalar(unescape(chars.as_str())?.into(), style, id, tag)
+        }
+        ("=ALI", rest) => Event::Alias(*anchors.ids.get(rest.strip_prefix('*')?)?),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Parse the anchor and tag of a node, in this order, both being optional.
+///
+/// Returns the ID of the anchor (or 0), the tag and what follows them.
+fn parse_properties<'a>(
+    mut rest: &'a str,
+    anchors: &mut Anchors,
+) -> Option<(usize, Option<Tag<'static>>, &'a str)> {
+    let mut id = 0;
+    if let Some(anchor) = rest.strip_prefix('&') {
+        let (name, after) = anchor.split_once(' ').unwrap_or((anchor, ""));
+        if name.is_empty() {
+            return None;
+        }
+        anchors.count += 1;
+        id = anchors.count;
+        anchors.ids.insert(name.to_owned(), id);
+        rest = after;
+    }
+    let mut tag = None;
+    if let Some(tagged) = rest.strip_prefix('<') {
+        let (full, after) = tagged.split_once('>')?;
+        tag = Some(split_tag(full));
+        rest = after.strip_prefix(' ').unwrap_or(after);
+    }
+    Some((id, tag, rest))
+}
+
+/// Split a full tag into a [`Tag`], the way the parser would by default.
+fn split_tag(full: &str) -> Tag<'static> {
+    let (handle, suffix) = if let Some(suffix) = full.strip_prefix(CORE_SCHEMA_HANDLE) {
+        (CORE_SCHEMA_HANDLE, suffix)
+    } else if let Some(suffix) = full.strip_prefix('!') {
+        ("!", suffix)
+    } else {
+        ("", full)
+    };
+    Tag {
+        handle: Cow::Owned(handle.to_owned()),
+        suffix: Cow::Owned(suffix.to_owned()),
+    }
+}
+
+fn format_anchor<'a>(id: usize, anchor_name: impl Fn(usize) -> Option<&'a str>) -> String {
+    match (id, anchor_name(id)) {
+        (0, _) => String::new(),
+        (_, Some(name)) => format!(" &{name}"),
+        (id, None) => format!(" &{id}"),
+    }
+}
+
+fn format_tag(tag: Option<&Tag<'_>>) -> String {
+    if let Some(tag) = tag {
+        format!(" <{}{}>", tag.handle, tag.suffix)
+    } else {
+        String::new()
+    }
+}
+
+/// Escape the characters of a scalar that cannot appear verbatim on a line.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '\n' => escaped.push_str(r"\n"),
+            '\r' => escaped.push_str(r"\r"),
+            '\x08' => escaped.push_str(r"\b"),
+            '\t' => escaped.push_str(r"\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverse [`escape`].
+///
+/// Returns `None` if `text` contains an unknown escape sequence.
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            'b' => '\x08',
+            't' => '\t',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
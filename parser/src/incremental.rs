@@ -0,0 +1,236 @@
+//! Parsing of inputs whose text is received over time.
+//!
+//! The scanner pulls characters from its input synchronously and cannot be suspended when it runs
+//! out of them. Instead, the state of the parser is saved before each event. Should the parser
+//! need text that has not been received yet, it is rolled back to that state and parsing resumes
+//! from there once more text is available.
+
+use alloc::vec::Vec;
+
+use crate::{input::chunked::ChunkedInput, Event, Parser, ParserLimits, ScanError, Span, Warning};
+
+/// What [`IncrementalParser::next_event`] could make of the input fed so far.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub enum Progress {
+    /// The next event, which is fully resolved.
+    Event(Event<'static>, Span),
+    /// The next event cannot be resolved without more input.
+    NeedMoreInput,
+    /// The stream has ended and there are no more events.
+    Finished,
+}
+
+/// A parser to which the input is pushed as it arrives.
+///
+/// Text is fed in chunks with [`Self::feed`], and the end of the input signaled with
+/// [`Self::finish`]. Meanwhile, [`Self::next_event`] returns the events that can be resolved from
+/// the text fed so far. When the next event depends on text that has not been fed yet (e.g. to
+/// know whether a scalar is a mapping key), [`Progress::NeedMoreInput`] is returned instead.
+///
+/// Once the input is finished, the events are the same as those of a [`Parser`] given the whole
+/// input. Since the input is received in chunks, events borrow nothing from it.
+///
+/// An event that spans many chunks (e.g. a very long scalar) is parsed again each time a chunk is
+/// fed, from the start of the event. Feeding larger chunks is cheaper. A scalar that exceeds
+/// [`ParserLimits::max_scalar_len`] is rejected as soon as enough of it was fed.
+///
+/// ```
+/// # use saphyr_parser::{Event, IncrementalParser, Progress, TScalarStyle};
+/// let mut parser = IncrementalParser::new();
+/// parser.feed("key: val");
+/// let mut events = vec![];
+/// while let Ok(Progress::Event(event, _)) = parser.next_event() {
+///     events.push(event);
+/// }
+/// // The scalar `val` may continue in the next chunk.
+/// assert_eq!(parser.next_event(), Ok(Progress::NeedMoreInput));
+/// assert_eq!(events.len(), 4);
+///
+/// parser.feed("ue\n");
+/// parser.finish();
+/// let events = parser.events().collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(events[0].0, Event::Scalar("value".into(), TScalarStyle::Plain, 0, None));
+/// assert_eq!(parser.next_event(), Ok(Progress::Finished));
+/// ```
+#[derive(Debug)]
+pub struct IncrementalParser {
+    /// The parser, in the state it was after the last event was parsed.
+    parser: Parser<'static, ChunkedInput>,
+    /// The number of times the parser was rolled back, to check how often events are parsed again.
+    #[cfg(test)]
+    pub(crate) rollbacks: usize,
+}
+
+impl IncrementalParser {
+    /// Create a new parser with no input fed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            parser: Parser::new(ChunkedInput::new()),
+            #[cfg(test)]
+            rollbacks: 0,
+        }
+    }
+
+    /// Whether to keep tags across multiple documents when parsing.
+    ///
+    /// See [`Parser::keep_tags`].
+    #[must_use]
+    pub fn keep_tags(mut self, value: bool) -> Self {
+        self.parser = self.parser.keep_tags(value);
+        self
+    }
+
+    /// Whether to emit comments as events.
+    ///
+    /// See [`Parser::keep_comments`].
+    #[must_use]
+    pub fn keep_comments(mut self, value: bool) -> Self {
+        self.parser = self.parser.keep_comments(value);
+        self
+    }
+
+    /// Set the limits the input is checked against.
+    ///
+    /// See [`Parser::limits`].
+    #[must_use]
+    pub fn limits(mut self, limits: ParserLimits) -> Self {
+        self.parser = self.parser.limits(limits);
+        self
+    }
+
+    /// Whether to keep parsing after an error.
+    ///
+    /// See [`Parser::recover_errors`].
+    #[must_use]
+    pub fn recover_errors(mut self, value: bool) -> Self {
+        self.parser = self.parser.recover_errors(value);
+        self
+    }
+
+    /// Whether to collect warnings.
+    ///
+    /// See [`Parser::collect_warnings`].
+    #[must_use]
+    pub fn collect_warnings(mut self, value: bool) -> Self {
+        self.parser = self.parser.collect_warnings(value);
+        self
+    }
+
+    /// Return the errors the parser has recovered from so far, leaving none in their place.
+    ///
+    /// See [`Parser::take_errors`].
+    pub fn take_errors(&mut self) -> Vec<ScanError> {
+        self.parser.take_errors()
+    }
+
+    /// Return the warnings found so far, leaving none in their place.
+    ///
+    /// See [`Parser::take_warnings`].
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.parser.take_warnings()
+    }
+
+    /// Return the name of the anchor with the given ID.
+    ///
+    /// See [`Parser::anchor_name`].
+    #[must_use]
+    pub fn anchor_name(&self, id: usize) -> Option<&str> {
+        self.parser.anchor_name(id)
+    }
+
+    /// Append a chunk of text to the input.
+    ///
+    /// Text fed after [`Self::finish`] was called is ignored.
+    pub fn feed(&mut self, text: &str) {
+        self.parser.input_mut().feed(text.as_bytes());
+    }
+
+    /// Signal that the input is complete.
+    ///
+    /// The remaining events can then be resolved and [`Self::next_event`] no longer returns
+    /// [`Progress::NeedMoreInput`].
+    pub fn finish(&mut self) {
+        self.parser.input_mut().finish();
+    }
+
+    /// Return whether [`Self::finish`] was called.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.parser.input().is_finished()
+    }
+
+    /// Append UTF-8 encoded bytes to the input.
+    ///
+    /// UTF-8 sequences may be split across calls.
+    #[cfg(feature = "async")]
+    pub(crate) fn feed_bytes(&mut self, bytes: &[u8]) {
+        self.parser.input_mut().feed(bytes);
+    }
+
+    /// Stop the input because reading its source failed.
+    #[cfg(feature = "async")]
+    pub(crate) fn fail_read(&mut self, error: &std::io::Error) {
+        self.parser.input_mut().fail_read(error);
+    }
+
+    /// Try to resolve the next event from the input fed so far.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading the next event fails.
+    pub fn next_event(&mut self) -> Result<Progress, ScanError> {
+        let event = if self.is_finished() {
+            // The input can no longer run out, there is no need to be able to go back.
+            self.parser.next_event()
+        } else {
+            let checkpoint = self.parser.checkpoint();
+            let event = self.parser.next_event();
+            // Exceeding a limit only depends on the text read so far. It is reported without
+            // waiting for the rest of the event, which may never fit within the limits.
+            let exceeded_limit = matches!(&event, Some(Err(e)) if e.kind().is_limit());
+            if self.parser.input().is_starved() && !exceeded_limit {
+                self.parser.rollback(checkpoint);
+                #[cfg(test)]
+                {
+                    self.rollbacks += 1;
+                }
+                return Ok(Progress::NeedMoreInput);
+            }
+            self.parser.commit();
+            event
+        };
+        match event {
+            Some(Ok((event, span))) => Ok(Progress::Event(event, span)),
+            Some(Err(e)) => Err(e),
+            None => Ok(Progress::Finished),
+        }
+    }
+
+    /// Return an iterator over the events that can be resolved from the input fed so far.
+    ///
+    /// The iterator stops when more input is needed, when the stream has ended or after an error.
+    pub fn events(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(Event<'static>, Span), ScanError>> + '_ {
+        let mut failed = false;
+        core::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            match self.next_event() {
+                Ok(Progress::Event(event, span)) => Some(Ok((event, span))),
+                Ok(Progress::NeedMoreInput | Progress::Finished) => None,
+                Err(e) => {
+                    failed = true;
+                    Some(Err(e))
+                }
+            }
+        })
+    }
+}
+
+impl Default for IncrementalParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
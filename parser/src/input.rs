@@ -4,15 +4,68 @@
 //! covered by the [`BufferedInput`].
 
 pub(crate) mod buffered;
+pub(crate) mod chunked;
+#[cfg(feature = "std")]
+pub(crate) mod reader;
 pub(crate) mod str;
 
 #[allow(clippy::module_name_repetitions)]
 pub use buffered::BufferedInput;
+#[cfg(feature = "std")]
+#[allow(clippy::module_name_repetitions)]
+pub use reader::ReaderInput;
+
+use alloc::{format, string::String};
+
+use crate::scanner::{Marker, ScanError, ScanErrorKind};
 
 pub use crate::char_traits::{
     is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_z,
 };
 
+/// A failure to read or decode an input.
+///
+/// Inputs that may fail record it and report it through [`Input::error_at`].
+#[derive(Clone, Debug)]
+pub(crate) struct InputFailure {
+    /// The index (in characters) at which the input failed.
+    index: usize,
+    /// The kind of error to report.
+    kind: ScanErrorKind,
+    /// The message of the error to report.
+    info: String,
+}
+
+impl InputFailure {
+    /// Create a failure for an invalid UTF-8 sequence starting with `first` at `index`.
+    pub(crate) fn invalid_utf8(index: usize, first: u8) -> Self {
+        Self {
+            index,
+            kind: ScanErrorKind::InvalidUtf8,
+            info: format!(
+                "{} starting with byte 0x{first:02X}",
+                ScanErrorKind::InvalidUtf8.message()
+            ),
+        }
+    }
+
+    /// Create a failure for an error of the source of bytes at `index`.
+    #[cfg(feature = "std")]
+    pub(crate) fn read_failed(index: usize, error: &std::io::Error) -> Self {
+        Self {
+            index,
+            kind: ScanErrorKind::ReadFailed,
+            info: format!("{}: {error}", ScanErrorKind::ReadFailed.message()),
+        }
+    }
+
+    /// Return the error to report if the scanner reached the failure at `mark`.
+    pub(crate) fn error_at(&self, mark: Marker) -> Option<ScanError> {
+        (mark.index() >= self.index)
+            .then(|| ScanError::new(mark, self.info.clone()).with_kind(self.kind))
+    }
+}
+
 /// Interface for a source of characters.
 ///
 /// Hiding the input's implementation behind this trait allows mostly:
@@ -41,6 +94,30 @@ pub trait Input {
     #[must_use]
     fn bufmaxlen(&self) -> usize;
 
+    /// Return the number of bytes that have been consumed from the input.
+    ///
+    /// This is the byte offset, within the source, of the next character to be read. It is the
+    /// size of the UTF-8 encoding of all characters that were consumed, whether or not the source
+    /// is UTF-8 encoded.
+    #[must_use]
+    fn byte_offset(&self) -> usize;
+
+    /// Return the error that stopped the input, if the scanner reached it at `mark`.
+    ///
+    /// Inputs that may fail (e.g. when reading or decoding bytes) behave as if they were exhausted
+    /// at the first failure and return `\0`. When the scanner reaches the end of the input or
+    /// fails, it calls this method with its current position so that the failure of the input is
+    /// reported rather than an unexpected end of stream. Implementors must return `None` if the
+    /// failure lies after `mark`.
+    ///
+    /// The default implementation returns `None`, for inputs that cannot fail.
+    #[inline]
+    #[must_use]
+    fn error_at(&self, mark: Marker) -> Option<ScanError> {
+        let _ = mark;
+        None
+    }
+
     /// Return whether the buffer (!= stream) is empty.
     #[inline]
     #[must_use]
@@ -422,6 +499,158 @@ pub trait Input {
         }
         n_chars
     }
+
+    /// Skip characters from the input while we encounter letters.
+    ///
+    /// This is the same as [`Input::fetch_while_is_alpha`], without storing the characters.
+    ///
+    /// # Return
+    /// Return the number of characters that were consumed. The number of characters returned can
+    /// be used to advance the index and column, since no end-of-line character will be consumed.
+    fn skip_while_is_alpha(&mut self) -> usize {
+        let mut n_chars = 0;
+        while is_alpha(self.look_ch()) {
+            n_chars += 1;
+            self.skip();
+        }
+        n_chars
+    }
+
+    /// Skip characters from the input while they can continue a plain scalar.
+    ///
+    /// This stops at [breakz], at a `:` that ends the scalar and, if `in_flow`, at flow indicators
+    /// (see [`Input::next_can_be_plain_scalar`]). It also stops at [blanks], unless more of the
+    /// scalar follows them on the same line, in which case implementations may skip over them.
+    /// The default implementation stops at all blanks.
+    ///
+    /// The characters are consumed from the input.
+    ///
+    /// # Return
+    /// Return the number of characters that were consumed. The number of characters returned can
+    /// be used to advance the index and column, since no end-of-line character will be consumed.
+    ///
+    /// [blanks]: is_blank
+    /// [breakz]: is_breakz
+    fn skip_while_plain_scalar_chars(&mut self, in_flow: bool) -> usize {
+        let mut n_chars = 0;
+        // Fill the buffer once and process all characters in the buffer until the next fetch.
+        // Note that `next_can_be_plain_scalar` needs 2 lookahead characters, hence the `for`
+        // loop looping `self.bufmaxlen() - 1` times.
+        loop {
+            self.lookahead(self.bufmaxlen());
+            for _ in 0..self.bufmaxlen() - 1 {
+                if self.next_is_blank_or_breakz() || !self.next_can_be_plain_scalar(in_flow) {
+                    return n_chars;
+                }
+                n_chars += 1;
+                self.skip();
+            }
+        }
+    }
+
+    /// Fetch characters from the input while they can continue a plain scalar and store them in
+    /// `out`.
+    ///
+    /// This stops where [`Input::skip_while_plain_scalar_chars`] does.
+    ///
+    /// The characters are consumed from the input.
+    ///
+    /// # Return
+    /// Return the number of characters that were consumed. The number of characters returned can
+    /// be used to advance the index and column, since no end-of-line character will be consumed.
+    fn fetch_while_plain_scalar_chars(&mut self, in_flow: bool, out: &mut String) -> usize {
+        let mut n_chars = 0;
+        loop {
+            self.lookahead(self.bufmaxlen());
+            for _ in 0..self.bufmaxlen() - 1 {
+                if self.next_is_blank_or_breakz() || !self.next_can_be_plain_scalar(in_flow) {
+                    return n_chars;
+                }
+                n_chars += 1;
+                out.push(self.peek());
+                self.skip();
+            }
+        }
+    }
+
+    /// Skip characters from the input while they need no processing in a quoted scalar.
+    ///
+    /// This stops at [breakz], at the quote closing the scalar (`'` if `single`, `"` otherwise)
+    /// and, in double-quoted scalars, at `\`. It also stops at [blanks], unless they are followed
+    /// by a character other than a [breakz], in which case implementations may skip over them. The
+    /// default implementation stops at all blanks.
+    ///
+    /// The characters are consumed from the input.
+    ///
+    /// # Return
+    /// Return the number of characters that were consumed. The number of characters returned can
+    /// be used to advance the index and column, since no end-of-line character will be consumed.
+    ///
+    /// [blanks]: is_blank
+    /// [breakz]: is_breakz
+    fn skip_while_quoted_scalar_chars(&mut self, single: bool) -> usize {
+        let mut n_chars = 0;
+        while !is_quoted_scalar_stop(self.look_ch(), single) {
+            n_chars += 1;
+            self.skip();
+        }
+        n_chars
+    }
+
+    /// Fetch characters from the input while they need no processing in a quoted scalar and
+    /// store them in `out`.
+    ///
+    /// This stops where [`Input::skip_while_quoted_scalar_chars`] does.
+    ///
+    /// The characters are consumed from the input.
+    ///
+    /// # Return
+    /// Return the number of characters that were consumed. The number of characters returned can
+    /// be used to advance the index and column, since no end-of-line character will be consumed.
+    fn fetch_while_quoted_scalar_chars(&mut self, single: bool, out: &mut String) -> usize {
+        let mut n_chars = 0;
+        while !is_quoted_scalar_stop(self.look_ch(), single) {
+            n_chars += 1;
+            out.push(self.peek());
+            self.skip();
+        }
+        n_chars
+    }
+}
+
+/// Check whether the character needs processing in a quoted scalar.
+///
+/// These are [blanks], [breakz], the closing quote and, in double-quoted scalars, `\`.
+///
+/// [blanks]: is_blank
+/// [breakz]: is_breakz
+#[inline]
+pub(crate) fn is_quoted_scalar_stop(c: char, single: bool) -> bool {
+    is_blank_or_breakz(c)
+        || if single {
+            c == '\''
+        } else {
+            c == '"' || c == '\\'
+        }
+}
+
+/// An [`Input`] whose source outlives the parser and can be sliced.
+///
+/// The scanner uses this to return scalars, anchors and comments that borrow from the source
+/// instead of allocating, whenever their contents appear verbatim in the input.
+///
+/// The default implementation disables borrowing. Inputs which cannot hand out slices (e.g.
+/// [`BufferedInput`]) only need an empty `impl` block.
+pub trait BorrowedInput<'input>: Input {
+    /// Return the slice of the source between the `start` and `end` byte offsets.
+    ///
+    /// Offsets are those returned by [`Input::byte_offset`]. Returning `None` means that the input
+    /// does not support borrowing. If this returns `Some` for an empty range, it must return
+    /// `Some` for any range of offsets returned by [`Input::byte_offset`].
+    #[inline]
+    fn slice_bytes(&self, _start: usize, _end: usize) -> Option<&'input str> {
+        None
+    }
 }
 
 /// Behavior to adopt regarding treating tabs as whitespace.
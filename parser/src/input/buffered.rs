@@ -1,5 +1,5 @@
 use crate::char_traits::is_breakz;
-use crate::input::Input;
+use crate::input::{BorrowedInput, Input};
 
 use arraydeque::ArrayDeque;
 
@@ -13,7 +13,7 @@ use arraydeque::ArrayDeque;
 /// This constant must be set to at least 8. When scanning indent in scalars, the lookahead is done
 /// in a single call if and only if the indent is `BUFFER_LEN - 2` or less. If the indent is higher
 /// than that, the code will fall back to a loop of lookaheads.
-const BUFFER_LEN: usize = 16;
+pub(crate) const BUFFER_LEN: usize = 16;
 
 /// A wrapper around an [`Iterator`] of [`char`]s with a buffer.
 ///
@@ -28,6 +28,8 @@ pub struct BufferedInput<T: Iterator<Item = char>> {
     input: T,
     /// Buffer for the next characters to consume.
     buffer: ArrayDeque<char, BUFFER_LEN>,
+    /// The number of bytes consumed so far, as if the characters were UTF-8 encoded.
+    byte_offset: usize,
 }
 
 impl<T: Iterator<Item = char>> BufferedInput<T> {
@@ -36,6 +38,7 @@ impl<T: Iterator<Item = char>> BufferedInput<T> {
         Self {
             input,
             buffer: ArrayDeque::default(),
+            byte_offset: 0,
         }
     }
 }
@@ -63,9 +66,19 @@ impl<T: Iterator<Item = char>> Input for BufferedInput<T> {
         BUFFER_LEN
     }
 
+    #[inline]
+    fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
     #[inline]
     fn raw_read_ch(&mut self) -> char {
-        self.input.next().unwrap_or('\0')
+        if let Some(c) = self.input.next() {
+            self.byte_offset += c.len_utf8();
+            c
+        } else {
+            '\0'
+        }
     }
 
     #[inline]
@@ -75,6 +88,7 @@ impl<T: Iterator<Item = char>> Input for BufferedInput<T> {
                 self.buffer.push_back(c).unwrap();
                 None
             } else {
+                self.byte_offset += c.len_utf8();
                 Some(c)
             }
         } else {
@@ -84,12 +98,18 @@ impl<T: Iterator<Item = char>> Input for BufferedInput<T> {
 
     #[inline]
     fn skip(&mut self) {
-        self.buffer.pop_front();
+        if let Some(c) = self.buffer.pop_front() {
+            self.byte_offset += c.len_utf8();
+        }
     }
 
     #[inline]
     fn skip_n(&mut self, count: usize) {
-        self.buffer.drain(0..count);
+        self.byte_offset += self
+            .buffer
+            .drain(0..count)
+            .map(char::len_utf8)
+            .sum::<usize>();
     }
 
     #[inline]
@@ -102,3 +122,5 @@ impl<T: Iterator<Item = char>> Input for BufferedInput<T> {
         self.buffer[n]
     }
 }
+
+impl<T: Iterator<Item = char>> BorrowedInput<'_> for BufferedInput<T> {}
@@ -0,0 +1,272 @@
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use core::cell::Cell;
+
+use crate::char_traits::is_breakz;
+use crate::input::{buffered::BUFFER_LEN, BorrowedInput, Input, InputFailure};
+use crate::scanner::{Marker, ScanError};
+
+use arraydeque::ArrayDeque;
+
+/// A parser input made of chunks of bytes that are fed over time.
+///
+/// When the scanner looks at characters that have not been fed yet, the input returns `\0` as if
+/// it ended and marks itself as starved. Whatever the parser made out of it must then be
+/// discarded, and parsing retried from a prior clone of the parser once more bytes have been fed or
+/// the input is finished.
+///
+/// Like [`StrInput`], the input does not pad its buffer with `\0`s when asked to look ahead past
+/// the characters it has. Looking ahead is thus harmless. Only peeking at or reading a character
+/// that has not been fed starves the input. Characters that are fed later are loaded into the
+/// buffer up to the requested lookahead.
+///
+/// Chunks are shared between clones, so that cloning the input is cheap.
+///
+/// [`StrInput`]: crate::StrInput
+#[derive(Clone, Debug)]
+pub(crate) struct ChunkedInput {
+    /// Decoded chunks that have not been entirely read yet.
+    chunks: VecDeque<Arc<str>>,
+    /// The position (in bytes) in the front chunk of the next character to read.
+    pos: usize,
+    /// Bytes at the end of the last chunk that are the start of a UTF-8 sequence.
+    partial: Vec<u8>,
+    /// The number of characters that were fed so far.
+    fed_chars: usize,
+    /// Whether no more bytes will be fed.
+    finished: bool,
+    /// Whether a character that has not been fed yet was requested.
+    starved: Cell<bool>,
+    /// The failure that stopped the input, if any.
+    failure: Option<InputFailure>,
+    /// Buffer for the next characters to consume.
+    buffer: ArrayDeque<char, BUFFER_LEN>,
+    /// The number of characters the scanner asked us to look ahead for.
+    ///
+    /// This may be more than the number of characters in [`Self::buffer`] if they have not been
+    /// fed yet.
+    lookahead: usize,
+    /// The number of bytes consumed so far.
+    byte_offset: usize,
+}
+
+impl ChunkedInput {
+    /// Create a new [`ChunkedInput`] with no bytes fed.
+    pub(crate) fn new() -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            pos: 0,
+            partial: Vec::new(),
+            fed_chars: 0,
+            finished: false,
+            starved: Cell::new(false),
+            failure: None,
+            buffer: ArrayDeque::default(),
+            lookahead: 0,
+            byte_offset: 0,
+        }
+    }
+
+    /// Append UTF-8 encoded bytes to the input.
+    ///
+    /// UTF-8 sequences may be split across calls. Bytes fed after the input was finished are
+    /// ignored.
+    pub(crate) fn feed(&mut self, bytes: &[u8]) {
+        if self.finished {
+            return;
+        }
+        let joined;
+        let bytes = if self.partial.is_empty() {
+            bytes
+        } else {
+            joined = [core::mem::take(&mut self.partial).as_slice(), bytes].concat();
+            joined.as_slice()
+        };
+
+        match core::str::from_utf8(bytes) {
+            Ok(text) => self.push_chunk(text),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                self.push_chunk(core::str::from_utf8(valid).unwrap());
+                if e.error_len().is_some() {
+                    self.fail(InputFailure::invalid_utf8(self.fed_chars, rest[0]));
+                } else {
+                    // The sequence may be completed by the next chunk.
+                    self.partial = rest.to_vec();
+                }
+            }
+        }
+    }
+
+    /// Mark the input as complete. No more bytes may be fed.
+    pub(crate) fn finish(&mut self) {
+        if let Some(&first) = self.partial.first() {
+            self.fail(InputFailure::invalid_utf8(self.fed_chars, first));
+        }
+        self.finished = true;
+    }
+
+    /// Stop the input because reading its source failed.
+    #[cfg(feature = "async")]
+    pub(crate) fn fail_read(&mut self, error: &std::io::Error) {
+        self.fail(InputFailure::read_failed(self.fed_chars, error));
+    }
+
+    /// Return whether no more bytes will be fed.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Return whether a character that has not been fed yet was requested.
+    pub(crate) fn is_starved(&self) -> bool {
+        self.starved.get()
+    }
+
+    /// Stop the input at its current end with the given failure.
+    fn fail(&mut self, failure: InputFailure) {
+        self.failure = Some(failure);
+        self.partial.clear();
+        self.finished = true;
+    }
+
+    /// Append a chunk of text to the input.
+    fn push_chunk(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.fed_chars += text.chars().count();
+        self.chunks.push_back(Arc::from(text));
+        self.fill_buffer();
+    }
+
+    /// Load characters into the buffer, up to the requested lookahead.
+    fn fill_buffer(&mut self) {
+        while self.buffer.len() < self.lookahead {
+            let Some(c) = self.next_char() else {
+                break;
+            };
+            self.buffer.push_back(c).unwrap();
+        }
+    }
+
+    /// Read the next character from the chunks.
+    ///
+    /// Returns `None` if all chunks have been read.
+    fn next_char(&mut self) -> Option<char> {
+        while let Some(chunk) = self.chunks.front() {
+            if let Some(c) = chunk[self.pos..].chars().next() {
+                self.pos += c.len_utf8();
+                return Some(c);
+            }
+            self.chunks.pop_front();
+            self.pos = 0;
+        }
+        None
+    }
+
+    /// Read the next character from the chunks for the scanner to use.
+    ///
+    /// Returns `None` if all chunks have been read, in which case the input is starved unless it
+    /// is finished.
+    fn read_char(&mut self) -> Option<char> {
+        let c = self.next_char();
+        if c.is_none() && !self.finished {
+            self.starved.set(true);
+        }
+        c
+    }
+}
+
+impl Input for ChunkedInput {
+    #[inline]
+    fn lookahead(&mut self, count: usize) {
+        if self.lookahead < count {
+            self.lookahead = count;
+            self.fill_buffer();
+        }
+    }
+
+    #[inline]
+    fn buflen(&self) -> usize {
+        self.lookahead
+    }
+
+    #[inline]
+    fn bufmaxlen(&self) -> usize {
+        BUFFER_LEN
+    }
+
+    #[inline]
+    fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    fn error_at(&self, mark: Marker) -> Option<ScanError> {
+        self.failure
+            .as_ref()
+            .and_then(|failure| failure.error_at(mark))
+    }
+
+    #[inline]
+    fn raw_read_ch(&mut self) -> char {
+        if let Some(c) = self.read_char() {
+            self.byte_offset += c.len_utf8();
+            c
+        } else {
+            '\0'
+        }
+    }
+
+    #[inline]
+    fn raw_read_non_breakz_ch(&mut self) -> Option<char> {
+        if let Some(c) = self.read_char() {
+            if is_breakz(c) {
+                self.buffer.push_back(c).unwrap();
+                self.lookahead = self.lookahead.max(1);
+                None
+            } else {
+                self.byte_offset += c.len_utf8();
+                Some(c)
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn skip(&mut self) {
+        if let Some(c) = self.buffer.pop_front() {
+            self.byte_offset += c.len_utf8();
+        }
+        self.lookahead = self.lookahead.saturating_sub(1);
+    }
+
+    #[inline]
+    fn skip_n(&mut self, count: usize) {
+        let loaded = count.min(self.buffer.len());
+        self.byte_offset += self
+            .buffer
+            .drain(0..loaded)
+            .map(char::len_utf8)
+            .sum::<usize>();
+        self.lookahead = self.lookahead.saturating_sub(count);
+    }
+
+    #[inline]
+    fn peek(&self) -> char {
+        self.peek_nth(0)
+    }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> char {
+        if let Some(&c) = self.buffer.get(n) {
+            c
+        } else {
+            if !self.finished {
+                self.starved.set(true);
+            }
+            '\0'
+        }
+    }
+}
+
+impl BorrowedInput<'_> for ChunkedInput {}
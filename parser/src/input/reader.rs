@@ -0,0 +1,234 @@
+use std::io::{ErrorKind, Read};
+
+use crate::char_traits::is_breakz;
+use crate::input::{buffered::BUFFER_LEN, BorrowedInput, Input, InputFailure};
+use crate::scanner::{Marker, ScanError};
+
+use arraydeque::ArrayDeque;
+
+/// The size of the buffer of bytes read from the [`Read`]er.
+const READ_BUFFER_LEN: usize = 8 * 1024;
+
+/// A parser input that decodes UTF-8 from a [`Read`]er.
+///
+/// Bytes are read into a fixed-size buffer and decoded as the scanner needs them, so that the
+/// memory used does not depend on the size of the input. Reads that are interrupted are retried.
+///
+/// If the input is not valid UTF-8 or if reading fails, the input behaves as if it ended there and
+/// the parser fails with a [`ScanError`] of kind [`ScanErrorKind::InvalidUtf8`] or
+/// [`ScanErrorKind::ReadFailed`], marked at the position of the failure.
+///
+/// [`ScanErrorKind::InvalidUtf8`]: crate::ScanErrorKind::InvalidUtf8
+/// [`ScanErrorKind::ReadFailed`]: crate::ScanErrorKind::ReadFailed
+///
+/// ```
+/// # use saphyr_parser::{Event, Parser, ScanErrorKind};
+/// let input: &[u8] = b"key: value";
+/// let events = Parser::new_from_reader(input).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(events.len(), 8);
+///
+/// let input: &[u8] = b"key: \xFF";
+/// let error = Parser::new_from_reader(input).find_map(Result::err).unwrap();
+/// assert_eq!(error.kind(), ScanErrorKind::InvalidUtf8);
+/// assert_eq!(error.marker().byte_offset(), 5);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct ReaderInput<R: Read> {
+    /// The reader source.
+    reader: R,
+    /// Bytes read from the reader. Only `bytes[start..end]` have yet to be decoded.
+    bytes: Box<[u8]>,
+    /// The index of the first byte in [`Self::bytes`] that has yet to be decoded.
+    start: usize,
+    /// The index past the last byte in [`Self::bytes`] that was read.
+    end: usize,
+    /// Whether the reader is exhausted.
+    eof: bool,
+    /// Buffer for the next characters to consume.
+    buffer: ArrayDeque<char, BUFFER_LEN>,
+    /// The number of bytes consumed so far.
+    byte_offset: usize,
+    /// The number of characters decoded so far.
+    decoded: usize,
+    /// The failure that stopped the input, if any.
+    failure: Option<InputFailure>,
+}
+
+impl<R: Read> ReaderInput<R> {
+    /// Create a new [`ReaderInput`] with the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            bytes: vec![0; READ_BUFFER_LEN].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            eof: false,
+            buffer: ArrayDeque::default(),
+            byte_offset: 0,
+            decoded: 0,
+            failure: None,
+        }
+    }
+
+    /// Decode the next character from the reader.
+    ///
+    /// Returns `None` if the reader is exhausted or if the input failed.
+    fn decode(&mut self) -> Option<char> {
+        if self.failure.is_some() || !self.fill(1) {
+            return None;
+        }
+        let first = self.bytes[self.start];
+        if first.is_ascii() {
+            self.start += 1;
+            self.decoded += 1;
+            return Some(char::from(first));
+        }
+
+        let width = utf8_width(first);
+        if width == 0 || !self.fill(width) {
+            return self.fail_decoding(first);
+        }
+        match std::str::from_utf8(&self.bytes[self.start..self.start + width]) {
+            Ok(s) => {
+                self.start += width;
+                self.decoded += 1;
+                s.chars().next()
+            }
+            Err(_) => self.fail_decoding(first),
+        }
+    }
+
+    /// Make sure at least `count` bytes are available for decoding.
+    ///
+    /// Returns `false` if the reader was exhausted or failed before.
+    fn fill(&mut self, count: usize) -> bool {
+        if self.end - self.start >= count {
+            return true;
+        }
+        self.bytes.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        while self.end < count {
+            if self.eof || self.failure.is_some() {
+                return false;
+            }
+            match self.reader.read(&mut self.bytes[self.end..]) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.end += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => self.failure = Some(InputFailure::read_failed(self.decoded, &e)),
+            }
+        }
+        true
+    }
+
+    /// Record that the bytes starting with `first` are not valid UTF-8.
+    ///
+    /// This always returns `None`, for convenience.
+    fn fail_decoding(&mut self, first: u8) -> Option<char> {
+        // Reading may have failed while fetching the rest of the sequence. Report that instead.
+        if self.failure.is_none() {
+            self.failure = Some(InputFailure::invalid_utf8(self.decoded, first));
+        }
+        None
+    }
+}
+
+impl<R: Read> Input for ReaderInput<R> {
+    #[inline]
+    fn lookahead(&mut self, count: usize) {
+        if self.buffer.len() >= count {
+            return;
+        }
+        for _ in 0..(count - self.buffer.len()) {
+            let c = self.decode().unwrap_or('\0');
+            self.buffer.push_back(c).unwrap();
+        }
+    }
+
+    #[inline]
+    fn buflen(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[inline]
+    fn bufmaxlen(&self) -> usize {
+        BUFFER_LEN
+    }
+
+    #[inline]
+    fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    fn error_at(&self, mark: Marker) -> Option<ScanError> {
+        self.failure
+            .as_ref()
+            .and_then(|failure| failure.error_at(mark))
+    }
+
+    #[inline]
+    fn raw_read_ch(&mut self) -> char {
+        if let Some(c) = self.decode() {
+            self.byte_offset += c.len_utf8();
+            c
+        } else {
+            '\0'
+        }
+    }
+
+    #[inline]
+    fn raw_read_non_breakz_ch(&mut self) -> Option<char> {
+        if let Some(c) = self.decode() {
+            if is_breakz(c) {
+                self.buffer.push_back(c).unwrap();
+                None
+            } else {
+                self.byte_offset += c.len_utf8();
+                Some(c)
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn skip(&mut self) {
+        if let Some(c) = self.buffer.pop_front() {
+            self.byte_offset += c.len_utf8();
+        }
+    }
+
+    #[inline]
+    fn skip_n(&mut self, count: usize) {
+        self.byte_offset += self
+            .buffer
+            .drain(0..count)
+            .map(char::len_utf8)
+            .sum::<usize>();
+    }
+
+    #[inline]
+    fn peek(&self) -> char {
+        self.buffer[0]
+    }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> char {
+        self.buffer[n]
+    }
+}
+
+impl<R: Read> BorrowedInput<'_> for ReaderInput<R> {}
+
+/// Return the length of the UTF-8 sequence starting with `first`, or 0 if `first` cannot start a
+/// sequence.
+fn utf8_width(first: u8) -> usize {
+    match first {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 0,
+    }
+}
@@ -1,14 +1,22 @@
+use alloc::string::String;
+
 use crate::{
     char_traits::{
         is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_z,
     },
-    input::{Input, SkipTabs},
+    input::{BorrowedInput, Input, SkipTabs},
 };
 
+use memchr::{memchr, memchr2, memchr3};
+
 /// A parser input that uses a `&str` as source.
 #[allow(clippy::module_name_repetitions)]
 pub struct StrInput<'a> {
+    /// The whole input, used to hand out borrowed slices.
+    original: &'a str,
     /// The input str buffer.
+    ///
+    /// This is the part of [`Self::original`] that has not been consumed yet.
     buffer: &'a str,
     /// The number of characters we have looked ahead.
     ///
@@ -22,12 +30,92 @@ impl<'a> StrInput<'a> {
     #[must_use]
     pub fn new(input: &'a str) -> Self {
         Self {
+            original: input,
             buffer: input,
             lookahead: 0,
         }
     }
 }
 
+impl StrInput<'_> {
+    /// Consume the next `len` bytes of the buffer and return the number of characters they hold.
+    ///
+    /// `len` must be at a character boundary.
+    #[inline]
+    fn skip_bytes(&mut self, len: usize) -> usize {
+        let (skipped, rest) = self.buffer.split_at(len);
+        self.buffer = rest;
+        skipped.chars().count()
+    }
+
+    /// Return the length, in bytes, of the characters at the start of the buffer that can continue
+    /// a plain scalar.
+    ///
+    /// See [`Input::skip_while_plain_scalar_chars`].
+    #[inline]
+    fn plain_scalar_len(&self, in_flow: bool) -> usize {
+        let bytes = self.buffer.as_bytes();
+        let stops = if in_flow {
+            &FLOW_PLAIN_STOPS
+        } else {
+            &BLOCK_PLAIN_STOPS
+        };
+        let mut len = 0;
+        while let Some(idx) = stops.find(&bytes[len..]) {
+            let pos = len + idx;
+            match bytes[pos] {
+                // A `:` only ends the scalar if it is followed by a blank, a breakz or, in a flow
+                // context, a flow indicator.
+                b':' => {
+                    let nc = bytes.get(pos + 1).map_or('\0', |&nc| nc as char);
+                    if is_blank_or_breakz(nc) || (in_flow && is_flow(nc)) {
+                        return pos;
+                    }
+                    len = pos + 1;
+                }
+                // Blanks are part of the scalar if more of it follows on the same line.
+                b' ' | b'\t' => {
+                    let next = skip_blanks(bytes, pos);
+                    if !can_start_plain_scalar_word(&bytes[next..], in_flow) {
+                        return pos;
+                    }
+                    len = next;
+                }
+                _ => return pos,
+            }
+        }
+        bytes.len()
+    }
+
+    /// Return the length, in bytes, of the characters at the start of the buffer that need no
+    /// processing in a quoted scalar.
+    ///
+    /// See [`Input::skip_while_quoted_scalar_chars`].
+    #[inline]
+    fn quoted_scalar_len(&self, single: bool) -> usize {
+        let bytes = self.buffer.as_bytes();
+        let stops = if single {
+            &SINGLE_QUOTED_STOPS
+        } else {
+            &DOUBLE_QUOTED_STOPS
+        };
+        let mut len = 0;
+        while let Some(idx) = stops.find(&bytes[len..]) {
+            let pos = len + idx;
+            if !is_blank(bytes[pos] as char) {
+                return pos;
+            }
+            // Blanks are part of the scalar if more of it follows on the same line.
+            let next = skip_blanks(bytes, pos);
+            if bytes.get(next).map_or(true, |&c| is_breakz(c as char)) {
+                return pos;
+            }
+            len = next;
+        }
+        bytes.len()
+    }
+}
+
 impl Input for StrInput<'_> {
     #[inline]
     fn lookahead(&mut self, x: usize) {
@@ -47,6 +135,11 @@ impl Input for StrInput<'_> {
         BUFFER_LEN
     }
 
+    #[inline]
+    fn byte_offset(&self) -> usize {
+        self.original.len() - self.buffer.len()
+    }
+
     fn buf_is_empty(&self) -> bool {
         self.buflen() == 0
     }
@@ -224,13 +317,9 @@ impl Input for StrInput<'_> {
             }
 
             // Skip remaining characters until we hit a breakz.
-            while let Some((c, sub_str)) = split_first_char(new_str) {
-                if is_breakz(c) {
-                    break;
-                }
-                new_str = sub_str;
-                chars_consumed += 1;
-            }
+            let len = memchr3(b'\n', b'\r', b'\0', new_str.as_bytes()).unwrap_or(new_str.len());
+            chars_consumed += new_str[..len].chars().count();
+            new_str = &new_str[len..];
         }
 
         self.buffer = new_str;
@@ -312,22 +401,11 @@ impl Input for StrInput<'_> {
         !self.buffer.is_empty() && is_alpha(self.buffer.as_bytes()[0] as char)
     }
 
+    #[inline]
     fn skip_while_non_breakz(&mut self) -> usize {
-        let mut new_str = self.buffer;
-        let mut count = 0;
-
-        // Skip over all non-breaks.
-        while let Some((c, sub_str)) = split_first_char(new_str) {
-            if is_breakz(c) {
-                break;
-            }
-            new_str = sub_str;
-            count += 1;
-        }
-
-        self.buffer = new_str;
-
-        count
+        let bytes = self.buffer.as_bytes();
+        let len = memchr3(b'\n', b'\r', b'\0', bytes).unwrap_or(bytes.len());
+        self.skip_bytes(len)
     }
 
     fn skip_while_blank(&mut self) -> usize {
@@ -343,6 +421,17 @@ impl Input for StrInput<'_> {
         i
     }
 
+    fn skip_while_is_alpha(&mut self) -> usize {
+        // All alpha characters are ascii, we can directly use the byte API of str.
+        let n_bytes = self
+            .buffer
+            .bytes()
+            .position(|c| !is_alpha(c as char))
+            .unwrap_or(self.buffer.len());
+        self.buffer = &self.buffer[n_bytes..];
+        n_bytes
+    }
+
     fn fetch_while_is_alpha(&mut self, out: &mut String) -> usize {
         let mut not_alpha = None;
 
@@ -370,6 +459,39 @@ impl Input for StrInput<'_> {
 
         n_bytes_to_append
     }
+
+    #[inline]
+    fn skip_while_plain_scalar_chars(&mut self, in_flow: bool) -> usize {
+        let len = self.plain_scalar_len(in_flow);
+        self.skip_bytes(len)
+    }
+
+    #[inline]
+    fn fetch_while_plain_scalar_chars(&mut self, in_flow: bool, out: &mut String) -> usize {
+        let len = self.plain_scalar_len(in_flow);
+        out.push_str(&self.buffer[..len]);
+        self.skip_bytes(len)
+    }
+
+    #[inline]
+    fn skip_while_quoted_scalar_chars(&mut self, single: bool) -> usize {
+        let len = self.quoted_scalar_len(single);
+        self.skip_bytes(len)
+    }
+
+    #[inline]
+    fn fetch_while_quoted_scalar_chars(&mut self, single: bool, out: &mut String) -> usize {
+        let len = self.quoted_scalar_len(single);
+        out.push_str(&self.buffer[..len]);
+        self.skip_bytes(len)
+    }
+}
+
+impl<'a> BorrowedInput<'a> for StrInput<'a> {
+    #[inline]
+    fn slice_bytes(&self, start: usize, end: usize) -> Option<&'a str> {
+        self.original.get(start..end)
+    }
 }
 
 /// The buffer size we return to the scanner.
@@ -396,6 +518,111 @@ impl Input for StrInput<'_> {
 /// [`buflen`]: `StrInput::buflen`
 const BUFFER_LEN: usize = 128;
 
+/// Return the position of the first byte of `bytes` from `pos` onwards that is not a blank.
+#[inline]
+fn skip_blanks(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|&b| !is_blank(b as char))
+        .map_or(bytes.len(), |idx| pos + idx)
+}
+
+/// Check whether `bytes` start with characters that continue a plain scalar after blanks.
+///
+/// This mirrors the checks [`Scanner`] makes before each run of non-blank characters of a plain
+/// scalar.
+///
+/// [`Scanner`]: crate::Scanner
+#[inline]
+fn can_start_plain_scalar_word(bytes: &[u8], in_flow: bool) -> bool {
+    let Some(&c) = bytes.first() else {
+        return false;
+    };
+    let nc = bytes.get(1).map_or('\0', |&nc| nc as char);
+    match c {
+        b'#' | b'\n' | b'\r' | b'\0' => false,
+        b':' => !(is_blank_or_breakz(nc) || (in_flow && is_flow(nc))),
+        b'-' if in_flow => !is_flow(nc),
+        c => !(in_flow && is_flow(c as char)),
+    }
+}
+
+/// The number of bytes [`ByteSet::find`] checks one at a time before searching with `memchr`.
+///
+/// Most scalars and words are short, and `memchr` only pays off on longer stretches of text.
+const SHORT_SCAN: usize = 16;
+
+/// The number of bytes [`ByteSet::find`] searches at once with `memchr`.
+const SCAN_WINDOW: usize = 64;
+
+/// A set of ASCII bytes to search for.
+struct ByteSet {
+    /// The bytes of the set.
+    needles: &'static [u8],
+    /// Whether each byte is in the set.
+    table: [bool; 256],
+}
+
+impl ByteSet {
+    /// Create a set of the given bytes.
+    const fn new(needles: &'static [u8]) -> Self {
+        let mut table = [false; 256];
+        let mut i = 0;
+        while i < needles.len() {
+            table[needles[i] as usize] = true;
+            i += 1;
+        }
+        Self { needles, table }
+    }
+
+    /// Return the position of the first byte of `haystack` that is in the set.
+    ///
+    /// `memchr` looks for at most 3 bytes at once. Past the first [`SHORT_SCAN`] bytes, the
+    /// haystack is thus searched one window at a time, for 3 needles after the other, each search
+    /// stopping at the match of the previous ones. Were the whole haystack searched for the first
+    /// needles, scanning many short scalars would be quadratic.
+    #[inline]
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let short = haystack.len().min(SHORT_SCAN);
+        if let Some(idx) = haystack[..short]
+            .iter()
+            .position(|&b| self.table[b as usize])
+        {
+            return Some(idx);
+        }
+        let mut start = short;
+        while start < haystack.len() {
+            let window = &haystack[start..haystack.len().min(start + SCAN_WINDOW)];
+            let mut end = window.len();
+            for group in self.needles.chunks(3) {
+                let found = match *group {
+                    [a] => memchr(a, &window[..end]),
+                    [a, b] => memchr2(a, b, &window[..end]),
+                    [a, b, c] => memchr3(a, b, c, &window[..end]),
+                    _ => unreachable!(),
+                };
+                if let Some(idx) = found {
+                    end = idx;
+                }
+            }
+            if end < window.len() {
+                return Some(start + end);
+            }
+            start += window.len();
+        }
+        None
+    }
+}
+
+/// Bytes that may end a plain scalar in a block context.
+static BLOCK_PLAIN_STOPS: ByteSet = ByteSet::new(b" \t\n\r\0:");
+/// Bytes that may end a plain scalar in a flow context.
+static FLOW_PLAIN_STOPS: ByteSet = ByteSet::new(b" \t\n\r\0:,[]{}");
+/// Bytes that need processing in a single-quoted scalar.
+static SINGLE_QUOTED_STOPS: ByteSet = ByteSet::new(b" \t\n\r\0'");
+/// Bytes that need processing in a double-quoted scalar.
+static DOUBLE_QUOTED_STOPS: ByteSet = ByteSet::new(b" \t\n\r\0\"\\");
+
 /// Splits the first character of the given string and returns it along with the rest of the
 /// string.
 #[inline]
@@ -407,10 +634,87 @@ fn split_first_char(s: &str) -> Option<(char, &str)> {
 
 #[cfg(test)]
 mod test {
-    use crate::input::Input;
+    use crate::input::{BufferedInput, Input};
 
     use super::StrInput;
 
+    /// Check where the fast paths of [`StrInput`] stop.
+    #[test]
+    pub fn bulk_scans() {
+        let plain = |text: &str, in_flow: bool| {
+            let mut input = StrInput::new(text);
+            let mut out = String::new();
+            let n_chars = input.fetch_while_plain_scalar_chars(in_flow, &mut out);
+            assert_eq!(n_chars, out.chars().count());
+            assert_eq!(input.byte_offset(), out.len());
+            out
+        };
+        assert_eq!(plain("a:b:c, d", false), "a:b:c, d");
+        assert_eq!(plain("a:b:c, d", true), "a:b:c");
+        assert_eq!(plain("key: value", false), "key");
+        assert_eq!(plain("tail:", false), "tail");
+        assert_eq!(plain("two words  # comment", false), "two words");
+        assert_eq!(plain("trailing \t\nnext", false), "trailing");
+        assert_eq!(plain("a -] b", true), "a");
+        assert_eq!(plain("a -b]", true), "a -b");
+        assert_eq!(plain("a :b, c", true), "a :b");
+        assert_eq!(plain("é ü:x\0after", false), "é ü:x");
+
+        let quoted = |text: &str, single: bool| {
+            let mut input = StrInput::new(text);
+            let mut out = String::new();
+            let n_chars = input.fetch_while_quoted_scalar_chars(single, &mut out);
+            assert_eq!(n_chars, out.chars().count());
+            assert_eq!(input.byte_offset(), out.len());
+            out
+        };
+        assert_eq!(quoted("it's \"quoted\" \\ here", true), "it");
+        assert_eq!(quoted("it's \"quoted\" \\ here", false), "it's ");
+        assert_eq!(quoted("a b \t\nc", false), "a b");
+        assert_eq!(quoted("a b '", true), "a b ");
+
+        let mut input = StrInput::new("comment é\r\nnext");
+        assert_eq!(input.skip_while_non_breakz(), 9);
+        assert_eq!(input.peek(), '\r');
+    }
+
+    /// Check that the fast paths of [`StrInput`] stop where the defaults of [`Input`] do, blanks
+    /// aside.
+    #[test]
+    pub fn bulk_scans_match_defaults() {
+        let long = "é".repeat(100);
+        let inputs = [
+            "plain",
+            "key:\nvalue",
+            "a:b:c,d",
+            "url:http://x]y",
+            "tail:",
+            "it's\"quoted\"\\here",
+            "comment\r\nnext",
+            "nul\0after",
+            &format!("{long}:{long}:"),
+            &format!("[{long},{long}]"),
+        ];
+        for text in inputs {
+            let check = |scan: &dyn Fn(&mut dyn Input, &mut String) -> usize| {
+                let mut str_input = StrInput::new(text);
+                let mut buffered = BufferedInput::new(text.chars());
+                let (mut str_out, mut buffered_out) = (String::new(), String::new());
+                let n_chars = scan(&mut str_input, &mut str_out);
+                assert_eq!(n_chars, scan(&mut buffered, &mut buffered_out), "{text:?}");
+                assert_eq!(str_out, buffered_out, "{text:?}");
+                assert_eq!(str_input.byte_offset(), buffered.byte_offset(), "{text:?}");
+            };
+            for flag in [false, true] {
+                check(&|input, out| input.fetch_while_plain_scalar_chars(flag, out));
+                check(&|input, _| input.skip_while_plain_scalar_chars(flag));
+                check(&|input, out| input.fetch_while_quoted_scalar_chars(flag, out));
+                check(&|input, _| input.skip_while_quoted_scalar_chars(flag));
+            }
+            check(&|input, _| input.skip_while_non_breakz());
+        }
+    }
+
     #[test]
     pub fn is_document_start() {
         let input = StrInput::new("---\n");
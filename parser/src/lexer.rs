@@ -0,0 +1,245 @@
+//! A lossless lexer, for tools that need every byte of the input (e.g. syntax highlighters and
+//! formatters).
+//!
+//! The [`Scanner`] skips over whitespace, comments and line breaks, and the spans of its tokens
+//! are those the [`Parser`] needs, not those of their text. [`Lexer`] runs a scanner and puts the
+//! text back together: each token is given the exact range of text it was scanned from, and the
+//! text in between tokens is reported as trivia.
+//!
+//! [`Parser`]: crate::Parser
+
+use alloc::collections::VecDeque;
+
+use crate::{
+    scanner::{Scanner, Token, TokenType},
+    Marker, ScanError, Span, StrInput, TScalarStyle,
+};
+
+/// What a [`Lexeme`] is.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub enum LexemeKind<'input> {
+    /// A token of the YAML syntax.
+    ///
+    /// Indicators (`-`, `?`, `:`, `,`, `[`, `]`, `{`, `}`) span that single character. Tokens the [`Scanner`] infers
+    /// from indentation or from a following `:` ([`TokenType::BlockSequenceStart`],
+    /// [`TokenType::BlockMappingStart`], [`TokenType::BlockEnd`] and [`TokenType::Key`] for
+    /// implicit keys), as well as [`TokenType::StreamStart`] and [`TokenType::StreamEnd`], have
+    /// no text. Block scalars span from their header to their last line, including a comment
+    /// that may follow the header.
+    ///
+    /// Comments are reported as [`LexemeKind::Comment`], never as [`TokenType::Comment`].
+    Token(TokenType<'input>),
+    /// A run of spaces and tabs.
+    Whitespace,
+    /// A line break (`\n`, `\r\n` or `\r`).
+    LineBreak,
+    /// A comment, from its `#` to the end of its line, excluding the line break.
+    Comment,
+    /// A byte order mark at the start of the input.
+    Bom,
+}
+
+impl LexemeKind<'_> {
+    /// Return whether the lexeme is trivia, i.e. not a token of the YAML syntax.
+    #[must_use]
+    pub fn is_trivia(&self) -> bool {
+        !matches!(self, LexemeKind::Token(_))
+    }
+}
+
+/// A piece of the input, as returned by [`Lexer`].
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct Lexeme<'input> {
+    /// What the lexeme is.
+    pub kind: LexemeKind<'input>,
+    /// The span of the text of the lexeme.
+    pub span: Span,
+    /// The text of the lexeme.
+    pub text: &'input str,
+}
+
+/// A lexer that reports every byte of its input.
+///
+/// The lexer yields the tokens of the [`Scanner`] along with the trivia in between them
+/// (whitespace, line breaks, comments and a byte order mark). Lexemes are yielded in the order of
+/// the input and their texts, concatenated, are the input.
+///
+/// If the input is invalid, the lexer yields the lexemes it could scan, then the error, then
+/// nothing.
+///
+/// ```
+/// # use saphyr_parser::{Lexer, LexemeKind, TokenType};
+/// let input = "key: value # comment\n";
+/// let lexemes = Lexer::new(input).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(lexemes.iter().map(|lexeme| lexeme.text).collect::<String>(), input);
+/// assert!(lexemes
+///     .iter()
+///     .any(|lexeme| lexeme.kind == LexemeKind::Comment && lexeme.text == "# comment"));
+/// assert!(lexemes
+///     .iter()
+///     .any(|lexeme| lexeme.kind == LexemeKind::Token(TokenType::Value) && lexeme.text == ":"));
+/// ```
+pub struct Lexer<'input> {
+    /// The input.
+    source: &'input str,
+    /// The scanner of the input.
+    scanner: Scanner<'input, StrInput<'input>>,
+    /// The position up to which the input has been lexed.
+    cursor: Marker,
+    /// Lexemes that were lexed but not yet returned.
+    pending: VecDeque<Lexeme<'input>>,
+    /// Whether the scanner has no more tokens.
+    done: bool,
+}
+
+impl<'input> Lexer<'input> {
+    /// Create a lexer for the given input.
+    #[must_use]
+    pub fn new(source: &'input str) -> Self {
+        Self {
+            source,
+            scanner: Scanner::new(StrInput::new(source)),
+            cursor: Marker::new(0, 1, 0),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Lex the text of a token and the trivia before it.
+    fn push_token(&mut self, Token(span, token): Token<'input>) {
+        let mut range = span.byte_range();
+        let bytes = self.source.as_bytes();
+        match &token {
+            // The scanner places block entries after the `-` and the trivia that follows it.
+            TokenType::BlockEntry => {
+                let start = self.skip_trivia(range.start);
+                if bytes.get(start) == Some(&b'-') {
+                    range = start..start + 1;
+                }
+            }
+            TokenType::Key | TokenType::Value => {
+                let indicator = if token == TokenType::Key { b'?' } else { b':' };
+                range.end = if bytes.get(range.start) == Some(&indicator) {
+                    range.start + 1
+                } else {
+                    range.start
+                };
+            }
+            TokenType::Scalar(TScalarStyle::Literal | TScalarStyle::Folded, _) => {
+                // The scanner starts block scalars at their content. Their header is the first
+                // token after the cursor.
+                range.start = self.skip_trivia(range.start);
+                // Blanks after the last line break are the indentation of the next line.
+                let text = &self.source[range.clone()];
+                let trimmed = text.trim_end_matches([' ', '\t']);
+                if trimmed.ends_with(['\n', '\r']) {
+                    range.end = range.start + trimmed.len();
+                }
+            }
+            // Reserved directives are skipped up to the end of their line, comment included.
+            TokenType::TagDirective(handle, _) if handle.is_empty() => {
+                let text = &self.source[range.clone()];
+                let end = text
+                    .match_indices('#')
+                    .map(|(idx, _)| idx)
+                    .find(|&idx| text[..idx].ends_with([' ', '\t']))
+                    .unwrap_or(text.len());
+                range.end = range.start + text[..end].trim_end_matches([' ', '\t']).len();
+            }
+            _ => {}
+        }
+
+        let start = range.start.max(self.cursor.byte_offset());
+        let end = range.end.max(start);
+        self.push_trivia(start);
+        self.push(LexemeKind::Token(token), end);
+    }
+
+    /// Return the byte offset of the first character after the cursor that is not trivia, or `end`
+    /// if there is none before it.
+    fn skip_trivia(&self, end: usize) -> usize {
+        let mut rest = &self.source[self.cursor.byte_offset()..end];
+        loop {
+            let trimmed = rest.trim_start_matches(['\u{FEFF}', ' ', '\t', '\n', '\r']);
+            rest = match trimmed.strip_prefix('#') {
+                Some(comment) => comment.trim_start_matches(|c| !matches!(c, '\n' | '\r')),
+                None => return end - trimmed.len(),
+            };
+        }
+    }
+
+    /// Lex the trivia from the cursor up to the given byte offset.
+    fn push_trivia(&mut self, end: usize) {
+        while self.cursor.byte_offset() < end {
+            let rest = &self.source[self.cursor.byte_offset()..end];
+            let (kind, len) = if rest.starts_with('\u{FEFF}') {
+                (LexemeKind::Bom, '\u{FEFF}'.len_utf8())
+            } else if rest.starts_with("\r\n") {
+                (LexemeKind::LineBreak, 2)
+            } else if rest.starts_with(['\n', '\r']) {
+                (LexemeKind::LineBreak, 1)
+            } else if rest.starts_with('#') {
+                (
+                    LexemeKind::Comment,
+                    rest.find(['\n', '\r']).unwrap_or(rest.len()),
+                )
+            } else {
+                // The scanner only skips over trivia. Should it have skipped anything else, it is
+                // reported as whitespace rather than lost.
+                let blanks = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+                let first = rest.chars().next().map_or(1, char::len_utf8);
+                (LexemeKind::Whitespace, blanks.max(first))
+            };
+            self.push(kind, self.cursor.byte_offset() + len);
+        }
+    }
+
+    /// Push a lexeme from the cursor up to the given byte offset and move the cursor there.
+    fn push(&mut self, kind: LexemeKind<'input>, end: usize) {
+        let start = self.cursor;
+        let text = &self.source[start.byte_offset()..end];
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let (index, line, col) = (
+                self.cursor.index() + 1,
+                self.cursor.line(),
+                self.cursor.col(),
+            );
+            let (line, col) = match c {
+                '\r' if chars.peek() == Some(&'\n') => (line, col + 1),
+                '\n' | '\r' => (line + 1, 0),
+                // The byte order mark does not count as a column.
+                '\u{FEFF}' if index == 1 => (line, col),
+                _ => (line, col + 1),
+            };
+            self.cursor = Marker::new(index, line, col)
+                .with_byte_offset(self.cursor.byte_offset() + c.len_utf8());
+        }
+        self.pending.push_back(Lexeme {
+            kind,
+            span: Span::new(start, self.cursor),
+            text,
+        });
+    }
+}
+
+impl<'input> Iterator for Lexer<'input> {
+    type Item = Result<Lexeme<'input>, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.done {
+            match self.scanner.next_token() {
+                Ok(Some(token)) => self.push_token(token),
+                Ok(None) => {
+                    self.done = true;
+                    self.push_trivia(self.source.len());
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
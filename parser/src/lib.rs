@@ -23,22 +23,59 @@
 //! # Features
 //! **Note:** With all features disabled, this crate's MSRV is `1.65.0`.
 //!
+//! #### `std`
+//! Enabled by default. Enables [`ReaderInput`] and `Parser::new_from_reader`, and implements
+//! `std::error::Error` for the error types. Without it, the crate is `#![no_std]` and only needs
+//! an allocator (`alloc`).
+//!
 //! #### `debug_prints`
 //! Enables the `debug` module and usage of debug prints in the scanner and the parser. Do not
 //! enable if you are consuming the crate rather than working on it as this can significantly
-//! decrease performance.
+//! decrease performance. Implies `std`.
 //!
 //! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `async`
+//! Enables [`AsyncParser`], which parses from a [`futures_io::AsyncRead`]er and awaits more
+//! input rather than blocking. Readers implementing `tokio`'s `AsyncRead` can be adapted with
+//! `tokio-util`'s `compat` module. Implies `std`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs, clippy::pedantic)]
 
+extern crate alloc;
+
+#[cfg(feature = "async")]
+mod async_parser;
 mod char_traits;
+pub mod cst;
 #[macro_use]
 mod debug;
+mod diagnostic;
+pub mod event_notation;
+mod incremental;
 pub mod input;
+mod lexer;
 mod parser;
+pub mod path;
+pub mod receiver;
 mod scanner;
+mod warning;
 
-pub use crate::input::{str::StrInput, BufferedInput, Input};
-pub use crate::parser::{Event, EventReceiver, Parser, SpannedEventReceiver, Tag};
-pub use crate::scanner::{Marker, ScanError, Span, TScalarStyle};
+#[cfg(feature = "async")]
+pub use crate::async_parser::AsyncParser;
+pub use crate::diagnostic::{Diagnostic, RenderStyle};
+pub use crate::incremental::{IncrementalParser, Progress};
+#[cfg(feature = "std")]
+pub use crate::input::ReaderInput;
+pub use crate::input::{str::StrInput, BorrowedInput, BufferedInput, Input};
+pub use crate::lexer::{Lexeme, LexemeKind, Lexer};
+pub use crate::parser::{
+    Directives, DocumentInfo, Documents, Event, EventReceiver, Parser, ParserLimits,
+    SpannedEventReceiver, Tag,
+};
+pub use crate::scanner::{
+    Marker, ScanError, ScanErrorKind, ScanResult, Scanner, Span, TEncoding, TScalarStyle, Token,
+    TokenType,
+};
+pub use crate::warning::{Warning, WarningKind, MAX_BUFFERED_WARNINGS};
@@ -4,13 +4,22 @@
 //! compliance, and emits a stream of YAML events. This stream can for instance be used to create
 //! YAML objects.
 
+#[cfg(feature = "std")]
+use crate::ReaderInput;
 use crate::{
-    input::{str::StrInput, Input},
-    scanner::{ScanError, Scanner, Span, TScalarStyle, Token, TokenType},
+    input::{str::StrInput, BorrowedInput},
+    scanner::{ScanError, ScanErrorKind, Scanner, Span, TScalarStyle, Token, TokenType},
+    warning::{push_warning, Warning, WarningKind},
     BufferedInput, Marker,
 };
 
-use std::collections::HashMap;
+use alloc::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    format,
+    string::String,
+    vec::Vec,
+};
 
 #[derive(Clone, Copy, PartialEq, Debug, Eq)]
 enum State {
@@ -42,8 +51,12 @@ enum State {
 ///
 /// Events are used in the low-level event-based API (push parser). The API entrypoint is the
 /// [`EventReceiver`] trait.
+///
+/// Strings in events borrow from the input (`'input`) whenever possible. This is the case when
+/// parsing from a [`StrInput`] and the contents appear verbatim in the source (i.e. no escape
+/// sequence or line folding had to be processed). Otherwise, they are owned.
 #[derive(Clone, PartialEq, Debug, Eq)]
-pub enum Event {
+pub enum Event<'input> {
     /// Reserved for internal use.
     Nothing,
     /// Event generated at the very beginning of parsing.
@@ -51,13 +64,18 @@ pub enum Event {
     /// Last event that will be generated by the parser. Signals EOF.
     StreamEnd,
     /// The start of a YAML document.
-    ///
-    /// When the boolean is `true`, it is an explicit document start
-    /// directive (`---`).
-    ///
-    /// When the boolean is `false`, it is an implicit document start
-    /// (without `---`).
-    DocumentStart(bool),
+    DocumentStart(
+        /// Whether the document start is explicit.
+        ///
+        /// When the boolean is `true`, it is an explicit document start
+        /// directive (`---`).
+        ///
+        /// When the boolean is `false`, it is an implicit document start
+        /// (without `---`).
+        bool,
+        /// The directives (`%YAML`, `%TAG`) that precede the document.
+        Directives<'input>,
+    ),
     /// The YAML end document directive (`...`).
     DocumentEnd,
     /// A YAML Alias.
@@ -66,13 +84,13 @@ pub enum Event {
         usize,
     ),
     /// Value, style, `anchor_id`, tag
-    Scalar(String, TScalarStyle, usize, Option<Tag>),
+    Scalar(Cow<'input, str>, TScalarStyle, usize, Option<Tag<'input>>),
     /// The start of a YAML sequence (array).
     SequenceStart(
         /// The anchor ID of the start of the sequence.
         usize,
         /// An optional tag
-        Option<Tag>,
+        Option<Tag<'input>>,
     ),
     /// The end of a YAML sequence (array).
     SequenceEnd,
@@ -81,39 +99,236 @@ pub enum Event {
         /// The anchor ID of the start of the mapping.
         usize,
         /// An optional tag
-        Option<Tag>,
+        Option<Tag<'input>>,
     ),
     /// The end of a YAML mapping (object, hash).
     MappingEnd,
+    /// A YAML comment.
+    ///
+    /// Comments are only emitted if the parser was told to keep them (see
+    /// [`Parser::keep_comments`]).
+    Comment(
+        /// The text of the comment, without the leading `#`.
+        Cow<'input, str>,
+        /// Whether the comment follows other content on the same line.
+        ///
+        /// This is `true` for `a: b # comment` and `false` for a comment on its own line.
+        bool,
+    ),
+}
+
+/// The directives preceding a YAML document.
+///
+/// ```yaml
+/// %YAML 1.2
+/// %TAG !e! tag:example.com,2000:
+/// ---
+/// ```
+/// Only explicit documents (starting with `---`) may have directives. For implicit documents, the
+/// directives are always empty.
+#[derive(Clone, PartialEq, Debug, Eq, Default)]
+pub struct Directives<'input> {
+    /// The version given by the `%YAML` directive, as a `(major, minor)` tuple.
+    pub version: Option<(u32, u32)>,
+    /// The `%TAG` directives, as `(handle, prefix)` pairs in the order they appear in.
+    pub tags: Vec<(Cow<'input, str>, Cow<'input, str>)>,
+}
+
+impl Directives<'_> {
+    /// Return a version of the directives that owns its data.
+    #[must_use]
+    pub fn into_owned(self) -> Directives<'static> {
+        Directives {
+            version: self.version,
+            tags: self
+                .tags
+                .into_iter()
+                .map(|(handle, prefix)| {
+                    (
+                        Cow::Owned(handle.into_owned()),
+                        Cow::Owned(prefix.into_owned()),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A YAML document of a stream, as skipped over by [`Parser::next_document`].
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct DocumentInfo<'input> {
+    /// The span of the document, from its start to its end.
+    ///
+    /// This includes the `---` and `...` markers, if any, but not the directives.
+    pub span: Span,
+    /// Whether the document start is explicit (`---`).
+    pub explicit_start: bool,
+    /// The directives (`%YAML`, `%TAG`) that precede the document.
+    pub directives: Directives<'input>,
+}
+
+impl DocumentInfo<'_> {
+    /// Return a version of the document information that owns its data.
+    #[must_use]
+    pub fn into_owned(self) -> DocumentInfo<'static> {
+        DocumentInfo {
+            span: self.span,
+            explicit_start: self.explicit_start,
+            directives: self.directives.into_owned(),
+        }
+    }
 }
 
 /// A YAML tag.
+///
+/// As with scalars, the handle and suffix borrow from the input whenever possible. A handle that
+/// was resolved from a `%TAG` directive borrows from the directive's prefix.
 #[derive(Clone, PartialEq, Debug, Eq)]
-pub struct Tag {
+pub struct Tag<'input> {
     /// Handle of the tag (`!` included).
-    pub handle: String,
+    pub handle: Cow<'input, str>,
     /// The suffix of the tag.
-    pub suffix: String,
+    pub suffix: Cow<'input, str>,
+}
+
+impl Tag<'_> {
+    /// Return a version of the tag that owns its data.
+    #[must_use]
+    pub fn into_owned(self) -> Tag<'static> {
+        Tag {
+            handle: Cow::Owned(self.handle.into_owned()),
+            suffix: Cow::Owned(self.suffix.into_owned()),
+        }
+    }
 }
 
-impl Event {
+impl<'input> Event<'input> {
     /// Create an empty scalar.
-    fn empty_scalar() -> Event {
+    fn empty_scalar() -> Self {
         // a null scalar
-        Event::Scalar("~".to_owned(), TScalarStyle::Plain, 0, None)
+        Event::Scalar(Cow::Borrowed("~"), TScalarStyle::Plain, 0, None)
     }
 
     /// Create an empty scalar with the given anchor.
-    fn empty_scalar_with_anchor(anchor: usize, tag: Option<Tag>) -> Event {
-        Event::Scalar(String::new(), TScalarStyle::Plain, anchor, tag)
+    fn empty_scalar_with_anchor(anchor: usize, tag: Option<Tag<'input>>) -> Self {
+        Event::Scalar(Cow::Borrowed(""), TScalarStyle::Plain, anchor, tag)
+    }
+
+    /// Return a version of the event that owns all its data.
+    ///
+    /// This allows the event to outlive the input it was parsed from.
+    #[must_use]
+    pub fn into_owned(self) -> Event<'static> {
+        match self {
+            Event::Nothing => Event::Nothing,
+            Event::StreamStart => Event::StreamStart,
+            Event::StreamEnd => Event::StreamEnd,
+            Event::DocumentStart(explicit, directives) => {
+                Event::DocumentStart(explicit, directives.into_owned())
+            }
+            Event::DocumentEnd => Event::DocumentEnd,
+            Event::Alias(id) => Event::Alias(id),
+            Event::Scalar(value, style, anchor_id, tag) => Event::Scalar(
+                Cow::Owned(value.into_owned()),
+                style,
+                anchor_id,
+                tag.map(Tag::into_owned),
+            ),
+            Event::SequenceStart(anchor_id, tag) => {
+                Event::SequenceStart(anchor_id, tag.map(Tag::into_owned))
+            }
+            Event::SequenceEnd => Event::SequenceEnd,
+            Event::MappingStart(anchor_id, tag) => {
+                Event::MappingStart(anchor_id, tag.map(Tag::into_owned))
+            }
+            Event::MappingEnd => Event::MappingEnd,
+            Event::Comment(text, trailing) => {
+                Event::Comment(Cow::Owned(text.into_owned()), trailing)
+            }
+        }
+    }
+}
+
+/// Limits the [`Parser`] enforces on its input.
+///
+/// By default, no limit is set. When parsing untrusted input, limits bound the resources the
+/// parser and its consumers may use. When a limit is exceeded, the parser returns a [`ScanError`]
+/// and stops.
+///
+/// The length of the input and of scalars is checked by the [`Scanner`] as it consumes
+/// characters: an oversized scalar is rejected as soon as it exceeds the limit, without being read
+/// in full. The nesting depth is checked when a collection is opened and the number of events as
+/// they are produced.
+///
+/// ```
+/// # use saphyr_parser::{Parser, ParserLimits};
+/// let limits = ParserLimits::new().max_depth(2);
+/// let mut parser = Parser::new_from_str("[[[deep]]]").limits(limits);
+/// let err = parser.find_map(Result::err).unwrap();
+/// assert_eq!(err.info(), "exceeded the maximum nesting depth of 2");
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
+pub struct ParserLimits {
+    /// Maximum number of nested collections.
+    pub(crate) depth: Option<usize>,
+    /// Maximum length of a scalar, in bytes.
+    pub(crate) scalar_len: Option<usize>,
+    /// Maximum number of events in a single document.
+    events_per_document: Option<usize>,
+    /// Maximum length of the input, in bytes.
+    pub(crate) input_len: Option<usize>,
+}
+
+impl ParserLimits {
+    /// Create a set of limits where nothing is limited.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of nested collections (sequences and mappings).
+    ///
+    /// A top-level collection has a depth of 1.
+    #[must_use]
+    pub fn max_depth(mut self, value: usize) -> Self {
+        self.depth = Some(value);
+        self
+    }
+
+    /// Set the maximum length of a scalar, in bytes.
+    ///
+    /// This is the length of the scalar once escape sequences and line folding have been
+    /// processed.
+    #[must_use]
+    pub fn max_scalar_len(mut self, value: usize) -> Self {
+        self.scalar_len = Some(value);
+        self
+    }
+
+    /// Set the maximum number of events a single document may produce.
+    ///
+    /// The [`Event::DocumentStart`] and [`Event::DocumentEnd`] events are included in the count.
+    /// [`Event::Comment`]s are not.
+    #[must_use]
+    pub fn max_events_per_document(mut self, value: usize) -> Self {
+        self.events_per_document = Some(value);
+        self
+    }
+
+    /// Set the maximum length of the input, in bytes.
+    #[must_use]
+    pub fn max_input_len(mut self, value: usize) -> Self {
+        self.input_len = Some(value);
+        self
     }
 }
 
 /// A YAML parser.
-#[derive(Debug)]
-pub struct Parser<T: Input> {
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Parser<'input, T: BorrowedInput<'input>> {
     /// The underlying scanner from which we pull tokens.
-    scanner: Scanner<T>,
+    scanner: Scanner<'input, T>,
     /// The stack of _previous_ states we were in.
     ///
     /// States are pushed in the context of subobjects to this stack. The top-most element is the
@@ -122,20 +337,25 @@ pub struct Parser<T: Input> {
     /// The state in which we currently are.
     state: State,
     /// The next token from the scanner.
-    token: Option<Token>,
+    token: Option<Token<'input>>,
     /// The next YAML event to emit.
-    current: Option<(Event, Span)>,
+    current: Option<(Event<'input>, Span)>,
     /// Anchors that have been encountered in the YAML document.
-    anchors: HashMap<String, usize>,
+    anchors: BTreeMap<Cow<'input, str>, usize>,
     /// Next ID available for an anchor.
     ///
     /// Every anchor is given a unique ID. We use an incrementing ID and this is both the ID to
     /// return for the next anchor and the count of anchor IDs emitted.
     anchor_id_count: usize,
+    /// The names of the anchors that have been encountered, indexed by their ID minus 1.
+    ///
+    /// Contrary to [`Self::anchors`], this is not cleared between documents so that IDs from any
+    /// previous event can be looked up.
+    anchor_names: Vec<Cow<'input, str>>,
     /// The tag directives (`%TAG`) the parser has encountered.
     ///
     /// Key is the handle, and value is the prefix.
-    tags: HashMap<String, String>,
+    tags: BTreeMap<Cow<'input, str>, Cow<'input, str>>,
     /// Whether we have emitted [`Event::StreamEnd`].
     ///
     /// Emitted means that it has been returned from [`Self::next_token`]. If it is stored in
@@ -143,6 +363,100 @@ pub struct Parser<T: Input> {
     stream_end_emitted: bool,
     /// Make tags global across all documents.
     keep_tags: bool,
+    /// Comments that were read from the scanner but not yet emitted.
+    ///
+    /// Comment tokens do not take part in the parser state machine. They are put aside when
+    /// fetching tokens and emitted as soon as possible.
+    comments: VecDeque<Token<'input>>,
+    /// An event that was held back in order to emit preceding comments first.
+    delayed_event: Option<(Event<'input>, Span)>,
+    /// The limits to enforce on the input.
+    limits: ParserLimits,
+    /// The number of events emitted since the start of the current document.
+    document_events: usize,
+    /// The collections we are currently nested in, from outermost to innermost.
+    collections: Vec<OpenCollection>,
+    /// Whether we have emitted an [`Event::DocumentStart`] without its [`Event::DocumentEnd`].
+    in_document: bool,
+    /// Whether to recover from errors rather than stopping at the first one.
+    recover_errors: bool,
+    /// The errors that were recovered from.
+    errors: Vec<ScanError>,
+    /// Events generated when recovering from an error, to be emitted before resuming parsing.
+    recovery_events: VecDeque<(Event<'input>, Span)>,
+    /// The ID of the first anchor of the current document.
+    document_first_anchor_id: usize,
+    /// The anchors of the current document that no alias referred to yet, by ID.
+    unused_anchors: BTreeMap<usize, Span>,
+    /// The warnings that were found so far.
+    warnings: Vec<Warning>,
+    /// The changes made to [`Self::anchors`] and [`Self::unused_anchors`] since the last
+    /// [`Self::checkpoint`], if the parser may be rolled back to it.
+    anchor_changes: Option<Vec<AnchorChange<'input>>>,
+}
+
+/// A change to the anchors of a [`Parser`], recorded so that it can be undone.
+#[derive(Clone, Debug)]
+enum AnchorChange<'input> {
+    /// An anchor was registered with the given ID, replacing the anchor of the same name, if any.
+    Registered {
+        /// The name of the anchor.
+        name: Cow<'input, str>,
+        /// The ID of the anchor.
+        id: usize,
+        /// The ID the name referred to before.
+        previous: Option<usize>,
+    },
+    /// The anchor with the given ID and span was used by an alias.
+    Used(usize, Span),
+    /// The anchors that were unused at the end of a document were reported.
+    Reported(BTreeMap<usize, Span>),
+    /// The anchors were cleared before loading a new document.
+    Cleared(BTreeMap<Cow<'input, str>, usize>),
+}
+
+/// A state a [`Parser`] can be rolled back to.
+///
+/// See [`Parser::checkpoint`].
+#[derive(Debug)]
+pub(crate) struct Checkpoint<'input, T: BorrowedInput<'input>> {
+    /// A copy of the parser, without its [`GrowingState`].
+    parser: Parser<'input, T>,
+    /// The number of anchor names at the checkpoint.
+    anchor_names: usize,
+    /// The number of warnings of the parser at the checkpoint.
+    warnings: usize,
+    /// The number of errors at the checkpoint.
+    errors: usize,
+    /// The number of warnings of the scanner at the checkpoint.
+    scanner_warnings: usize,
+}
+
+/// The state of a [`Parser`] that grows as the input is parsed.
+///
+/// This is left out of [`Checkpoint`]s, so that saving the state of the parser takes the same
+/// time regardless of how much input was parsed.
+struct GrowingState<'input> {
+    anchors: BTreeMap<Cow<'input, str>, usize>,
+    unused_anchors: BTreeMap<usize, Span>,
+    anchor_names: Vec<Cow<'input, str>>,
+    warnings: Vec<Warning>,
+    errors: Vec<ScanError>,
+    scanner_warnings: Vec<Warning>,
+}
+
+/// A collection the parser has emitted the start event of, but not yet the end event.
+#[derive(Clone, Copy, Debug)]
+struct OpenCollection {
+    /// Whether the collection is a mapping (as opposed to a sequence).
+    mapping: bool,
+    /// For block collections, the column of the collection and the state in which to parse its
+    /// next entry.
+    block: Option<(usize, State)>,
+    /// The length of [`Parser::states`] while parsing entries of the collection.
+    states_len: usize,
+    /// The number of nodes (keys and values, for mappings) emitted in the collection.
+    nodes: usize,
 }
 
 /// Trait to be implemented in order to use the low-level parsing API.
@@ -194,19 +508,19 @@ pub struct Parser<T: Input> {
 /// # use saphyr_parser::{Event, EventReceiver, Parser};
 /// #
 /// /// Sink of events. Collects them into an array.
-/// struct EventSink {
-///     events: Vec<Event>,
+/// struct EventSink<'input> {
+///     events: Vec<Event<'input>>,
 /// }
 ///
 /// /// Implement `on_event`, pushing into `self.events`.
-/// impl EventReceiver for EventSink {
-///     fn on_event(&mut self, ev: Event) {
+/// impl<'input> EventReceiver<'input> for EventSink<'input> {
+///     fn on_event(&mut self, ev: Event<'input>) {
 ///         self.events.push(ev);
 ///     }
 /// }
 ///
 /// /// Load events from a yaml string.
-/// fn str_to_events(yaml: &str) -> Vec<Event> {
+/// fn str_to_events(yaml: &str) -> Vec<Event<'_>> {
 ///     let mut sink = EventSink { events: Vec::new() };
 ///     let mut parser = Parser::new_from_str(yaml);
 ///     // Load events using our sink as the receiver.
@@ -214,29 +528,29 @@ pub struct Parser<T: Input> {
 ///     sink.events
 /// }
 /// ```
-pub trait EventReceiver {
+pub trait EventReceiver<'input> {
     /// Handler called for each YAML event that is emitted by the parser.
-    fn on_event(&mut self, ev: Event);
+    fn on_event(&mut self, ev: Event<'input>);
 }
 
 /// Trait to be implemented for using the low-level parsing API.
 ///
 /// Functionally similar to [`EventReceiver`], but receives a [`Span`] as well as the event.
-pub trait SpannedEventReceiver {
+pub trait SpannedEventReceiver<'input> {
     /// Handler called for each event that occurs.
-    fn on_event(&mut self, ev: Event, span: Span);
+    fn on_event(&mut self, ev: Event<'input>, span: Span);
 }
 
-impl<R: EventReceiver> SpannedEventReceiver for R {
-    fn on_event(&mut self, ev: Event, _span: Span) {
+impl<'input, R: EventReceiver<'input>> SpannedEventReceiver<'input> for R {
+    fn on_event(&mut self, ev: Event<'input>, _span: Span) {
         self.on_event(ev);
     }
 }
 
 /// A convenience alias for a `Result` of a parser event.
-pub type ParseResult = Result<(Event, Span), ScanError>;
+pub type ParseResult<'input> = Result<(Event<'input>, Span), ScanError>;
 
-impl<'a> Parser<StrInput<'a>> {
+impl<'a> Parser<'a, StrInput<'a>> {
     /// Create a new instance of a parser from a &str.
     #[must_use]
     pub fn new_from_str(value: &'a str) -> Self {
@@ -245,7 +559,7 @@ impl<'a> Parser<StrInput<'a>> {
     }
 }
 
-impl<T> Parser<BufferedInput<T>>
+impl<T> Parser<'_, BufferedInput<T>>
 where
     T: Iterator<Item = char>,
 {
@@ -257,9 +571,23 @@ where
     }
 }
 
-impl<T: Input> Parser<T> {
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Parser<'_, ReaderInput<R>> {
+    /// Create a new instance of a parser from a [`Read`]er of UTF-8 encoded bytes.
+    ///
+    /// See [`ReaderInput`] for how the bytes are decoded.
+    ///
+    /// [`Read`]: std::io::Read
+    #[must_use]
+    pub fn new_from_reader(reader: R) -> Self {
+        debug_print!("\x1B[;31m>>>>>>>>>> New parser from reader\x1B[;0m");
+        Parser::new(ReaderInput::new(reader))
+    }
+}
+
+impl<'input, T: BorrowedInput<'input>> Parser<'input, T> {
     /// Create a new instance of a parser from the given input of characters.
-    pub fn new(src: T) -> Parser<T> {
+    pub fn new(src: T) -> Self {
         Parser {
             scanner: Scanner::new(src),
             states: Vec::new(),
@@ -267,12 +595,26 @@ impl<T: Input> Parser<T> {
             token: None,
             current: None,
 
-            anchors: HashMap::new(),
+            anchors: BTreeMap::new(),
             // valid anchor_id starts from 1
             anchor_id_count: 1,
-            tags: HashMap::new(),
+            anchor_names: Vec::new(),
+            tags: BTreeMap::new(),
             stream_end_emitted: false,
             keep_tags: false,
+            comments: VecDeque::new(),
+            delayed_event: None,
+            limits: ParserLimits::default(),
+            document_events: 0,
+            collections: Vec::new(),
+            in_document: false,
+            recover_errors: false,
+            errors: Vec::new(),
+            recovery_events: VecDeque::new(),
+            document_first_anchor_id: 1,
+            unused_anchors: BTreeMap::new(),
+            warnings: Vec::new(),
+            anchor_changes: None,
         }
     }
 
@@ -304,6 +646,168 @@ impl<T: Input> Parser<T> {
         self
     }
 
+    /// Whether to emit [`Event::Comment`] events for comments in the YAML input.
+    ///
+    /// By default, comments are discarded. When set to `true`, each comment is reported as an
+    /// [`Event::Comment`] with the [`Span`] of the comment (from the `#` to the end of the line):
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let events: Vec<_> = Parser::new_from_str("# License header\na: b # trailing\n")
+    ///     .keep_comments(true)
+    ///     .map(|x| x.unwrap().0)
+    ///     .filter(|ev| matches!(ev, Event::Comment(..)))
+    ///     .collect();
+    /// assert_eq!(
+    ///     events,
+    ///     [
+    ///         Event::Comment(" License header".into(), false),
+    ///         Event::Comment(" trailing".into(), true),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// Comments are emitted before the first event following them whenever possible. Since they
+    /// can appear anywhere in the input, receivers must expect them between any two events.
+    #[must_use]
+    pub fn keep_comments(mut self, value: bool) -> Self {
+        self.scanner.keep_comments(value);
+        self
+    }
+
+    /// Whether to collect warnings, to be retrieved with [`Self::take_warnings`].
+    ///
+    /// By default, no warning is collected and the bookkeeping they need (e.g. to find unused
+    /// anchors) is skipped.
+    #[must_use]
+    pub fn collect_warnings(mut self, value: bool) -> Self {
+        self.scanner.collect_warnings(value);
+        self
+    }
+
+    /// Set the limits the parser enforces on its input.
+    ///
+    /// See [`ParserLimits`] for details.
+    #[must_use]
+    pub fn limits(mut self, limits: ParserLimits) -> Self {
+        self.scanner.limits(limits);
+        self.limits = limits;
+        self
+    }
+
+    /// Whether to recover from errors and carry on parsing.
+    ///
+    /// By default, the parser stops at the first error. When set to `true`, the parser records
+    /// the error and skips input up to a point where it can resume: the next line starting at the
+    /// column of an enclosing block collection, or the next document. The collections and
+    /// document that cannot be resumed are closed with the appropriate events, inserting empty
+    /// scalars for missing mapping values, so that the event stream stays well-formed.
+    ///
+    /// The parser then never returns an error (except when exceeding [`ParserLimits`]). The errors
+    /// that were recovered from can be retrieved with [`Self::take_errors`]:
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: [1\nb: 2\nc: 'x' y\nd: 4").recover_errors(true);
+    /// assert!(parser.by_ref().all(|ev| ev.is_ok()));
+    /// let errors = parser.take_errors();
+    /// assert_eq!(
+    ///     errors.iter().map(|e| e.marker().line()).collect::<Vec<_>>(),
+    ///     [2, 3]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn recover_errors(mut self, value: bool) -> Self {
+        self.recover_errors = value;
+        self
+    }
+
+    /// Return the errors the parser has recovered from so far, leaving none in their place.
+    ///
+    /// See [`Self::recover_errors`].
+    pub fn take_errors(&mut self) -> Vec<ScanError> {
+        core::mem::take(&mut self.errors)
+    }
+
+    /// Return the warnings found so far, leaving none in their place.
+    ///
+    /// Warnings point at input that is valid, but suspicious: unknown `%YAML` versions and
+    /// directives, redefined or unused anchors and tabs that may be mistaken for indentation (see
+    /// [`WarningKind`]). They are sorted by location.
+    ///
+    /// Unused anchors are reported once their document has ended.
+    ///
+    /// Warnings are only collected if enabled with [`Self::collect_warnings`]. The parser and its
+    /// scanner each keep at most [`MAX_BUFFERED_WARNINGS`](crate::MAX_BUFFERED_WARNINGS) until they
+    /// are taken.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, WarningKind};
+    /// let mut parser =
+    ///     Parser::new_from_str("%YAML 1.3\n---\na: &x 1\nb: &x 2\n").collect_warnings(true);
+    /// for event in parser.by_ref() {
+    ///     event.unwrap();
+    /// }
+    /// let kinds: Vec<_> = parser.take_warnings().iter().map(|w| w.kind()).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [
+    ///         WarningKind::UnknownVersion,
+    ///         WarningKind::UnusedAnchor,
+    ///         WarningKind::RedefinedAnchor,
+    ///         WarningKind::UnusedAnchor,
+    ///     ]
+    /// );
+    /// ```
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        let mut warnings = self.scanner.take_warnings();
+        warnings.append(&mut self.warnings);
+        warnings.sort_by_key(|warning| warning.span().start.index());
+        warnings
+    }
+
+    /// Return the input of the parser.
+    pub(crate) fn input(&self) -> &T {
+        self.scanner.input()
+    }
+
+    /// Return the input of the parser, mutably.
+    pub(crate) fn input_mut(&mut self) -> &mut T {
+        self.scanner.input_mut()
+    }
+
+    /// Record a change to the anchors if the parser may be rolled back.
+    fn record_anchor_change(&mut self, change: impl FnOnce() -> AnchorChange<'input>) {
+        if let Some(changes) = self.anchor_changes.as_mut() {
+            changes.push(change());
+        }
+    }
+
+    /// Return the name of the anchor with the given ID.
+    ///
+    /// Events only carry the numeric ID of the anchor they define ([`Event::Scalar`],
+    /// [`Event::SequenceStart`], [`Event::MappingStart`]) or refer to ([`Event::Alias`]). This
+    /// returns the name the anchor was given in the source, for any anchor the parser has
+    /// encountered so far. Returns `None` if no anchor has that ID (e.g. for ID `0`, which means
+    /// "no anchor").
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("base: &base_config {}\nderived: *base_config");
+    /// let alias_id = parser
+    ///     .by_ref()
+    ///     .find_map(|ev| match ev.unwrap().0 {
+    ///         Event::Alias(id) => Some(id),
+    ///         _ => None,
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(parser.anchor_name(alias_id), Some("base_config"));
+    /// ```
+    #[must_use]
+    pub fn anchor_name(&self, id: usize) -> Option<&str> {
+        self.anchor_names.get(id.checked_sub(1)?).map(AsRef::as_ref)
+    }
+
     /// Try to load the next event and return it, but do not consuming it from `self`.
     ///
     /// Any subsequent call to [`Parser::peek`] will return the same value, until a call to
@@ -311,7 +815,7 @@ impl<T: Input> Parser<T> {
     ///
     /// # Errors
     /// Returns `ScanError` when loading the next event fails.
-    pub fn peek(&mut self) -> Option<Result<&(Event, Span), ScanError>> {
+    pub fn peek(&mut self) -> Option<Result<&(Event<'input>, Span), ScanError>> {
         if let Some(ref x) = self.current {
             Some(Ok(x))
         } else {
@@ -330,7 +834,7 @@ impl<T: Input> Parser<T> {
     ///
     /// # Errors
     /// Returns `ScanError` when loading the next event fails.
-    pub fn next_event(&mut self) -> Option<ParseResult> {
+    pub fn next_event(&mut self) -> Option<ParseResult<'input>> {
         if self.stream_end_emitted {
             return None;
         }
@@ -342,12 +846,89 @@ impl<T: Input> Parser<T> {
         Some(tok)
     }
 
+    /// Skip over the next document, returning where it is and how it starts.
+    ///
+    /// The events of the document are parsed, but discarded rather than sent to a receiver.
+    /// Comments preceding the document are discarded as well. Returns `None` once the stream has
+    /// ended.
+    ///
+    /// Skipping documents may be interleaved with reading events. To read a given document, skip
+    /// the ones before it and then read its events with [`Self::next_event`] or [`Self::load`].
+    /// If called in the middle of a document, the rest of that document is skipped first.
+    /// ```
+    /// # use saphyr_parser::{Event, Parser, TScalarStyle};
+    /// let mut parser = Parser::new_from_str("a\n---\nb\n---\nc\n");
+    /// let doc = parser.next_document().unwrap().unwrap();
+    /// assert!(!doc.explicit_start);
+    ///
+    /// let (ev, _) = parser.nth(1).unwrap().unwrap();
+    /// assert_eq!(ev, Event::Scalar("b".into(), TScalarStyle::Plain, 0, None));
+    /// assert_eq!(parser.documents().count(), 1);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ScanError` when parsing the document fails.
+    pub fn next_document(&mut self) -> Option<Result<DocumentInfo<'input>, ScanError>> {
+        loop {
+            match self.next_event()? {
+                Ok((Event::DocumentStart(explicit_start, directives), span)) => {
+                    return Some(self.skip_document(span.start, explicit_start, directives));
+                }
+                // `StreamStart` and comments.
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Return an iterator skipping over the remaining documents.
+    ///
+    /// See [`Self::next_document`]. The iterator stops after an error.
+    pub fn documents(&mut self) -> Documents<'_, 'input, T> {
+        Documents {
+            parser: self,
+            failed: false,
+        }
+    }
+
+    /// Discard events up to the end of the current document.
+    fn skip_document(
+        &mut self,
+        start: Marker,
+        explicit_start: bool,
+        directives: Directives<'input>,
+    ) -> Result<DocumentInfo<'input>, ScanError> {
+        loop {
+            let (ev, span) = self.next_event_impl()?;
+            match ev {
+                Event::DocumentEnd => {
+                    // An implicit document end has the span of the token that follows it.
+                    let explicit_end = self.state == State::ImplicitDocumentStart;
+                    let end = if explicit_end { span.end } else { span.start };
+                    return Ok(DocumentInfo {
+                        span: Span::new(start, end),
+                        explicit_start,
+                        directives,
+                    });
+                }
+                Event::StreamEnd => {
+                    self.stream_end_emitted = true;
+                    return Err(ScanError::from_kind(
+                        span.start,
+                        ScanErrorKind::UnexpectedEof,
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Implementation function for [`Self::next_event`] without the `Option`.
     ///
     /// [`Self::next_event`] should conform to the expectations of an [`Iterator`] and return an
     /// option. This burdens the parser code. This function is used internally when an option is
     /// undesirable.
-    fn next_event_impl(&mut self) -> ParseResult {
+    fn next_event_impl(&mut self) -> ParseResult<'input> {
         match self.current.take() {
             None => self.parse(),
             Some(v) => Ok(v),
@@ -355,7 +936,7 @@ impl<T: Input> Parser<T> {
     }
 
     /// Peek at the next token from the scanner.
-    fn peek_token(&mut self) -> Result<&Token, ScanError> {
+    fn peek_token(&mut self) -> Result<&Token<'input>, ScanError> {
         match self.token {
             None => {
                 self.token = Some(self.scan_next_token()?);
@@ -367,19 +948,28 @@ impl<T: Input> Parser<T> {
 
     /// Extract and return the next token from the scanner.
     ///
-    /// This function does _not_ make use of `self.token`.
-    fn scan_next_token(&mut self) -> Result<Token, ScanError> {
-        let token = self.scanner.next();
-        match token {
-            None => match self.scanner.get_error() {
-                None => Err(ScanError::new_str(self.scanner.mark(), "unexpected eof")),
-                Some(e) => Err(e),
-            },
-            Some(tok) => Ok(tok),
+    /// This function does _not_ make use of `self.token`. Comment tokens are put aside in
+    /// [`Self::comments`] and never returned.
+    fn scan_next_token(&mut self) -> Result<Token<'input>, ScanError> {
+        loop {
+            let token = self.scanner.next();
+            match token {
+                None => {
+                    return match self.scanner.get_error() {
+                        None => Err(ScanError::from_kind(
+                            self.scanner.mark(),
+                            ScanErrorKind::UnexpectedEof,
+                        )),
+                        Some(e) => Err(e),
+                    }
+                }
+                Some(tok @ Token(_, TokenType::Comment(..))) => self.comments.push_back(tok),
+                Some(tok) => return Ok(tok),
+            }
         }
     }
 
-    fn fetch_token(&mut self) -> Token {
+    fn fetch_token(&mut self) -> Token<'input> {
         self.token
             .take()
             .expect("fetch_token needs to be preceded by peek_token")
@@ -398,12 +988,224 @@ impl<T: Input> Parser<T> {
         self.states.push(state);
     }
 
-    fn parse(&mut self) -> ParseResult {
+    fn parse(&mut self) -> ParseResult<'input> {
+        if self.scanner.keeps_comments() {
+            return self.parse_with_comments();
+        }
         if self.state == State::End {
             return Ok((Event::StreamEnd, Span::empty(self.scanner.mark())));
         }
-        let (ev, mark) = self.state_machine()?;
-        Ok((ev, mark))
+        self.state_machine_checked()
+    }
+
+    /// Run the state machine, keeping track of open collections and enforcing [`Self::limits`].
+    ///
+    /// If [`Self::recover_errors`] is set, errors from the state machine are recovered from.
+    fn state_machine_checked(&mut self) -> ParseResult<'input> {
+        let (ev, span) = loop {
+            if let Some(ev) = self.recovery_events.pop_front() {
+                break ev;
+            }
+            match self.state_machine() {
+                Ok(ev) => break ev,
+                Err(e) if self.recover_errors && !e.kind().is_limit() => self.recover(e),
+                Err(e) => return Err(e),
+            }
+        };
+        self.track_collections(&ev, span);
+        self.track_anchors(&ev);
+        self.check_limits(&ev, span)?;
+        Ok((ev, span))
+    }
+
+    /// Update the anchors of the current document with an event that is emitted.
+    ///
+    /// Anchors that are still unused at the end of the document are reported as warnings.
+    fn track_anchors(&mut self, ev: &Event<'input>) {
+        match ev {
+            Event::DocumentStart(..) => self.document_first_anchor_id = self.anchor_id_count,
+            Event::DocumentEnd => {
+                let unused_anchors = core::mem::take(&mut self.unused_anchors);
+                for (&id, &span) in &unused_anchors {
+                    push_warning(&mut self.warnings, WarningKind::UnusedAnchor, span, || {
+                        format!("anchor `{}` is never used", self.anchor_names[id - 1])
+                    });
+                }
+                self.record_anchor_change(|| AnchorChange::Reported(unused_anchors));
+            }
+            _ => {}
+        }
+    }
+
+    /// Update [`Self::collections`] and [`Self::in_document`] with an event that is emitted.
+    fn track_collections(&mut self, ev: &Event<'input>, span: Span) {
+        match ev {
+            Event::DocumentStart(..) => self.in_document = true,
+            Event::DocumentEnd => self.in_document = false,
+            Event::Scalar(..) | Event::Alias(..) => {
+                if let Some(parent) = self.collections.last_mut() {
+                    parent.nodes += 1;
+                }
+            }
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                if let Some(parent) = self.collections.last_mut() {
+                    parent.nodes += 1;
+                }
+                let block = match self.state {
+                    State::BlockMappingFirstKey => Some(State::BlockMappingKey),
+                    State::BlockSequenceFirstEntry => Some(State::BlockSequenceEntry),
+                    State::IndentlessSequenceEntry => Some(State::IndentlessSequenceEntry),
+                    _ => None,
+                };
+                self.collections.push(OpenCollection {
+                    mapping: matches!(ev, Event::MappingStart(..)),
+                    block: block.map(|state| (span.start.col(), state)),
+                    states_len: self.states.len(),
+                    nodes: 0,
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.collections.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Record `error` and prepare the parser to resume parsing.
+    ///
+    /// The scanner skips input up to the next line that starts at the column of one of the open
+    /// block collections, or up to the next document. Events closing the collections (and the
+    /// document) that cannot be resumed are queued in [`Self::recovery_events`].
+    fn recover(&mut self, error: ScanError) {
+        let span = Span::empty(*error.marker());
+        let columns: Vec<usize> = self
+            .collections
+            .iter()
+            .filter_map(|collection| collection.block.map(|(col, _)| col))
+            .collect();
+        let resumed = self.scanner.resync(error.marker().line(), &columns);
+        self.errors.push(error);
+        self.token = None;
+
+        let resume_at = resumed.and_then(|col| {
+            self.collections
+                .iter()
+                .rposition(|collection| collection.block.is_some_and(|(c, _)| c == col))
+        });
+        let keep = resume_at.map_or(0, |idx| idx + 1);
+        for collection in self.collections[keep..].iter().rev() {
+            if collection.mapping {
+                if collection.nodes % 2 == 1 {
+                    self.recovery_events
+                        .push_back((Event::empty_scalar(), span));
+                }
+                self.recovery_events.push_back((Event::MappingEnd, span));
+            } else {
+                self.recovery_events.push_back((Event::SequenceEnd, span));
+            }
+        }
+
+        if let Some(idx) = resume_at {
+            let collection = self.collections[idx];
+            if collection.mapping && collection.nodes % 2 == 1 {
+                self.recovery_events
+                    .push_back((Event::empty_scalar(), span));
+            }
+            self.states.truncate(collection.states_len);
+            self.state = collection.block.unwrap().1;
+        } else {
+            if self.in_document {
+                self.recovery_events.push_back((Event::DocumentEnd, span));
+            }
+            if !self.keep_tags {
+                self.tags.clear();
+            }
+            self.states.clear();
+            self.state = State::ImplicitDocumentStart;
+        }
+    }
+
+    /// Update the counters used to enforce [`Self::limits`] with the given event.
+    ///
+    /// This must be called after [`Self::track_collections`].
+    ///
+    /// # Errors
+    /// Returns `ScanError` if a limit is exceeded.
+    fn check_limits(&mut self, ev: &Event<'input>, span: Span) -> Result<(), ScanError> {
+        let limits = &self.limits;
+        match ev {
+            Event::DocumentStart(..) => self.document_events = 0,
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                if let Some(max) = limits.depth {
+                    if self.collections.len() > max {
+                        return Err(ScanError::new(
+                            span.start,
+                            format!("exceeded the maximum nesting depth of {max}"),
+                        )
+                        .with_kind(ScanErrorKind::NestingTooDeep));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.document_events += 1;
+        if let Some(max) = limits.events_per_document {
+            if self.document_events > max {
+                return Err(ScanError::new(
+                    span.start,
+                    format!("exceeded the maximum number of events per document ({max})"),
+                )
+                .with_kind(ScanErrorKind::TooManyEvents));
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as [`Self::parse`], but interleaves [`Event::Comment`]s with the other events.
+    ///
+    /// The state machine may read tokens further than the event it returns. If a comment was
+    /// read that is located before that event, the comment is returned first and the event is
+    /// held back in [`Self::delayed_event`].
+    fn parse_with_comments(&mut self) -> ParseResult<'input> {
+        let (ev, span) = if let Some(delayed) = self.delayed_event.take() {
+            delayed
+        } else if self.state == State::End {
+            (Event::StreamEnd, Span::empty(self.scanner.mark()))
+        } else {
+            self.state_machine_checked()?
+        };
+
+        if self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.0.start.index() <= span.start.index())
+        {
+            if let Some(Token(comment_span, TokenType::Comment(text, trailing))) =
+                self.comments.pop_front()
+            {
+                self.delayed_event = Some((ev, span));
+                return Ok((Event::Comment(text, trailing), comment_span));
+            }
+        }
+        Ok((ev, span))
+    }
+
+    /// Return the next event that is not an [`Event::Comment`].
+    ///
+    /// Comments that are encountered in the process are sent to `recv`.
+    fn next_event_forward_comments<R: SpannedEventReceiver<'input>>(
+        &mut self,
+        recv: &mut R,
+    ) -> ParseResult<'input> {
+        loop {
+            let (ev, span) = self.next_event_impl()?;
+            if let Event::Comment(..) = ev {
+                recv.on_event(ev, span);
+            } else {
+                return Ok((ev, span));
+            }
+        }
     }
 
     /// Load the YAML from the stream in `self`, pushing events into `recv`.
@@ -418,17 +1220,17 @@ impl<T: Input> Parser<T> {
     /// former is enough to call this function.
     /// # Errors
     /// Returns `ScanError` when loading fails.
-    pub fn load<R: SpannedEventReceiver>(
+    pub fn load<R: SpannedEventReceiver<'input>>(
         &mut self,
         recv: &mut R,
         multi: bool,
     ) -> Result<(), ScanError> {
         if !self.scanner.stream_started() {
-            let (ev, span) = self.next_event_impl()?;
+            let (ev, span) = self.next_event_forward_comments(recv)?;
             if ev != Event::StreamStart {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     span.start,
-                    "did not find expected <stream-start>",
+                    ScanErrorKind::MissingStreamStart,
                 ));
             }
             recv.on_event(ev, span);
@@ -440,13 +1242,14 @@ impl<T: Input> Parser<T> {
             return Ok(());
         }
         loop {
-            let (ev, span) = self.next_event_impl()?;
+            let (ev, span) = self.next_event_forward_comments(recv)?;
             if ev == Event::StreamEnd {
                 recv.on_event(ev, span);
                 return Ok(());
             }
             // clear anchors before a new document
-            self.anchors.clear();
+            let anchors = core::mem::take(&mut self.anchors);
+            self.record_anchor_change(|| AnchorChange::Cleared(anchors));
             self.load_document(ev, span, recv)?;
             if !multi {
                 break;
@@ -455,34 +1258,34 @@ impl<T: Input> Parser<T> {
         Ok(())
     }
 
-    fn load_document<R: SpannedEventReceiver>(
+    fn load_document<R: SpannedEventReceiver<'input>>(
         &mut self,
-        first_ev: Event,
+        first_ev: Event<'input>,
         span: Span,
         recv: &mut R,
     ) -> Result<(), ScanError> {
-        if !matches!(first_ev, Event::DocumentStart(_)) {
-            return Err(ScanError::new_str(
+        if !matches!(first_ev, Event::DocumentStart(..)) {
+            return Err(ScanError::from_kind(
                 span.start,
-                "did not find expected <document-start>",
+                ScanErrorKind::MissingDocumentStart,
             ));
         }
         recv.on_event(first_ev, span);
 
-        let (ev, span) = self.next_event_impl()?;
+        let (ev, span) = self.next_event_forward_comments(recv)?;
         self.load_node(ev, span, recv)?;
 
         // DOCUMENT-END is expected.
-        let (ev, mark) = self.next_event_impl()?;
+        let (ev, mark) = self.next_event_forward_comments(recv)?;
         assert_eq!(ev, Event::DocumentEnd);
         recv.on_event(ev, mark);
 
         Ok(())
     }
 
-    fn load_node<R: SpannedEventReceiver>(
+    fn load_node<R: SpannedEventReceiver<'input>>(
         &mut self,
-        first_ev: Event,
+        first_ev: Event<'input>,
         span: Span,
         recv: &mut R,
     ) -> Result<(), ScanError> {
@@ -500,24 +1303,26 @@ impl<T: Input> Parser<T> {
                 self.load_mapping(recv)
             }
             _ => {
-                println!("UNREACHABLE EVENT: {first_ev:?}");
-                unreachable!();
+                unreachable!("unexpected event: {first_ev:?}");
             }
         }
     }
 
-    fn load_mapping<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> Result<(), ScanError> {
-        let (mut key_ev, mut key_mark) = self.next_event_impl()?;
+    fn load_mapping<R: SpannedEventReceiver<'input>>(
+        &mut self,
+        recv: &mut R,
+    ) -> Result<(), ScanError> {
+        let (mut key_ev, mut key_mark) = self.next_event_forward_comments(recv)?;
         while key_ev != Event::MappingEnd {
             // key
             self.load_node(key_ev, key_mark, recv)?;
 
             // value
-            let (ev, mark) = self.next_event_impl()?;
+            let (ev, mark) = self.next_event_forward_comments(recv)?;
             self.load_node(ev, mark, recv)?;
 
             // next event
-            let (ev, mark) = self.next_event_impl()?;
+            let (ev, mark) = self.next_event_forward_comments(recv)?;
             key_ev = ev;
             key_mark = mark;
         }
@@ -525,13 +1330,16 @@ impl<T: Input> Parser<T> {
         Ok(())
     }
 
-    fn load_sequence<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> Result<(), ScanError> {
-        let (mut ev, mut mark) = self.next_event_impl()?;
+    fn load_sequence<R: SpannedEventReceiver<'input>>(
+        &mut self,
+        recv: &mut R,
+    ) -> Result<(), ScanError> {
+        let (mut ev, mut mark) = self.next_event_forward_comments(recv)?;
         while ev != Event::SequenceEnd {
             self.load_node(ev, mark, recv)?;
 
             // next event
-            let (next_ev, next_mark) = self.next_event_impl()?;
+            let (next_ev, next_mark) = self.next_event_forward_comments(recv)?;
             ev = next_ev;
             mark = next_mark;
         }
@@ -539,7 +1347,7 @@ impl<T: Input> Parser<T> {
         Ok(())
     }
 
-    fn state_machine(&mut self) -> ParseResult {
+    fn state_machine(&mut self) -> ParseResult<'input> {
         // let next_tok = self.peek_token().cloned()?;
         // println!("cur_state {:?}, next tok: {:?}", self.state, next_tok);
         debug_print!("\n\x1B[;33mParser state: {:?} \x1B[;0m", self.state);
@@ -581,21 +1389,21 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn stream_start(&mut self) -> ParseResult {
+    fn stream_start(&mut self) -> ParseResult<'input> {
         match *self.peek_token()? {
             Token(span, TokenType::StreamStart(_)) => {
                 self.state = State::ImplicitDocumentStart;
                 self.skip();
                 Ok((Event::StreamStart, span))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::from_kind(
                 span.start,
-                "did not find expected <stream-start>",
+                ScanErrorKind::MissingStreamStart,
             )),
         }
     }
 
-    fn document_start(&mut self, implicit: bool) -> ParseResult {
+    fn document_start(&mut self, implicit: bool) -> ParseResult<'input> {
         while let TokenType::DocumentEnd = self.peek_token()?.1 {
             self.skip();
         }
@@ -616,10 +1424,10 @@ impl<T: Input> Parser<T> {
                 self.explicit_document_start()
             }
             Token(span, _) if implicit => {
-                self.parser_process_directives()?;
+                let directives = self.parser_process_directives()?;
                 self.push_state(State::DocumentEnd);
                 self.state = State::BlockNode;
-                Ok((Event::DocumentStart(false), span))
+                Ok((Event::DocumentStart(false, directives), span))
             }
             _ => {
                 // explicit document
@@ -628,56 +1436,68 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn parser_process_directives(&mut self) -> Result<(), ScanError> {
-        let mut version_directive_received = false;
+    /// Read the directives preceding a document.
+    ///
+    /// `%TAG` directives are registered for use by [`Self::resolve_tag`].
+    fn parser_process_directives(&mut self) -> Result<Directives<'input>, ScanError> {
+        let mut directives = Directives::default();
+        let mut tags = BTreeMap::new();
         loop {
-            let mut tags = HashMap::new();
             match self.peek_token()? {
-                Token(span, TokenType::VersionDirective(_, _)) => {
-                    // XXX parsing with warning according to spec
-                    //if major != 1 || minor > 2 {
-                    //    return Err(ScanError::new_str(tok.0,
-                    //        "found incompatible YAML document"));
-                    //}
-                    if version_directive_received {
-                        return Err(ScanError::new_str(
+                &Token(span, TokenType::VersionDirective(major, minor)) => {
+                    if directives.version.is_some() {
+                        return Err(ScanError::from_kind(
                             span.start,
-                            "duplicate version directive",
+                            ScanErrorKind::DuplicateVersionDirective,
                         ));
                     }
-                    version_directive_received = true;
+                    if !matches!((major, minor), (1, 1 | 2)) {
+                        self.warn(WarningKind::UnknownVersion, span, || {
+                            format!("unknown YAML version {major}.{minor}, parsing as YAML 1.2")
+                        });
+                    }
+                    directives.version = Some((major, minor));
                 }
+                // Reserved directives are reported by the scanner as tag directives with an
+                // empty handle. They are ignored.
+                Token(_, TokenType::TagDirective(handle, _)) if handle.is_empty() => {}
                 Token(mark, TokenType::TagDirective(handle, prefix)) => {
                     if tags.contains_key(handle) {
-                        return Err(ScanError::new_str(mark.start, "the TAG directive must only be given at most once per handle in the same document"));
+                        return Err(ScanError::from_kind(
+                            mark.start,
+                            ScanErrorKind::DuplicateTagDirective,
+                        ));
                     }
-                    tags.insert(handle.to_string(), prefix.to_string());
+                    directives.tags.push((handle.clone(), prefix.clone()));
+                    tags.insert(handle.clone(), prefix.clone());
                 }
                 _ => break,
             }
-            self.tags = tags;
             self.skip();
         }
-        Ok(())
+        if !tags.is_empty() {
+            self.tags = tags;
+        }
+        Ok(directives)
     }
 
-    fn explicit_document_start(&mut self) -> ParseResult {
-        self.parser_process_directives()?;
+    fn explicit_document_start(&mut self) -> ParseResult<'input> {
+        let directives = self.parser_process_directives()?;
         match *self.peek_token()? {
             Token(mark, TokenType::DocumentStart) => {
                 self.push_state(State::DocumentEnd);
                 self.state = State::DocumentContent;
                 self.skip();
-                Ok((Event::DocumentStart(true), mark))
+                Ok((Event::DocumentStart(true, directives), mark))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::from_kind(
                 span.start,
-                "did not find expected <document start>",
+                ScanErrorKind::MissingDocumentStart,
             )),
         }
     }
 
-    fn document_content(&mut self) -> ParseResult {
+    fn document_content(&mut self) -> ParseResult<'input> {
         match *self.peek_token()? {
             Token(
                 mark,
@@ -688,14 +1508,15 @@ impl<T: Input> Parser<T> {
                 | TokenType::StreamEnd,
             ) => {
                 self.pop_state();
-                // empty scalar
-                Ok((Event::empty_scalar(), mark))
+                // The empty scalar is placed before the token, which belongs to what follows the
+                // document.
+                Ok((Event::empty_scalar(), Span::empty(mark.start)))
             }
             _ => self.parse_node(true, false),
         }
     }
 
-    fn document_end(&mut self) -> ParseResult {
+    fn document_end(&mut self) -> ParseResult<'input> {
         let mut explicit_end = false;
         let span: Span = match *self.peek_token()? {
             Token(span, TokenType::DocumentEnd) => {
@@ -715,9 +1536,9 @@ impl<T: Input> Parser<T> {
             if let Token(span, TokenType::VersionDirective(..) | TokenType::TagDirective(..)) =
                 *self.peek_token()?
             {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     span.start,
-                    "missing explicit document end marker before directive",
+                    ScanErrorKind::MissingDocumentEndBeforeDirective,
                 ));
             }
             self.state = State::DocumentStart;
@@ -726,19 +1547,41 @@ impl<T: Input> Parser<T> {
         Ok((Event::DocumentEnd, span))
     }
 
-    fn register_anchor(&mut self, name: String, _: &Span) -> usize {
+    /// Record a warning, if warnings are collected.
+    fn warn(&mut self, kind: WarningKind, span: Span, info: impl FnOnce() -> String) {
+        if self.scanner.collects_warnings() {
+            push_warning(&mut self.warnings, kind, span, info);
+        }
+    }
+
+    fn register_anchor(&mut self, name: Cow<'input, str>, span: &Span) -> usize {
         // anchors can be overridden/reused
-        // if self.anchors.contains_key(name) {
-        //     return Err(ScanError::new_str(*mark,
-        //         "while parsing anchor, found duplicated anchor"));
-        // }
+        if self
+            .anchors
+            .get(&name)
+            .is_some_and(|&id| id >= self.document_first_anchor_id)
+        {
+            self.warn(WarningKind::RedefinedAnchor, *span, || {
+                format!("anchor `{name}` is redefined")
+            });
+        }
         let new_id = self.anchor_id_count;
         self.anchor_id_count += 1;
-        self.anchors.insert(name, new_id);
+        self.anchor_names.push(name.clone());
+        let previous = self.anchors.insert(name.clone(), new_id);
+        // Unused anchors are only looked for if they are reported.
+        if self.scanner.collects_warnings() {
+            self.unused_anchors.insert(new_id, *span);
+        }
+        self.record_anchor_change(|| AnchorChange::Registered {
+            name,
+            id: new_id,
+            previous,
+        });
         new_id
     }
 
-    fn parse_node(&mut self, block: bool, indentless_sequence: bool) -> ParseResult {
+    fn parse_node(&mut self, block: bool, indentless_sequence: bool) -> ParseResult<'input> {
         let mut anchor_id = 0;
         let mut tag = None;
         match *self.peek_token()? {
@@ -747,12 +1590,17 @@ impl<T: Input> Parser<T> {
                 if let Token(span, TokenType::Alias(name)) = self.fetch_token() {
                     match self.anchors.get(&name) {
                         None => {
-                            return Err(ScanError::new_str(
+                            return Err(ScanError::from_kind(
                                 span.start,
-                                "while parsing node, found unknown anchor",
+                                ScanErrorKind::UnknownAnchor,
                             ))
                         }
-                        Some(id) => return Ok((Event::Alias(*id), span)),
+                        Some(&id) => {
+                            if let Some(anchor_span) = self.unused_anchors.remove(&id) {
+                                self.record_anchor_change(|| AnchorChange::Used(id, anchor_span));
+                            }
+                            return Ok((Event::Alias(id), span));
+                        }
                     }
                 }
                 unreachable!()
@@ -762,7 +1610,7 @@ impl<T: Input> Parser<T> {
                     anchor_id = self.register_anchor(name, &span);
                     if let TokenType::Tag(..) = self.peek_token()?.1 {
                         if let TokenType::Tag(handle, suffix) = self.fetch_token().1 {
-                            tag = Some(self.resolve_tag(span, &handle, suffix)?);
+                            tag = Some(self.resolve_tag(span, handle, suffix)?);
                         } else {
                             unreachable!()
                         }
@@ -773,7 +1621,7 @@ impl<T: Input> Parser<T> {
             }
             Token(mark, TokenType::Tag(..)) => {
                 if let TokenType::Tag(handle, suffix) = self.fetch_token().1 {
-                    tag = Some(self.resolve_tag(mark, &handle, suffix)?);
+                    tag = Some(self.resolve_tag(mark, handle, suffix)?);
                     if let TokenType::Anchor(_) = &self.peek_token()?.1 {
                         if let Token(mark, TokenType::Anchor(name)) = self.fetch_token() {
                             anchor_id = self.register_anchor(name, &mark);
@@ -821,14 +1669,14 @@ impl<T: Input> Parser<T> {
                 self.pop_state();
                 Ok((Event::empty_scalar_with_anchor(anchor_id, tag), mark))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::from_kind(
                 span.start,
-                "while parsing a node, did not find expected node content",
+                ScanErrorKind::MissingNodeContent,
             )),
         }
     }
 
-    fn block_mapping_key(&mut self, first: bool) -> ParseResult {
+    fn block_mapping_key(&mut self, first: bool) -> ParseResult<'input> {
         // skip BlockMappingStart
         if first {
             let _ = self.peek_token()?;
@@ -859,14 +1707,14 @@ impl<T: Input> Parser<T> {
                 self.skip();
                 Ok((Event::MappingEnd, mark))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::from_kind(
                 span.start,
-                "while parsing a block mapping, did not find expected key",
+                ScanErrorKind::BlockMappingMissingKey,
             )),
         }
     }
 
-    fn block_mapping_value(&mut self) -> ParseResult {
+    fn block_mapping_value(&mut self) -> ParseResult<'input> {
         match *self.peek_token()? {
             Token(_, TokenType::Value) => {
                 self.skip();
@@ -889,7 +1737,7 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn flow_mapping_key(&mut self, first: bool) -> ParseResult {
+    fn flow_mapping_key(&mut self, first: bool) -> ParseResult<'input> {
         if first {
             let _ = self.peek_token()?;
             self.skip();
@@ -901,10 +1749,12 @@ impl<T: Input> Parser<T> {
                     if !first {
                         match *self.peek_token()? {
                             Token(_, TokenType::FlowEntry) => self.skip(),
-                            Token(span, _) => return Err(ScanError::new_str(
-                                span.start,
-                                "while parsing a flow mapping, did not find expected ',' or '}'",
-                            )),
+                            Token(span, _) => {
+                                return Err(ScanError::from_kind(
+                                    span.start,
+                                    ScanErrorKind::FlowMappingMissingSeparator,
+                                ))
+                            }
                         }
                     }
 
@@ -943,7 +1793,7 @@ impl<T: Input> Parser<T> {
         Ok((Event::MappingEnd, span))
     }
 
-    fn flow_mapping_value(&mut self, empty: bool) -> ParseResult {
+    fn flow_mapping_value(&mut self, empty: bool) -> ParseResult<'input> {
         let span: Span = {
             if empty {
                 let Token(mark, _) = *self.peek_token()?;
@@ -970,7 +1820,7 @@ impl<T: Input> Parser<T> {
         Ok((Event::empty_scalar(), span))
     }
 
-    fn flow_sequence_entry(&mut self, first: bool) -> ParseResult {
+    fn flow_sequence_entry(&mut self, first: bool) -> ParseResult<'input> {
         // skip FlowMappingStart
         if first {
             let _ = self.peek_token()?;
@@ -987,9 +1837,9 @@ impl<T: Input> Parser<T> {
                 self.skip();
             }
             Token(span, _) if !first => {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     span.start,
-                    "while parsing a flow sequence, expected ',' or ']'",
+                    ScanErrorKind::FlowSequenceMissingSeparator,
                 ));
             }
             _ => { /* next */ }
@@ -1012,7 +1862,7 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn indentless_sequence_entry(&mut self) -> ParseResult {
+    fn indentless_sequence_entry(&mut self) -> ParseResult<'input> {
         match *self.peek_token()? {
             Token(_, TokenType::BlockEntry) => (),
             Token(mark, _) => {
@@ -1034,7 +1884,7 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn block_sequence_entry(&mut self, first: bool) -> ParseResult {
+    fn block_sequence_entry(&mut self, first: bool) -> ParseResult<'input> {
         // BLOCK-SEQUENCE-START
         if first {
             let _ = self.peek_token()?;
@@ -1059,14 +1909,14 @@ impl<T: Input> Parser<T> {
                     self.parse_node(true, false)
                 }
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::from_kind(
                 span.start,
-                "while parsing a block collection, did not find expected '-' indicator",
+                ScanErrorKind::BlockSequenceMissingEntry,
             )),
         }
     }
 
-    fn flow_sequence_entry_mapping_key(&mut self) -> ParseResult {
+    fn flow_sequence_entry_mapping_key(&mut self) -> ParseResult<'input> {
         if let Token(mark, TokenType::Value | TokenType::FlowEntry | TokenType::FlowSequenceEnd) =
             *self.peek_token()?
         {
@@ -1079,7 +1929,7 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn flow_sequence_entry_mapping_value(&mut self) -> ParseResult {
+    fn flow_sequence_entry_mapping_value(&mut self) -> ParseResult<'input> {
         match *self.peek_token()? {
             Token(_, TokenType::Value) => {
                 self.skip();
@@ -1101,13 +1951,18 @@ impl<T: Input> Parser<T> {
     }
 
     #[allow(clippy::unnecessary_wraps)]
-    fn flow_sequence_entry_mapping_end(&mut self, mark: Marker) -> ParseResult {
+    fn flow_sequence_entry_mapping_end(&mut self, mark: Marker) -> ParseResult<'input> {
         self.state = State::FlowSequenceEntry;
         Ok((Event::MappingEnd, Span::empty(mark)))
     }
 
     /// Resolve a tag from the handle and the suffix.
-    fn resolve_tag(&self, span: Span, handle: &str, suffix: String) -> Result<Tag, ScanError> {
+    fn resolve_tag(
+        &self,
+        span: Span,
+        handle: Cow<'input, str>,
+        suffix: Cow<'input, str>,
+    ) -> Result<Tag<'input>, ScanError> {
         if handle == "!!" {
             // "!!" is a shorthand for "tag:yaml.org,2002:". However, that default can be
             // overridden.
@@ -1115,27 +1970,24 @@ impl<T: Input> Parser<T> {
                 handle: self
                     .tags
                     .get("!!")
-                    .map_or_else(|| "tag:yaml.org,2002:".to_string(), ToString::to_string),
+                    .map_or(Cow::Borrowed("tag:yaml.org,2002:"), Clone::clone),
                 suffix,
             })
         } else if handle.is_empty() && suffix == "!" {
             // "!" introduces a local tag. Local tags may have their prefix overridden.
             match self.tags.get("") {
                 Some(prefix) => Ok(Tag {
-                    handle: prefix.to_string(),
-                    suffix,
-                }),
-                None => Ok(Tag {
-                    handle: String::new(),
+                    handle: prefix.clone(),
                     suffix,
                 }),
+                None => Ok(Tag { handle, suffix }),
             }
         } else {
             // Lookup handle in our tag directives.
-            let prefix = self.tags.get(handle);
+            let prefix = self.tags.get(&handle);
             if let Some(prefix) = prefix {
                 Ok(Tag {
-                    handle: prefix.to_string(),
+                    handle: prefix.clone(),
                     suffix,
                 })
             } else {
@@ -1144,20 +1996,123 @@ impl<T: Input> Parser<T> {
                 // If the handle is of the form "!foo!", this cannot be a local handle and we need
                 // to error.
                 if handle.len() >= 2 && handle.starts_with('!') && handle.ends_with('!') {
-                    Err(ScanError::new_str(span.start, "the handle wasn't declared"))
+                    Err(ScanError::from_kind(
+                        span.start,
+                        ScanErrorKind::UndeclaredTagHandle,
+                    ))
                 } else {
-                    Ok(Tag {
-                        handle: handle.to_string(),
-                        suffix,
-                    })
+                    Ok(Tag { handle, suffix })
                 }
             }
         }
     }
 }
 
-impl<T: Input> Iterator for Parser<T> {
-    type Item = Result<(Event, Span), ScanError>;
+impl<'input, T: BorrowedInput<'input> + Clone> Parser<'input, T> {
+    /// Save the state of the parser, so that it can be restored with [`Self::rollback`].
+    ///
+    /// Rather than cloning the whole parser, the state that grows as the input is parsed (anchors,
+    /// warnings and errors) is left out of the copy. Lists are truncated back to their length when
+    /// rolling back, and changes to the anchors are recorded until [`Self::commit`] or
+    /// [`Self::rollback`] is called.
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint<'input, T> {
+        let growing = self.take_growing_state();
+        let checkpoint = Checkpoint {
+            parser: self.clone(),
+            anchor_names: growing.anchor_names.len(),
+            warnings: growing.warnings.len(),
+            errors: growing.errors.len(),
+            scanner_warnings: growing.scanner_warnings.len(),
+        };
+        self.restore_growing_state(growing);
+        self.anchor_changes = Some(Vec::new());
+        checkpoint
+    }
+
+    /// Restore the state saved by [`Self::checkpoint`].
+    pub(crate) fn rollback(&mut self, checkpoint: Checkpoint<'input, T>) {
+        let changes = self.anchor_changes.take().unwrap_or_default();
+        for change in changes.into_iter().rev() {
+            match change {
+                AnchorChange::Registered { name, id, previous } => {
+                    self.unused_anchors.remove(&id);
+                    match previous {
+                        Some(previous) => self.anchors.insert(name, previous),
+                        None => self.anchors.remove(&name),
+                    };
+                }
+                AnchorChange::Used(id, span) => {
+                    self.unused_anchors.insert(id, span);
+                }
+                AnchorChange::Reported(unused_anchors) => self.unused_anchors = unused_anchors,
+                AnchorChange::Cleared(anchors) => self.anchors = anchors,
+            }
+        }
+        let mut growing = self.take_growing_state();
+        growing.anchor_names.truncate(checkpoint.anchor_names);
+        growing.warnings.truncate(checkpoint.warnings);
+        growing.errors.truncate(checkpoint.errors);
+        growing
+            .scanner_warnings
+            .truncate(checkpoint.scanner_warnings);
+        *self = checkpoint.parser;
+        self.restore_growing_state(growing);
+    }
+
+    /// Discard the state saved by the last [`Self::checkpoint`], which can no longer be restored.
+    pub(crate) fn commit(&mut self) {
+        self.anchor_changes = None;
+    }
+
+    /// Move the [`GrowingState`] out of the parser.
+    fn take_growing_state(&mut self) -> GrowingState<'input> {
+        GrowingState {
+            anchors: core::mem::take(&mut self.anchors),
+            unused_anchors: core::mem::take(&mut self.unused_anchors),
+            anchor_names: core::mem::take(&mut self.anchor_names),
+            warnings: core::mem::take(&mut self.warnings),
+            errors: core::mem::take(&mut self.errors),
+            scanner_warnings: core::mem::take(self.scanner.warnings_mut()),
+        }
+    }
+
+    /// Move the [`GrowingState`] back into the parser.
+    fn restore_growing_state(&mut self, growing: GrowingState<'input>) {
+        self.anchors = growing.anchors;
+        self.unused_anchors = growing.unused_anchors;
+        self.anchor_names = growing.anchor_names;
+        self.warnings = growing.warnings;
+        self.errors = growing.errors;
+        *self.scanner.warnings_mut() = growing.scanner_warnings;
+    }
+}
+
+/// An iterator skipping over the documents of a [`Parser`].
+///
+/// This is returned by [`Parser::documents`].
+#[derive(Debug)]
+pub struct Documents<'a, 'input, T: BorrowedInput<'input>> {
+    /// The parser to skip documents from.
+    parser: &'a mut Parser<'input, T>,
+    /// Whether an error was returned, after which the iterator is exhausted.
+    failed: bool,
+}
+
+impl<'input, T: BorrowedInput<'input>> Iterator for Documents<'_, 'input, T> {
+    type Item = Result<DocumentInfo<'input>, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let doc = self.parser.next_document()?;
+        self.failed = doc.is_err();
+        Some(doc)
+    }
+}
+
+impl<'input, T: BorrowedInput<'input>> Iterator for Parser<'input, T> {
+    type Item = Result<(Event<'input>, Span), ScanError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next_event()
@@ -1166,7 +2121,10 @@ impl<T: Input> Iterator for Parser<T> {
 
 #[cfg(test)]
 mod test {
-    use super::{Event, Parser};
+    use core::fmt::Write;
+
+    use super::{Directives, Event, Parser, Tag};
+    use crate::input::chunked::ChunkedInput;
 
     #[test]
     fn test_peek_eq_parse() {
@@ -1193,6 +2151,87 @@ a5: *x
         }
     }
 
+    #[test]
+    fn test_anchor_names() {
+        let s = "
+a: &x 1
+b: &y [*x]
+---
+c: &x 2
+d: *x
+";
+        let mut p = Parser::new_from_str(s);
+        let mut anchors = vec![];
+        while let Some(x) = p.next_event() {
+            match x.unwrap().0 {
+                Event::Scalar(_, _, id, _)
+                | Event::SequenceStart(id, _)
+                | Event::MappingStart(id, _)
+                | Event::Alias(id)
+                    if id > 0 =>
+                {
+                    anchors.push((id, p.anchor_name(id).unwrap().to_owned()));
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            anchors,
+            [
+                (1, "x".to_owned()),
+                (2, "y".to_owned()),
+                (1, "x".to_owned()),
+                (3, "x".to_owned()),
+                (3, "x".to_owned()),
+            ]
+        );
+        assert_eq!(p.anchor_name(0), None);
+        assert_eq!(p.anchor_name(4), None);
+    }
+
+    #[test]
+    fn test_document_directives() {
+        let s = "
+%YAML 1.2
+%TAG !a! tag:a.com,2000:
+%TAG !b! tag:b.com,2000:
+---
+- !a!x 1
+- !b!y 2
+---
+3
+";
+        let events = Parser::new_from_str(s)
+            .map(|x| x.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events[1],
+            Event::DocumentStart(
+                true,
+                Directives {
+                    version: Some((1, 2)),
+                    tags: vec![
+                        ("!a!".into(), "tag:a.com,2000:".into()),
+                        ("!b!".into(), "tag:b.com,2000:".into()),
+                    ],
+                }
+            )
+        );
+        let tag = |handle: &str, suffix: &str| {
+            Some(Tag {
+                handle: handle.to_owned().into(),
+                suffix: suffix.to_owned().into(),
+            })
+        };
+        assert!(
+            matches!(&events[3], Event::Scalar(_, _, _, t) if *t == tag("tag:a.com,2000:", "x"))
+        );
+        assert!(
+            matches!(&events[4], Event::Scalar(_, _, _, t) if *t == tag("tag:b.com,2000:", "y"))
+        );
+        assert_eq!(events[7], Event::DocumentStart(true, Directives::default()));
+    }
+
     #[test]
     fn test_keep_tags_across_multiple_documents() {
         let text = r#"
@@ -1219,4 +2258,62 @@ baz: "qux"
         }
         panic!("Test failed, did not encounter error")
     }
+
+    #[test]
+    fn test_document_without_tag_directives_after_tagged_one() {
+        let text = "
+%TAG !t! tag:test,2024:
+--- !t!1
+a: b
+...
+%YAML 1.2
+--- !t!2
+c: d
+";
+        // A document without `%TAG` directives keeps the tags of the previous one when they are
+        // kept across documents, even if it has other directives.
+        let handles: Vec<_> = Parser::new_from_str(text)
+            .keep_tags(true)
+            .filter_map(|x| match x.unwrap().0 {
+                Event::MappingStart(_, tag) => Some(tag.unwrap().handle.into_owned()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(handles, ["tag:test,2024:", "tag:test,2024:"]);
+
+        // Otherwise, the tags of the previous document no longer apply.
+        let mut parser = Parser::new_from_str(text);
+        let err = parser.find_map(Result::err).unwrap();
+        assert_eq!(err.marker().line(), 7);
+    }
+
+    #[test]
+    fn test_checkpoint_leaves_out_parsed_anchors() {
+        // Saving the state of the parser must not copy the anchors parsed so far, lest parsing
+        // incrementally take quadratic time.
+        let mut s = String::new();
+        for i in 0..1000 {
+            writeln!(s, "- &a{i} [*a{i}]").unwrap();
+        }
+        let mut input = ChunkedInput::new();
+        input.feed(s.as_bytes());
+        input.finish();
+        let mut p = Parser::new(input).collect_warnings(true);
+        for _ in 0..3 + 500 * 3 {
+            p.next_event().unwrap().unwrap();
+        }
+        assert_eq!(p.anchors.len(), 500);
+        let checkpoint = p.checkpoint();
+        assert!(checkpoint.parser.anchors.is_empty());
+        assert!(checkpoint.parser.unused_anchors.is_empty());
+        assert!(checkpoint.parser.anchor_names.is_empty());
+
+        // Rolling back restores them, without what was parsed after the checkpoint.
+        p.next_event().unwrap().unwrap();
+        assert_eq!(p.anchors.len(), 501);
+        p.rollback(checkpoint);
+        assert_eq!(p.anchors.len(), 500);
+        assert_eq!(p.anchor_names.len(), 500);
+        assert_eq!(p.anchor_name(500), Some("a499"));
+    }
 }
@@ -0,0 +1,388 @@
+//! Paths of events in documents, and extraction of the subtree at a path.
+//!
+//! A path is the list of mapping keys and sequence indices leading from the root of a document to
+//! a node. In the following document, the path of `nginx` is `spec`, `containers`, `0`, `image`:
+//!
+//! ```yaml
+//! spec:
+//!   containers:
+//!     - image: nginx
+//! ```
+//!
+//! [`PathTracker`] follows events and returns the path of each of them. [`SubtreeExtractor`] uses
+//! it to pass on only the events of the node at a given path, e.g. to a `saphyr` `YamlLoader` to
+//! load that node without building the rest of the document.
+
+use alloc::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+
+use crate::{Directives, Event, Span, SpannedEventReceiver};
+
+/// A step of a path from the root of a document to a node.
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub enum PathSegment<'input> {
+    /// The value of a mapping, with a scalar key.
+    ///
+    /// Keys are compared by value, regardless of their style and tag: `"1"`, `'1'` and `1` are all
+    /// `Key("1")`.
+    Key(Cow<'input, str>),
+    /// The item of a sequence at the given index.
+    Index(usize),
+    /// The value of a mapping, with a key that is not a scalar (i.e. a collection or an alias).
+    ComplexKey,
+}
+
+impl<'input> From<&'input str> for PathSegment<'input> {
+    fn from(key: &'input str) -> Self {
+        Self::Key(Cow::Borrowed(key))
+    }
+}
+
+impl From<usize> for PathSegment<'_> {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+/// An open collection, as tracked by [`PathTracker`].
+#[derive(Clone, Debug)]
+enum Frame<'input> {
+    /// A sequence, with the index of its next item.
+    Sequence(usize),
+    /// A mapping, with the segment of its next value if its key has been read.
+    Mapping(Option<PathSegment<'input>>),
+}
+
+/// Tracks the path of events in documents.
+///
+/// [`Self::on_event`] is given each event of the stream, in order, and returns its path:
+///   - Events of a node (scalars, aliases, and the start and end of collections) have the path of
+///     that node.
+///   - Events of a key have the path of the mapping they are a key of. Keys are not nodes of
+///     paths, they name the segment of their value.
+///   - Other events have the path of the collection they are in, if any, i.e. the empty path.
+///
+/// ```
+/// # use saphyr_parser::{path::{PathSegment, PathTracker}, Event, Parser};
+/// let mut tracker = PathTracker::new();
+/// let mut paths = vec![];
+/// for event in Parser::new_from_str("a: [b, {c: d}]") {
+///     let (ev, _) = event.unwrap();
+///     if let Event::Scalar(value, ..) = &ev {
+///         paths.push((value.to_string(), tracker.on_event(&ev).to_vec()));
+///     } else {
+///         tracker.on_event(&ev);
+///     }
+/// }
+/// assert_eq!(paths[1], ("b".into(), vec!["a".into(), 0.into()]));
+/// assert_eq!(paths[2], ("c".into(), vec!["a".into(), 1.into()]));
+/// assert_eq!(paths[3], ("d".into(), vec!["a".into(), 1.into(), "c".into()]));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PathTracker<'input> {
+    /// The path of the last event.
+    path: Vec<PathSegment<'input>>,
+    /// The collections that are open, outside of keys.
+    frames: Vec<Frame<'input>>,
+    /// The number of collections that are open within the collection key being read, if any.
+    key_depth: usize,
+    /// Whether the last segment of the path is that of the last node and must be popped.
+    pop: bool,
+}
+
+impl<'input> PathTracker<'input> {
+    /// Create a tracker at the start of a stream.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the path of the last event given to [`Self::on_event`].
+    #[must_use]
+    pub fn path(&self) -> &[PathSegment<'input>] {
+        &self.path
+    }
+
+    /// Return whether the last event given to [`Self::on_event`] is part of a mapping key.
+    #[must_use]
+    pub fn in_key(&self) -> bool {
+        self.key_depth > 0 || matches!(self.frames.last(), Some(Frame::Mapping(Some(_))))
+    }
+
+    /// Follow the next event of the stream and return its path.
+    pub fn on_event(&mut self, ev: &Event<'input>) -> &[PathSegment<'input>] {
+        if self.pop {
+            self.path.pop();
+            self.pop = false;
+        }
+
+        if self.key_depth > 0 {
+            match ev {
+                Event::SequenceStart(..) | Event::MappingStart(..) => self.key_depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => {
+                    self.key_depth -= 1;
+                    if self.key_depth == 0 {
+                        if let Some(Frame::Mapping(value)) = self.frames.last_mut() {
+                            *value = Some(PathSegment::ComplexKey);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return &self.path;
+        }
+
+        match ev {
+            Event::DocumentStart(..) => {
+                self.path.clear();
+                self.frames.clear();
+            }
+            Event::Scalar(..)
+            | Event::Alias(..)
+            | Event::SequenceStart(..)
+            | Event::MappingStart(..) => {
+                let segment = match self.frames.last_mut() {
+                    None => None,
+                    Some(Frame::Sequence(index)) => {
+                        *index += 1;
+                        Some(PathSegment::Index(*index - 1))
+                    }
+                    Some(Frame::Mapping(value)) => {
+                        if value.is_none() {
+                            // The node is a key.
+                            match ev {
+                                Event::Scalar(key, ..) => {
+                                    *value = Some(PathSegment::Key(key.clone()));
+                                }
+                                Event::Alias(..) => *value = Some(PathSegment::ComplexKey),
+                                _ => self.key_depth = 1,
+                            }
+                            return &self.path;
+                        }
+                        value.take()
+                    }
+                };
+                let nested = segment.is_some();
+                self.path.extend(segment);
+                match ev {
+                    Event::SequenceStart(..) => self.frames.push(Frame::Sequence(0)),
+                    Event::MappingStart(..) => self.frames.push(Frame::Mapping(None)),
+                    _ => self.pop = nested,
+                }
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.frames.pop();
+                self.pop = !self.frames.is_empty();
+            }
+            _ => {}
+        }
+        &self.path
+    }
+}
+
+/// An anchored node being recorded by [`SubtreeExtractor`].
+#[derive(Clone, Debug)]
+struct Recording<'input> {
+    /// The anchor ID of the node.
+    anchor_id: usize,
+    /// The number of collections of the node that are open.
+    depth: usize,
+    /// The events of the node so far.
+    events: Vec<(Event<'input>, Span)>,
+}
+
+/// A receiver that passes on the events of the nodes at a path, and drops the others.
+///
+/// The events of each node at the path are passed on as a document of their own, between a
+/// [`Event::DocumentStart`] (with the directives of the document the node is in) and an
+/// [`Event::DocumentEnd`]. [`Event::StreamStart`] and [`Event::StreamEnd`] are passed on as is.
+/// A receiver building documents from events (e.g. `saphyr`'s `YamlLoader`) thus builds one
+/// document per node at the path, e.g. one per document of the stream that has the path.
+///
+/// Aliases in the node may refer to anchors outside of it. The extractor records the events of
+/// anchored nodes and passes those on in place of such aliases.
+///
+/// ```
+/// # use saphyr_parser::{path::SubtreeExtractor, receiver::EventBuffer, Event, Parser};
+/// let input = "spec:\n  name: app\n  containers:\n    - image: nginx\n";
+/// let path = ["spec".into(), "containers".into(), 0.into(), "image".into()];
+/// let mut extractor = SubtreeExtractor::new(EventBuffer::new(), path);
+/// Parser::new_from_str(input).load(&mut extractor, true).unwrap();
+/// assert_eq!(extractor.matches(), 1);
+///
+/// let events = extractor.into_inner().into_events();
+/// assert!(matches!(&events[2].0, Event::Scalar(value, ..) if value == "nginx"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SubtreeExtractor<'input, R> {
+    /// The receiver events are passed on to.
+    inner: R,
+    /// The path of the nodes to pass on.
+    target: Vec<PathSegment<'input>>,
+    /// The tracker of the path of events.
+    tracker: PathTracker<'input>,
+    /// The directives of the current document.
+    directives: Directives<'input>,
+    /// The number of collections of the node being passed on that are open, if any.
+    depth: Option<usize>,
+    /// The number of nodes at the path found so far.
+    matches: usize,
+    /// The anchored nodes of the current document being recorded.
+    recordings: Vec<Recording<'input>>,
+    /// The events of the anchored nodes of the current document, by anchor ID.
+    anchored: BTreeMap<usize, Vec<(Event<'input>, Span)>>,
+    /// The anchor IDs the inner receiver knows in the current node.
+    known_anchors: BTreeSet<usize>,
+}
+
+impl<'input, R> SubtreeExtractor<'input, R> {
+    /// Create an extractor passing the events of the nodes at `path` on to `inner`.
+    #[must_use]
+    pub fn new<P>(inner: R, path: P) -> Self
+    where
+        P: IntoIterator<Item = PathSegment<'input>>,
+    {
+        Self {
+            inner,
+            target: path.into_iter().collect(),
+            tracker: PathTracker::new(),
+            directives: Directives::default(),
+            depth: None,
+            matches: 0,
+            recordings: Vec::new(),
+            anchored: BTreeMap::new(),
+            known_anchors: BTreeSet::new(),
+        }
+    }
+
+    /// Return the number of nodes at the path found so far.
+    #[must_use]
+    pub fn matches(&self) -> usize {
+        self.matches
+    }
+
+    /// Return a reference to the wrapped receiver.
+    #[must_use]
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return a mutable reference to the wrapped receiver.
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Return the wrapped receiver.
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Record the event if it is part of an anchored node.
+    fn record(&mut self, ev: &Event<'input>, span: Span) {
+        let mut idx = 0;
+        while idx < self.recordings.len() {
+            let recording = &mut self.recordings[idx];
+            recording.events.push((ev.clone(), span));
+            match ev {
+                Event::SequenceStart(..) | Event::MappingStart(..) => recording.depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => recording.depth -= 1,
+                _ => {}
+            }
+            if recording.depth == 0 {
+                let recording = self.recordings.remove(idx);
+                self.anchored.insert(recording.anchor_id, recording.events);
+            } else {
+                idx += 1;
+            }
+        }
+
+        match ev {
+            Event::Scalar(_, _, anchor_id, _) if *anchor_id > 0 => {
+                self.anchored.insert(*anchor_id, vec![(ev.clone(), span)]);
+            }
+            Event::SequenceStart(anchor_id, _) | Event::MappingStart(anchor_id, _)
+                if *anchor_id > 0 =>
+            {
+                self.recordings.push(Recording {
+                    anchor_id: *anchor_id,
+                    depth: 1,
+                    events: vec![(ev.clone(), span)],
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'input, R: SpannedEventReceiver<'input>> SubtreeExtractor<'input, R> {
+    /// Pass an event of a node at the path on, replacing aliases to anchors outside of the node.
+    fn pass_on(&mut self, ev: Event<'input>, span: Span) {
+        match &ev {
+            Event::Alias(id) if !self.known_anchors.contains(id) => {
+                if let Some(events) = self.anchored.get(id).cloned() {
+                    for (ev, span) in events {
+                        self.pass_on(ev, span);
+                    }
+                    return;
+                }
+            }
+            Event::Scalar(_, _, anchor_id, _)
+            | Event::SequenceStart(anchor_id, _)
+            | Event::MappingStart(anchor_id, _)
+                if *anchor_id > 0 =>
+            {
+                self.known_anchors.insert(*anchor_id);
+            }
+            _ => {}
+        }
+        self.inner.on_event(ev, span);
+    }
+}
+
+impl<'input, R: SpannedEventReceiver<'input>> SpannedEventReceiver<'input>
+    for SubtreeExtractor<'input, R>
+{
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        let at_target = self.tracker.on_event(&ev) == self.target.as_slice();
+        if let Event::DocumentStart(_, directives) = &ev {
+            self.directives = directives.clone();
+            self.recordings.clear();
+            self.anchored.clear();
+        }
+        self.record(&ev, span);
+
+        let depth = match (self.depth, &ev) {
+            (None, Event::StreamStart | Event::StreamEnd) => {
+                self.inner.on_event(ev, span);
+                return;
+            }
+            (None, Event::Scalar(..) | Event::Alias(..)) if at_target => 0,
+            (None, Event::SequenceStart(..) | Event::MappingStart(..)) if at_target => 1,
+            (None, _) => return,
+            (Some(depth), Event::SequenceStart(..) | Event::MappingStart(..)) => depth + 1,
+            (Some(depth), Event::SequenceEnd | Event::MappingEnd) => depth - 1,
+            (Some(depth), _) => depth,
+        };
+
+        if self.depth.is_none() {
+            self.matches += 1;
+            self.known_anchors.clear();
+            let start = Span::new(span.start, span.start);
+            self.inner
+                .on_event(Event::DocumentStart(false, self.directives.clone()), start);
+        }
+        self.pass_on(ev, span);
+        if depth == 0 {
+            self.depth = None;
+            self.inner
+                .on_event(Event::DocumentEnd, Span::new(span.end, span.end));
+        } else {
+            self.depth = Some(depth);
+        }
+    }
+}
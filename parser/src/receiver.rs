@@ -0,0 +1,360 @@
+//! Adapters to compose [`SpannedEventReceiver`]s.
+//!
+//! The adapters of this module are receivers that pass events on to the receivers they wrap, so
+//! that event pipelines can be built once and given to [`Parser::load`]:
+//!   - [`Tee`] sends each event to two receivers.
+//!   - [`FilterMap`] transforms or drops events before passing them on.
+//!   - [`DepthTracker`] keeps track of how deep in collections events are.
+//!   - [`EventBuffer`] records events, to be replayed into any receiver later.
+//!
+//! Adapters own the receivers they wrap. Receivers are borrowed back with the accessors of each
+//! adapter, or taken back with `into_inner`.
+//!
+//! ```
+//! # use saphyr_parser::{
+//! #     receiver::{DepthTracker, EventBuffer, FilterMap, Tee},
+//! #     Event, Parser,
+//! # };
+//! // Record the events of the input, and the scalars of the input on their own.
+//! let scalars = FilterMap::new(EventBuffer::new(), |ev, span| {
+//!     matches!(ev, Event::Scalar(..)).then_some((ev, span))
+//! });
+//! let mut pipeline = Tee::new(DepthTracker::new(EventBuffer::new()), scalars);
+//! Parser::new_from_str("a: [b, {c: d}]").load(&mut pipeline, true).unwrap();
+//!
+//! let (events, scalars) = pipeline.into_inner();
+//! assert_eq!(events.max_depth(), 3);
+//! assert_eq!(events.into_inner().len(), 14);
+//! assert_eq!(scalars.into_inner().len(), 4);
+//! ```
+//!
+//! [`Parser::load`]: crate::Parser::load
+
+use alloc::vec::Vec;
+use core::slice;
+
+use crate::{Event, Span, SpannedEventReceiver};
+
+/// A receiver that sends each event to two receivers.
+///
+/// The event is cloned for the first receiver and moved into the second one. More receivers can
+/// be fed by nesting tees.
+#[derive(Clone, Debug, Default)]
+pub struct Tee<A, B> {
+    /// The receiver that receives events first.
+    first: A,
+    /// The receiver that receives events second.
+    second: B,
+}
+
+impl<A, B> Tee<A, B> {
+    /// Create a tee sending events to `first`, then to `second`.
+    #[must_use]
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Return a reference to the receiver that receives events first.
+    #[must_use]
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Return a mutable reference to the receiver that receives events first.
+    pub fn first_mut(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    /// Return a reference to the receiver that receives events second.
+    #[must_use]
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// Return a mutable reference to the receiver that receives events second.
+    pub fn second_mut(&mut self) -> &mut B {
+        &mut self.second
+    }
+
+    /// Return the two receivers.
+    #[must_use]
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<'input, A, B> SpannedEventReceiver<'input> for Tee<A, B>
+where
+    A: SpannedEventReceiver<'input>,
+    B: SpannedEventReceiver<'input>,
+{
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        self.first.on_event(ev.clone(), span);
+        self.second.on_event(ev, span);
+    }
+}
+
+/// A receiver that transforms or drops events before passing them on.
+///
+/// The function is given each event and its span. It returns the event and span to pass on to the
+/// wrapped receiver, or `None` to drop the event.
+///
+/// Dropping events may leave the wrapped receiver with collections or documents that are not
+/// closed. Receivers that build nodes out of events (e.g. `saphyr`'s `YamlLoader`) expect every
+/// start event to have its end event.
+#[derive(Clone, Debug)]
+pub struct FilterMap<R, F> {
+    /// The receiver events are passed on to.
+    inner: R,
+    /// The function transforming events.
+    f: F,
+}
+
+impl<R, F> FilterMap<R, F> {
+    /// Create an adapter passing events transformed by `f` on to `inner`.
+    #[must_use]
+    pub fn new<'input>(inner: R, f: F) -> Self
+    where
+        F: FnMut(Event<'input>, Span) -> Option<(Event<'input>, Span)>,
+    {
+        Self { inner, f }
+    }
+
+    /// Return a reference to the wrapped receiver.
+    #[must_use]
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return a mutable reference to the wrapped receiver.
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Return the wrapped receiver.
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<'input, R, F> SpannedEventReceiver<'input> for FilterMap<R, F>
+where
+    R: SpannedEventReceiver<'input>,
+    F: FnMut(Event<'input>, Span) -> Option<(Event<'input>, Span)>,
+{
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        if let Some((ev, span)) = (self.f)(ev, span) {
+            self.inner.on_event(ev, span);
+        }
+    }
+}
+
+/// A receiver that keeps track of the nesting of collections while passing events on.
+///
+/// The depth is the number of collections that are open. It is 0 at the root of a document.
+#[derive(Clone, Debug, Default)]
+pub struct DepthTracker<R> {
+    /// The receiver events are passed on to.
+    inner: R,
+    /// The number of collections that are open.
+    depth: usize,
+    /// The highest depth reached so far.
+    max_depth: usize,
+}
+
+impl<R> DepthTracker<R> {
+    /// Create a tracker passing events on to `inner`.
+    #[must_use]
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            depth: 0,
+            max_depth: 0,
+        }
+    }
+
+    /// Return the number of collections that are open after the last event.
+    ///
+    /// The depth is updated before the event is passed on. A collection's start event increments
+    /// it, and its end event decrements it.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Return the highest depth reached so far.
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Return a reference to the wrapped receiver.
+    #[must_use]
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return a mutable reference to the wrapped receiver.
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Return the wrapped receiver.
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<'input, R: SpannedEventReceiver<'input>> SpannedEventReceiver<'input> for DepthTracker<R> {
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        match ev {
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                self.depth += 1;
+                self.max_depth = self.max_depth.max(self.depth);
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.depth = self.depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+        self.inner.on_event(ev, span);
+    }
+}
+
+/// A receiver that records events, to replay them into other receivers.
+///
+/// ```
+/// # use saphyr_parser::{receiver::EventBuffer, Event, Parser, ScanError};
+/// // Parsers are iterators of events, which can be collected into a buffer.
+/// let buffer = Parser::new_from_str("- a\n- b")
+///     .collect::<Result<EventBuffer, ScanError>>()
+///     .unwrap();
+/// assert!(matches!(buffer.events()[2].0, Event::SequenceStart(..)));
+///
+/// let mut docs = saphyr::YamlLoader::<saphyr::Yaml>::default();
+/// buffer.replay(&mut docs);
+/// assert_eq!(docs.into_documents()[0][1].as_str(), Some("b"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventBuffer<'input> {
+    /// The events recorded so far, along with their spans.
+    events: Vec<(Event<'input>, Span)>,
+}
+
+impl<'input> EventBuffer<'input> {
+    /// Create an empty buffer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the events recorded so far, along with their spans.
+    #[must_use]
+    pub fn events(&self) -> &[(Event<'input>, Span)] {
+        &self.events
+    }
+
+    /// Return an iterator over the events recorded so far, along with their spans.
+    pub fn iter(&self) -> slice::Iter<'_, (Event<'input>, Span)> {
+        self.events.iter()
+    }
+
+    /// Return the number of events recorded so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Return whether no events were recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Forget the events recorded so far.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Send copies of the recorded events to `recv`, in the order they were recorded.
+    ///
+    /// The buffer is left as is and can be replayed again.
+    pub fn replay<R: SpannedEventReceiver<'input>>(&self, recv: &mut R) {
+        for (ev, span) in &self.events {
+            recv.on_event(ev.clone(), *span);
+        }
+    }
+
+    /// Send the recorded events to `recv`, in the order they were recorded.
+    pub fn replay_into<R: SpannedEventReceiver<'input>>(self, recv: &mut R) {
+        for (ev, span) in self.events {
+            recv.on_event(ev, span);
+        }
+    }
+
+    /// Return a version of the buffer that owns all its events.
+    ///
+    /// See [`Event::into_owned`].
+    #[must_use]
+    pub fn into_owned(self) -> EventBuffer<'static> {
+        EventBuffer {
+            events: self
+                .events
+                .into_iter()
+                .map(|(ev, span)| (ev.into_owned(), span))
+                .collect(),
+        }
+    }
+
+    /// Return the recorded events, along with their spans.
+    #[must_use]
+    pub fn into_events(self) -> Vec<(Event<'input>, Span)> {
+        self.events
+    }
+}
+
+impl<'input> SpannedEventReceiver<'input> for EventBuffer<'input> {
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        self.events.push((ev, span));
+    }
+}
+
+impl<'input> From<Vec<(Event<'input>, Span)>> for EventBuffer<'input> {
+    fn from(events: Vec<(Event<'input>, Span)>) -> Self {
+        Self { events }
+    }
+}
+
+impl<'input> FromIterator<(Event<'input>, Span)> for EventBuffer<'input> {
+    fn from_iter<I: IntoIterator<Item = (Event<'input>, Span)>>(iter: I) -> Self {
+        Self {
+            events: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'input> Extend<(Event<'input>, Span)> for EventBuffer<'input> {
+    fn extend<I: IntoIterator<Item = (Event<'input>, Span)>>(&mut self, iter: I) {
+        self.events.extend(iter);
+    }
+}
+
+impl<'input> IntoIterator for EventBuffer<'input> {
+    type Item = (Event<'input>, Span);
+    type IntoIter = alloc::vec::IntoIter<(Event<'input>, Span)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.into_iter()
+    }
+}
+
+impl<'a, 'input> IntoIterator for &'a EventBuffer<'input> {
+    type Item = &'a (Event<'input>, Span);
+    type IntoIter = slice::Iter<'a, (Event<'input>, Span)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -9,14 +9,24 @@
 #![allow(clippy::cast_possible_wrap)]
 #![allow(clippy::cast_sign_loss)]
 
-use std::{char, collections::VecDeque, error::Error, fmt};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::VecDeque,
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::{char, fmt, ops::Range};
 
 use crate::{
     char_traits::{
         as_hex, is_anchor_char, is_blank_or_breakz, is_break, is_breakz, is_flow, is_hex,
         is_tag_char, is_uri_char,
     },
-    input::{Input, SkipTabs},
+    input::{is_quoted_scalar_stop, BorrowedInput, SkipTabs},
+    parser::ParserLimits,
+    warning::{push_warning, Warning, WarningKind},
 };
 
 /// The encoding of the input. Currently, only UTF-8 is supported.
@@ -47,6 +57,8 @@ pub enum TScalarStyle {
 pub struct Marker {
     /// The index (in chars) in the input string.
     index: usize,
+    /// The offset (in bytes) in the input string.
+    byte_offset: usize,
     /// The line (1-indexed).
     line: usize,
     /// The column (1-indexed).
@@ -55,17 +67,40 @@ pub struct Marker {
 
 impl Marker {
     /// Create a new [`Marker`] at the given position.
+    ///
+    /// The byte offset of the marker is set to `index`, which is only correct for ASCII input.
+    /// Use [`Marker::with_byte_offset`] to set it.
     #[must_use]
     pub fn new(index: usize, line: usize, col: usize) -> Marker {
-        Marker { index, line, col }
+        Marker {
+            index,
+            byte_offset: index,
+            line,
+            col,
+        }
+    }
+
+    /// Return a copy of the marker with the given byte offset.
+    #[must_use]
+    pub fn with_byte_offset(mut self, byte_offset: usize) -> Marker {
+        self.byte_offset = byte_offset;
+        self
     }
 
-    /// Return the index (in bytes) of the marker in the source.
+    /// Return the index (in chars) of the marker in the source.
     #[must_use]
     pub fn index(&self) -> usize {
         self.index
     }
 
+    /// Return the offset (in bytes) of the marker in the source.
+    ///
+    /// This is the offset in the UTF-8 encoding of the source and can be used to slice a `&str`.
+    #[must_use]
+    pub fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
     /// Return the line of the marker in the source.
     #[must_use]
     pub fn line(&self) -> usize {
@@ -95,6 +130,23 @@ impl Span {
         Span { start, end }
     }
 
+    /// Return the range of bytes the [`Span`] covers in the source.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let source = "key: välue";
+    /// let (_, span) = Parser::new_from_str(source)
+    ///     .map(Result::unwrap)
+    ///     .filter(|(ev, _)| matches!(ev, Event::Scalar(..)))
+    ///     .nth(1)
+    ///     .unwrap();
+    /// assert_eq!(&source[span.byte_range()], "välue");
+    /// ```
+    #[must_use]
+    pub fn byte_range(&self) -> Range<usize> {
+        self.start.byte_offset..self.end.byte_offset
+    }
+
     /// Create a empty [`Span`] at a given location.
     ///
     /// An empty span doesn't contain any characters, but its position may still be meaningful.
@@ -117,24 +169,54 @@ pub struct ScanError {
     mark: Marker,
     /// Human-readable details about the error.
     info: String,
+    /// The kind of error.
+    kind: ScanErrorKind,
 }
 
 impl ScanError {
     /// Create a new error from a location and an error string.
+    ///
+    /// The kind of the error is [`ScanErrorKind::Other`].
     #[must_use]
     pub fn new(loc: Marker, info: String) -> ScanError {
-        ScanError { mark: loc, info }
+        ScanError {
+            mark: loc,
+            info,
+            kind: ScanErrorKind::Other,
+        }
     }
 
     /// Convenience alias for string slices.
     #[must_use]
     pub fn new_str(loc: Marker, info: &str) -> ScanError {
+        ScanError::new(loc, info.to_owned())
+    }
+
+    /// Create a new error from a location and a kind.
+    ///
+    /// The information string is the [message](ScanErrorKind::message) of the kind.
+    #[must_use]
+    pub fn from_kind(loc: Marker, kind: ScanErrorKind) -> ScanError {
         ScanError {
             mark: loc,
-            info: info.to_owned(),
+            info: kind.message().to_owned(),
+            kind,
         }
     }
 
+    /// Set the kind of the error, keeping its information string (builder-style).
+    #[must_use]
+    pub fn with_kind(mut self, kind: ScanErrorKind) -> ScanError {
+        self.kind = kind;
+        self
+    }
+
+    /// Return the kind of the error.
+    #[must_use]
+    pub fn kind(&self) -> ScanErrorKind {
+        self.kind
+    }
+
     /// Return the marker pointing to the error in the source.
     #[must_use]
     pub fn marker(&self) -> &Marker {
@@ -148,8 +230,9 @@ impl ScanError {
     }
 }
 
-impl Error for ScanError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
+#[cfg(feature = "std")]
+impl std::error::Error for ScanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         None
     }
 }
@@ -160,16 +243,211 @@ impl fmt::Display for ScanError {
             formatter,
             "{} at byte {} line {} column {}",
             self.info,
-            self.mark.index,
+            self.mark.byte_offset,
             self.mark.line,
             self.mark.col + 1,
         )
     }
 }
 
+/// Define [`ScanErrorKind`] along with its code, message and expected tokens.
+///
+/// Each kind is given as `Name = "code", "message"`, optionally followed by the list of tokens
+/// that were expected. The message is used as the documentation of the variant.
+macro_rules! scan_error_kinds {
+    ($($name:ident = $code:literal, $message:literal $(, [$($expected:literal),*])?;)*) => {
+        /// The kind of a [`ScanError`].
+        ///
+        /// Kinds allow telling errors apart without matching on their message. Each kind has a
+        /// stable [`code`], a default [`message`] and, where it applies, the list of tokens that
+        /// were [`expected`] where the error occurred.
+        ///
+        /// [`code`]: Self::code
+        /// [`message`]: Self::message
+        /// [`expected`]: Self::expected
+        #[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum ScanErrorKind {
+            $(
+                #[doc = concat!("`` ", $message, " ``")]
+                $name,
+            )*
+        }
+
+        impl ScanErrorKind {
+            /// Return the stable code of the kind (e.g. `"E102"`).
+            ///
+            /// Codes never change meaning across versions. `E0xx` codes are for errors
+            /// without a specific kind, `E1xx` for errors raised by the scanner, `E2xx` for
+            /// errors raised by the parser, `E3xx` for exceeded [`ParserLimits`] and `E4xx` for
+            /// errors raised by the [`Input`].
+            ///
+            /// [`ParserLimits`]: crate::ParserLimits
+            /// [`Input`]: crate::Input
+            #[must_use]
+            pub fn code(self) -> &'static str {
+                match self {
+                    $(Self::$name => $code,)*
+                }
+            }
+
+            /// Return the default message for errors of this kind.
+            ///
+            /// This is the message of the [`ScanError`], unless the error holds more details.
+            #[must_use]
+            pub fn message(self) -> &'static str {
+                match self {
+                    $(Self::$name => $message,)*
+                }
+            }
+
+            /// Return the tokens that were expected when the error occurred.
+            ///
+            /// The slice is empty if the error is not about a missing token.
+            #[must_use]
+            pub fn expected(self) -> &'static [&'static str] {
+                match self {
+                    $(Self::$name => &[$($($expected),*)?],)*
+                }
+            }
+        }
+    };
+}
+
+scan_error_kinds! {
+    Other = "E000", "error";
+
+    InvalidContentAfterDocumentEnd = "E101", "invalid content after document end marker";
+    InvalidIndentation = "E102", "invalid indentation";
+    UnexpectedCharacter = "E103", "unexpected character";
+    MissingNextToken = "E104", "did not find expected next token";
+    SimpleKeyMissingColon = "E105", "simple key expect ':'", ["':'"];
+    SimpleKeyExpected = "E106", "simple key expected";
+    TabInBlockIndentation = "E107", "tabs disallowed within this context (block indentation)";
+    MissingWhitespace = "E108", "expected whitespace", ["whitespace"];
+    CommentWithoutWhitespace = "E109",
+        "comments must be separated from other tokens by whitespace", ["whitespace"];
+    DirectiveMissingLineBreak = "E110",
+        "while scanning a directive, did not find expected comment or line break",
+        ["comment", "line break"];
+    VersionDirectiveMissingDigitOrDot = "E111",
+        "while scanning a YAML directive, did not find expected digit or '.' character",
+        ["digit", "'.'"];
+    DirectiveMissingName = "E112",
+        "while scanning a directive, could not find expected directive name",
+        ["directive name"];
+    DirectiveNameNonAlphabetical = "E113",
+        "while scanning a directive, found unexpected non-alphabetical character";
+    VersionNumberTooLong = "E114",
+        "while scanning a YAML directive, found extremely long version number";
+    VersionDirectiveMissingNumber = "E115",
+        "while scanning a YAML directive, did not find expected version number",
+        ["version number"];
+    TagDirectiveMissingWhitespace = "E116",
+        "while scanning TAG, did not find expected whitespace or line break",
+        ["whitespace", "line break"];
+    TagMissingWhitespace = "E117",
+        "while scanning a tag, did not find expected whitespace or line break",
+        ["whitespace", "line break"];
+    TagMissingBang = "E118", "while scanning a tag, did not find expected '!'", ["'!'"];
+    TagDirectiveMissingBang = "E119",
+        "while parsing a tag directive, did not find expected '!'", ["'!'"];
+    InvalidGlobalTagCharacter = "E120", "invalid global tag character";
+    VerbatimTagMissingClosingBracket = "E121",
+        "while scanning a verbatim tag, did not find the expected '>'", ["'>'"];
+    TagMissingUri = "E122", "while parsing a tag, did not find expected tag URI", ["tag URI"];
+    InvalidTagEscape = "E123", "while parsing a tag, found an invalid escape sequence";
+    InvalidTagUtf8LeadingByte = "E124",
+        "while parsing a tag, found an incorrect leading UTF-8 byte";
+    InvalidTagUtf8TrailingByte = "E125",
+        "while parsing a tag, found an incorrect trailing UTF-8 byte";
+    InvalidTagUtf8Codepoint = "E126", "while parsing a tag, found an invalid UTF-8 codepoint";
+    InvalidAnchorName = "E127",
+        "while scanning an anchor or alias, did not find expected alphabetic or numeric character",
+        ["alphabetic or numeric character"];
+    RecursionLimitExceeded = "E128", "recursion limit exceeded";
+    BlockEntryOutsideBlock = "E129", "\"-\" is only valid inside a block";
+    BlockEntryNotAllowed = "E130", "block sequence entries are not allowed in this context";
+    InvalidAnchorIndentation = "E131", "invalid indentation for anchor";
+    BlockEntryMissingWhitespace = "E132",
+        "'-' must be followed by a valid YAML whitespace", ["whitespace"];
+    BlockScalarZeroIndentation = "E133",
+        "while scanning a block scalar, found an indentation indicator equal to 0";
+    BlockScalarMissingLineBreak = "E134",
+        "while scanning a block scalar, did not find expected comment or line break",
+        ["comment", "line break"];
+    BlockScalarStartsWithTab = "E135", "a block scalar content cannot start with a tab";
+    BlockScalarInvalidIndentation = "E136", "wrongly indented line in block scalar";
+    QuotedScalarDocumentIndicator = "E137",
+        "while scanning a quoted scalar, found unexpected document indicator";
+    QuotedScalarUnexpectedEof = "E138",
+        "while scanning a quoted scalar, found unexpected end of stream";
+    QuotedScalarInvalidIndentation = "E139", "invalid indentation in quoted scalar";
+    TabIndentation = "E140", "tab cannot be used as indentation";
+    QuotedScalarTrailingContent = "E141", "invalid trailing content after double-quoted scalar";
+    UnknownEscapeCharacter = "E142",
+        "while parsing a quoted scalar, found unknown escape character";
+    EscapeMissingHexNumber = "E143",
+        "while parsing a quoted scalar, did not find expected hexadecimal number",
+        ["hexadecimal number"];
+    InvalidUnicodeEscape = "E144",
+        "while parsing a quoted scalar, found invalid Unicode character escape code";
+    FlowInvalidIndentation = "E145", "invalid indentation in flow construct";
+    PlainScalarDashFlowIndicator = "E146", "plain scalar cannot start with '-' followed by ,[]{}";
+    PlainScalarTab = "E147", "while scanning a plain scalar, found a tab";
+    PlainScalarUnexpectedEnd = "E148", "unexpected end of plain scalar";
+    MappingKeyNotAllowed = "E149", "mapping keys are not allowed in this context";
+    TabNotAllowed = "E150", "tabs disallowed in this context";
+    ColonBeforeFlowCollection = "E151", "':' may not precede any of `[{` in flow mapping";
+    ColonMissingWhitespace = "E152",
+        "':' must be followed by a valid YAML whitespace", ["whitespace"];
+    IllegalColonPlacement = "E153", "illegal placement of ':' indicator";
+    MappingValueNotAllowed = "E154", "mapping values are not allowed in this context";
+
+    UnexpectedEof = "E201", "unexpected eof";
+    MissingStreamStart = "E202", "did not find expected <stream-start>", ["<stream-start>"];
+    MissingDocumentStart = "E203",
+        "did not find expected <document start>", ["<document start>"];
+    DuplicateVersionDirective = "E204", "duplicate version directive";
+    DuplicateTagDirective = "E205",
+        "the TAG directive must only be given at most once per handle in the same document";
+    MissingDocumentEndBeforeDirective = "E206",
+        "missing explicit document end marker before directive", ["'...'"];
+    UnknownAnchor = "E207", "while parsing node, found unknown anchor";
+    MissingNodeContent = "E208",
+        "while parsing a node, did not find expected node content", ["node content"];
+    BlockMappingMissingKey = "E209",
+        "while parsing a block mapping, did not find expected key", ["key"];
+    FlowMappingMissingSeparator = "E210",
+        "while parsing a flow mapping, did not find expected ',' or '}'", ["','", "'}'"];
+    FlowSequenceMissingSeparator = "E211",
+        "while parsing a flow sequence, expected ',' or ']'", ["','", "']'"];
+    BlockSequenceMissingEntry = "E212",
+        "while parsing a block collection, did not find expected '-' indicator", ["'-'"];
+    UndeclaredTagHandle = "E213", "the handle wasn't declared";
+
+    InputTooLong = "E301", "exceeded the maximum input length";
+    NestingTooDeep = "E302", "exceeded the maximum nesting depth";
+    ScalarTooLong = "E303", "exceeded the maximum scalar length";
+    TooManyEvents = "E304", "exceeded the maximum number of events per document";
+
+    InvalidUtf8 = "E401", "invalid UTF-8 sequence";
+    ReadFailed = "E402", "failed to read from the input";
+}
+
+impl ScanErrorKind {
+    /// Return whether the error was raised because one of the [`ParserLimits`] was exceeded.
+    pub(crate) fn is_limit(self) -> bool {
+        matches!(
+            self,
+            Self::InputTooLong | Self::NestingTooDeep | Self::ScalarTooLong | Self::TooManyEvents
+        )
+    }
+}
+
 /// The contents of a scanner token.
 #[derive(Clone, PartialEq, Debug, Eq)]
-pub enum TokenType {
+pub enum TokenType<'input> {
     /// The start of the stream. Sent first, before even [`TokenType::DocumentStart`].
     StreamStart(TEncoding),
     /// The end of the stream, EOF.
@@ -184,9 +462,9 @@ pub enum TokenType {
     /// A YAML tag directive (e.g.: `!!str`, `!foo!bar`, ...).
     TagDirective(
         /// Handle
-        String,
+        Cow<'input, str>,
         /// Prefix
-        String,
+        Cow<'input, str>,
     ),
     /// The start of a YAML document (`---`).
     DocumentStart,
@@ -219,23 +497,35 @@ pub enum TokenType {
     /// A value in a mapping.
     Value,
     /// A reference to an anchor.
-    Alias(String),
+    Alias(Cow<'input, str>),
     /// A YAML anchor (`&`/`*`).
-    Anchor(String),
+    Anchor(Cow<'input, str>),
     /// A YAML tag (starting with bangs `!`).
     Tag(
         /// The handle of the tag.
-        String,
+        Cow<'input, str>,
         /// The suffix of the tag.
-        String,
+        Cow<'input, str>,
     ),
     /// A regular YAML scalar.
-    Scalar(TScalarStyle, String),
+    Scalar(TScalarStyle, Cow<'input, str>),
+    /// A YAML comment (`# ...`).
+    ///
+    /// Comments are only emitted if the scanner was told to keep them (see
+    /// [`Scanner::keep_comments`]).
+    Comment(
+        /// The text of the comment, without the leading `#`.
+        Cow<'input, str>,
+        /// Whether the comment follows other content on the same line.
+        ///
+        /// This is `true` for `a: b # comment` and `false` for a comment on its own line.
+        bool,
+    ),
 }
 
 /// A scanner token.
 #[derive(Clone, PartialEq, Debug, Eq)]
-pub struct Token(pub Span, pub TokenType);
+pub struct Token<'input>(pub Span, pub TokenType<'input>);
 
 /// A scalar that was parsed and may correspond to a simple key.
 ///
@@ -362,7 +652,7 @@ struct Indent {
 ///
 /// [`FlowMappingStart`]: TokenType::FlowMappingStart
 /// [`FlowMappingEnd`]: TokenType::FlowMappingEnd
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 enum ImplicitMappingState {
     /// It is possible there is an implicit mapping.
     ///
@@ -384,9 +674,9 @@ enum ImplicitMappingState {
 ///
 /// It is however not a full parser and needs [`crate::parser::Parser`] to fully detect invalid
 /// YAML documents.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[allow(clippy::struct_excessive_bools)]
-pub struct Scanner<T> {
+pub struct Scanner<'input, T> {
     /// The input source.
     ///
     /// This must implement [`Input`].
@@ -399,7 +689,7 @@ pub struct Scanner<T> {
     /// instance, if we just read a scalar, it can be a value or a key if an implicit mapping
     /// follows. In this case, the token stays in the `VecDeque` but cannot be returned from
     /// [`Self::next`] until we have more context.
-    tokens: VecDeque<Token>,
+    tokens: VecDeque<Token<'input>>,
     /// The last error that happened.
     error: Option<ScanError>,
 
@@ -453,14 +743,36 @@ pub struct Scanner<T> {
     /// [`Possible`]: ImplicitMappingState::Possible
     /// [`Inside`]: ImplicitMappingState::Inside
     implicit_flow_mapping_states: Vec<ImplicitMappingState>,
+    /// Whether comments should be emitted as [`TokenType::Comment`] tokens.
+    keep_comments: bool,
+    /// Comments that were scanned but not yet pushed to [`Self::tokens`].
+    ///
+    /// Comments may be encountered while scanning another token (e.g.: after the closing quote
+    /// of a quoted scalar). Pushing them directly would break the indices used by
+    /// [`SimpleKey`]s. They are instead kept here and pushed after the token that is being
+    /// scanned.
+    pending_comments: Vec<Token<'input>>,
+    /// The location of a tab in the leading whitespace of the current line, if any.
+    ///
+    /// A [`WarningKind::Tab`] is emitted if the line turns out to have content.
+    leading_tab: Option<Marker>,
+    /// Whether warnings are collected into [`Self::warnings`].
+    collect_warnings: bool,
+    /// The warnings that were found so far.
+    warnings: Vec<Warning>,
+    /// The limits enforced while reading the input.
+    ///
+    /// The scanner enforces the nesting depth, the length of scalars and the length of the input.
+    /// The number of events per document is enforced by the [`crate::parser::Parser`].
+    limits: ParserLimits,
     buf_leading_break: String,
     buf_trailing_breaks: String,
     buf_whitespaces: String,
 }
 
-impl<T: Input> Iterator for Scanner<T> {
-    type Item = Token;
-    fn next(&mut self) -> Option<Token> {
+impl<'input, T: BorrowedInput<'input>> Iterator for Scanner<'input, T> {
+    type Item = Token<'input>;
+    fn next(&mut self) -> Option<Token<'input>> {
         if self.error.is_some() {
             return None;
         }
@@ -485,9 +797,9 @@ impl<T: Input> Iterator for Scanner<T> {
 /// A convenience alias for scanner functions that may fail without returning a value.
 pub type ScanResult = Result<(), ScanError>;
 
-impl<T: Input> Scanner<T> {
+impl<'input, T: BorrowedInput<'input>> Scanner<'input, T> {
     /// Creates the YAML tokenizer.
-    pub fn new(input: T) -> Scanner<T> {
+    pub fn new(input: T) -> Self {
         Scanner {
             input,
             mark: Marker::new(0, 1, 0),
@@ -507,6 +819,12 @@ impl<T: Input> Scanner<T> {
             leading_whitespace: true,
             flow_mapping_started: false,
             implicit_flow_mapping_states: vec![],
+            keep_comments: false,
+            pending_comments: vec![],
+            leading_tab: None,
+            collect_warnings: false,
+            warnings: vec![],
+            limits: ParserLimits::default(),
 
             buf_leading_break: String::new(),
             buf_trailing_breaks: String::new(),
@@ -514,6 +832,51 @@ impl<T: Input> Scanner<T> {
         }
     }
 
+    /// Whether to emit comments as [`TokenType::Comment`] tokens.
+    ///
+    /// By default, comments are skipped over as whitespace.
+    pub fn keep_comments(&mut self, value: bool) {
+        self.keep_comments = value;
+    }
+
+    /// Return whether comments are emitted as [`TokenType::Comment`] tokens.
+    #[inline]
+    pub fn keeps_comments(&self) -> bool {
+        self.keep_comments
+    }
+
+    /// Whether to collect warnings, to be retrieved with [`Self::take_warnings`].
+    ///
+    /// By default, no warning is collected.
+    pub fn collect_warnings(&mut self, value: bool) {
+        self.collect_warnings = value;
+    }
+
+    /// Return whether warnings are collected.
+    #[inline]
+    pub fn collects_warnings(&self) -> bool {
+        self.collect_warnings
+    }
+
+    /// Set the limits the scanner enforces as it reads the input.
+    ///
+    /// The nesting depth, the length of scalars and the length of the input are checked as
+    /// characters are consumed, so that an oversized scalar is rejected before it is read in full.
+    /// The number of events per document is left to the [`crate::parser::Parser`].
+    pub fn limits(&mut self, limits: ParserLimits) {
+        self.limits = limits;
+    }
+
+    /// Return the input of the scanner.
+    pub(crate) fn input(&self) -> &T {
+        &self.input
+    }
+
+    /// Return the input of the scanner, mutably.
+    pub(crate) fn input_mut(&mut self) -> &mut T {
+        &mut self.input
+    }
+
     /// Get a copy of the last error that was encountered, if any.
     ///
     /// This does not clear the error state and further calls to [`Self::get_error`] will return (a
@@ -523,21 +886,115 @@ impl<T: Input> Scanner<T> {
         self.error.clone()
     }
 
+    /// Return the warnings the scanner has found so far, leaving none in their place.
+    ///
+    /// Warnings are only collected if enabled with [`Self::collect_warnings`]. At most
+    /// [`MAX_BUFFERED_WARNINGS`](crate::MAX_BUFFERED_WARNINGS) are kept until they are taken.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        core::mem::take(&mut self.warnings)
+    }
+
+    /// Return the warnings the scanner has found so far, mutably.
+    pub(crate) fn warnings_mut(&mut self) -> &mut Vec<Warning> {
+        &mut self.warnings
+    }
+
+    /// Skip input after an error, up to a point where scanning can resume.
+    ///
+    /// Unless the scanner is at the start of a line past `error_line`, the rest of the current
+    /// line is skipped. Lines are then skipped until either:
+    ///   - A line whose content starts at one of `columns` is found. The scanner stops before its
+    ///     content and `Some(column)` is returned. Block indentation levels deeper than that
+    ///     column are dropped, without emitting [`TokenType::BlockEnd`] tokens.
+    ///   - A document indicator or a directive is found, or the end of the stream is reached.
+    ///     All block indentation levels are dropped and `None` is returned.
+    ///
+    /// In both cases, the error state, pending tokens, flow levels and simple keys are reset.
+    pub(crate) fn resync(&mut self, error_line: usize, columns: &[usize]) -> Option<usize> {
+        self.error = None;
+        self.tokens.clear();
+        self.token_available = false;
+        self.stream_end_produced = false;
+        self.pending_comments.clear();
+        self.flow_level = 0;
+        self.flow_mapping_started = false;
+        self.implicit_flow_mapping_states.clear();
+        self.adjacent_value_allowed_at = 0;
+
+        let mut skip_line = !self.leading_whitespace || self.mark.line <= error_line;
+        let column = loop {
+            if skip_line {
+                let n_chars = self.input.skip_while_non_breakz();
+                self.mark.index += n_chars;
+                self.mark.byte_offset = self.input.byte_offset();
+                self.mark.col += n_chars;
+                self.input.lookahead(2);
+                if self.input.next_is_z() {
+                    break None;
+                }
+                self.skip_linebreak();
+            }
+            skip_line = true;
+
+            let n_chars = self.input.skip_while_blank();
+            self.mark.index += n_chars;
+            self.mark.byte_offset = self.input.byte_offset();
+            self.mark.col += n_chars;
+            self.input.lookahead(4);
+            if self.input.next_is_z()
+                || (self.mark.col == 0
+                    && (self.input.next_is_document_indicator() || self.input.next_char_is('%')))
+            {
+                break None;
+            }
+            if !self.input.next_is_breakz()
+                && !self.input.next_char_is('#')
+                && columns.contains(&self.mark.col)
+            {
+                break Some(self.mark.col);
+            }
+        };
+
+        if let Some(col) = column {
+            while self.indent > col as isize {
+                self.indent = self.indents.pop().map_or(-1, |indent| indent.indent);
+            }
+        } else {
+            self.indents.clear();
+            self.indent = -1;
+        }
+        self.simple_keys.clear();
+        self.simple_keys.push(SimpleKey::new(self.mark));
+        self.allow_simple_key();
+        self.leading_whitespace = true;
+        column
+    }
+
     /// Consume the next character. It is assumed the next character is a blank.
     #[inline]
     fn skip_blank(&mut self) {
         self.input.skip();
 
         self.mark.index += 1;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += 1;
     }
 
+    /// Consume the next character. It is assumed the next character is a byte order mark.
+    fn skip_bom(&mut self) {
+        self.input.skip();
+
+        self.mark.index += 1;
+        self.mark.byte_offset = self.input.byte_offset();
+    }
+
     /// Consume the next character. It is assumed the next character is not a blank.
     #[inline]
     fn skip_non_blank(&mut self) {
         self.input.skip();
 
         self.mark.index += 1;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += 1;
         self.leading_whitespace = false;
     }
@@ -548,6 +1005,17 @@ impl<T: Input> Scanner<T> {
         self.input.skip_n(count);
 
         self.mark.index += count;
+        self.mark.byte_offset = self.input.byte_offset();
+        self.mark.col += count;
+        self.leading_whitespace = false;
+    }
+
+    /// Advance the marker past `count` characters the input consumed. It is assumed none of them
+    /// are blanks.
+    #[inline]
+    fn advance_non_blank(&mut self, count: usize) {
+        self.mark.index += count;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += count;
         self.leading_whitespace = false;
     }
@@ -558,6 +1026,7 @@ impl<T: Input> Scanner<T> {
         self.input.skip();
 
         self.mark.index += 1;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col = 0;
         self.mark.line += 1;
         self.leading_whitespace = true;
@@ -621,8 +1090,102 @@ impl<T: Input> Scanner<T> {
         self.skip_nl();
     }
 
+    /// Record a warning, if warnings are collected.
+    fn warn(&mut self, kind: WarningKind, span: Span, info: impl FnOnce() -> String) {
+        if self.collect_warnings {
+            push_warning(&mut self.warnings, kind, span, info);
+        }
+    }
+
+    /// Return an error if more than [`ParserLimits::max_input_len`] bytes were consumed.
+    fn check_input_len(&self) -> ScanResult {
+        match self.limits.input_len {
+            Some(max) if self.input.byte_offset() > max => Err(ScanError::new(
+                self.mark,
+                format!("exceeded the maximum input length of {max} bytes"),
+            )
+            .with_kind(ScanErrorKind::InputTooLong)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return an error if the scalar starting at `start_mark`, of which `len` bytes were read so
+    /// far, exceeds [`ParserLimits::max_scalar_len`], or if the input is too long.
+    fn check_scalar_len(&self, len: usize, start_mark: Marker) -> ScanResult {
+        match self.limits.scalar_len {
+            Some(max) if len > max => Err(ScanError::new(
+                start_mark,
+                format!("exceeded the maximum scalar length of {max} bytes"),
+            )
+            .with_kind(ScanErrorKind::ScalarTooLong)),
+            _ => self.check_input_len(),
+        }
+    }
+
+    /// Return an error if opening one more collection exceeds [`ParserLimits::max_depth`].
+    ///
+    /// Only the collections the scanner knows of are counted. The implicit mappings of flow
+    /// sequences (`[a: b]`) are left to the parser.
+    fn check_depth(&self, mark: Marker) -> ScanResult {
+        let Some(max) = self.limits.depth else {
+            return Ok(());
+        };
+        let blocks = self.indents.iter().filter(|x| x.needs_block_end).count();
+        if usize::from(self.flow_level) + blocks >= max {
+            return Err(ScanError::new(
+                mark,
+                format!("exceeded the maximum nesting depth of {max}"),
+            )
+            .with_kind(ScanErrorKind::NestingTooDeep));
+        }
+        Ok(())
+    }
+
+    /// Return whether blanks or line breaks may be buffered for a scalar of which `len` bytes
+    /// are buffered already.
+    ///
+    /// Blanks and line breaks only belong to a scalar if content follows them. Past
+    /// [`ParserLimits::max_scalar_len`], they are skipped without being buffered: should content
+    /// follow, the scalar is too long anyway.
+    fn may_buffer_scalar_blank(&self, len: usize) -> bool {
+        !self.limits.scalar_len.is_some_and(|max| len > max)
+    }
+
+    /// Same as [`Input::fetch_while_plain_scalar_chars`] and
+    /// [`Input::fetch_while_quoted_scalar_chars`], but enforces [`Self::limits`] after each
+    /// character rather than once the whole run was fetched.
+    ///
+    /// `is_stop` is called with 2 characters of lookahead and returns whether the next character
+    /// ends the run.
+    ///
+    /// [`Input::fetch_while_plain_scalar_chars`]: crate::input::Input::fetch_while_plain_scalar_chars
+    /// [`Input::fetch_while_quoted_scalar_chars`]: crate::input::Input::fetch_while_quoted_scalar_chars
+    fn fetch_while_checked(
+        &mut self,
+        out: &mut String,
+        start_mark: Marker,
+        is_stop: impl Fn(&T) -> bool,
+    ) -> Result<usize, ScanError> {
+        let mut n_chars = 0;
+        loop {
+            self.input.lookahead(2);
+            if is_stop(&self.input) {
+                return Ok(n_chars);
+            }
+            out.push(self.input.peek());
+            self.input.skip();
+            n_chars += 1;
+            self.check_scalar_len(out.len(), start_mark)?;
+        }
+    }
+
+    /// Return whether scalars must be checked against [`Self::limits`] as they are read.
+    fn limits_scalars(&self) -> bool {
+        self.limits.scalar_len.is_some() || self.limits.input_len.is_some()
+    }
+
     /// Insert a token at the given position.
-    fn insert_token(&mut self, pos: usize, tok: Token) {
+    fn insert_token(&mut self, pos: usize, tok: Token<'input>) {
         let old_len = self.tokens.len();
         assert!(pos <= old_len);
         self.tokens.insert(pos, tok);
@@ -641,6 +1204,19 @@ impl<T: Input> Scanner<T> {
     /// # Errors
     /// Returns `ScanError` when the scanner does not find the next expected token.
     pub fn fetch_next_token(&mut self) -> ScanResult {
+        // If the input failed, the error we got is most likely caused by the input ending early.
+        // Report the failure of the input instead.
+        self.fetch_next_token_impl()
+            .map_err(|e| self.input.error_at(self.mark).unwrap_or(e))?;
+        // Comments that were found while scanning the token come after it.
+        self.flush_pending_comments();
+        Ok(())
+    }
+
+    /// Implementation function for [`Self::fetch_next_token`].
+    ///
+    /// Comments encountered while fetching are stored in [`Self::pending_comments`].
+    fn fetch_next_token_impl(&mut self) -> ScanResult {
         self.input.lookahead(1);
 
         if !self.stream_start_produced {
@@ -657,12 +1233,19 @@ impl<T: Input> Scanner<T> {
 
         self.stale_simple_keys()?;
 
+        // Comments preceding the token are emitted before it, and before the end of the blocks we
+        // are about to leave.
+        self.flush_pending_comments();
+
         let mark = self.mark;
         self.unroll_indent(mark.col as isize);
 
         self.input.lookahead(4);
 
         if self.input.next_is_z() {
+            if let Some(e) = self.input.error_at(self.mark) {
+                return Err(e);
+            }
             self.fetch_stream_end()?;
             return Ok(());
         }
@@ -676,9 +1259,9 @@ impl<T: Input> Scanner<T> {
                 self.fetch_document_indicator(TokenType::DocumentEnd)?;
                 self.skip_ws_to_eol(SkipTabs::Yes)?;
                 if !self.input.next_is_breakz() {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::from_kind(
                         self.mark,
-                        "invalid content after document end marker",
+                        ScanErrorKind::InvalidContentAfterDocumentEnd,
                     ));
                 }
                 return Ok(());
@@ -686,7 +1269,10 @@ impl<T: Input> Scanner<T> {
         }
 
         if (self.mark.col as isize) < self.indent {
-            return Err(ScanError::new_str(self.mark, "invalid indentation"));
+            return Err(ScanError::from_kind(
+                self.mark,
+                ScanErrorKind::InvalidIndentation,
+            ));
         }
 
         let c = self.input.peek();
@@ -724,7 +1310,8 @@ impl<T: Input> Scanner<T> {
             '%' | '@' | '`' => Err(ScanError::new(
                 self.mark,
                 format!("unexpected character: `{c}'"),
-            )),
+            )
+            .with_kind(ScanErrorKind::UnexpectedCharacter)),
             _ => self.fetch_plain_scalar(),
         }
     }
@@ -732,7 +1319,7 @@ impl<T: Input> Scanner<T> {
     /// Return the next token in the stream.
     /// # Errors
     /// Returns `ScanError` when scanning fails to find an expected next token.
-    pub fn next_token(&mut self) -> Result<Option<Token>, ScanError> {
+    pub fn next_token(&mut self) -> Result<Option<Token<'input>>, ScanError> {
         if self.stream_end_produced {
             return Ok(None);
         }
@@ -741,9 +1328,9 @@ impl<T: Input> Scanner<T> {
             self.fetch_more_tokens()?;
         }
         let Some(t) = self.tokens.pop_front() else {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                "did not find expected next token",
+                ScanErrorKind::MissingNextToken,
             ));
         };
         self.token_available = false;
@@ -801,7 +1388,10 @@ impl<T: Input> Scanner<T> {
                     && (sk.mark.line < self.mark.line || sk.mark.index + 1024 < self.mark.index)
             {
                 if sk.required {
-                    return Err(ScanError::new_str(self.mark, "simple key expect ':'"));
+                    return Err(ScanError::from_kind(
+                        self.mark,
+                        ScanErrorKind::SimpleKeyMissingColon,
+                    ));
                 }
                 sk.possible = false;
             }
@@ -816,8 +1406,10 @@ impl<T: Input> Scanner<T> {
     /// one.
     fn skip_to_next_token(&mut self) -> ScanResult {
         loop {
-            // TODO(chenyh) BOM
+            self.check_input_len()?;
             match self.input.look_ch() {
+                // A byte order mark may start the stream. It does not count as a column.
+                '\u{FEFF}' if self.mark.index == 0 => self.skip_bom(),
                 // Tabs may not be used as indentation.
                 // "Indentation" only exists as long as a block is started, but does not exist
                 // inside of flow-style constructs. Tabs are allowed as part of leading
@@ -831,28 +1423,37 @@ impl<T: Input> Scanner<T> {
                     self.skip_ws_to_eol(SkipTabs::Yes)?;
                     // If we have content on that line with a tab, return an error.
                     if !self.input.next_is_breakz() {
-                        return Err(ScanError::new_str(
+                        return Err(ScanError::from_kind(
                             self.mark,
-                            "tabs disallowed within this context (block indentation)",
+                            ScanErrorKind::TabInBlockIndentation,
                         ));
                     }
                 }
+                '\t' if self.leading_whitespace => {
+                    self.leading_tab.get_or_insert(self.mark);
+                    self.skip_blank();
+                }
                 '\t' | ' ' => self.skip_blank(),
                 '\n' | '\r' => {
                     self.input.lookahead(2);
                     self.skip_linebreak();
+                    self.leading_tab = None;
                     if self.flow_level == 0 {
                         self.allow_simple_key();
                     }
                 }
                 '#' => {
-                    let comment_length = self.input.skip_while_non_breakz();
-                    self.mark.index += comment_length;
-                    self.mark.col += comment_length;
+                    self.leading_tab = None;
+                    self.skip_comment()?;
                 }
                 _ => break,
             }
         }
+        if let Some(mark) = self.leading_tab.take() {
+            self.warn(WarningKind::Tab, Span::new(mark, self.mark), || {
+                "tab before the content of a line".to_owned()
+            });
+        }
         Ok(())
     }
 
@@ -877,27 +1478,180 @@ impl<T: Input> Scanner<T> {
                     }
                     need_whitespace = false;
                 }
-                '#' => {
-                    let comment_length = self.input.skip_while_non_breakz();
-                    self.mark.index += comment_length;
-                    self.mark.col += comment_length;
-                }
+                '#' => self.skip_comment()?,
                 _ => break,
             }
         }
 
         if need_whitespace {
-            Err(ScanError::new_str(self.mark(), "expected whitespace"))
+            Err(ScanError::from_kind(
+                self.mark(),
+                ScanErrorKind::MissingWhitespace,
+            ))
         } else {
             Ok(())
         }
     }
 
     fn skip_ws_to_eol(&mut self, skip_tabs: SkipTabs) -> Result<SkipTabs, ScanError> {
+        if self.keep_comments {
+            return self.skip_ws_to_eol_keep_comments(skip_tabs);
+        }
         let (n_bytes, result) = self.input.skip_ws_to_eol(skip_tabs);
         self.mark.col += n_bytes;
         self.mark.index += n_bytes;
-        result.map_err(|msg| ScanError::new_str(self.mark, msg))
+        self.mark.byte_offset = self.input.byte_offset();
+        // The only error `Input::skip_ws_to_eol` may return is a comment not preceded by whitespace.
+        result.map_err(|_| ScanError::from_kind(self.mark, ScanErrorKind::CommentWithoutWhitespace))
+    }
+
+    /// Same as [`Input::skip_ws_to_eol`], but comments are scanned into tokens.
+    fn skip_ws_to_eol_keep_comments(&mut self, skip_tabs: SkipTabs) -> Result<SkipTabs, ScanError> {
+        let mut encountered_tab = false;
+        let mut has_yaml_ws = false;
+        loop {
+            match self.input.look_ch() {
+                ' ' => {
+                    has_yaml_ws = true;
+                    self.skip_blank();
+                }
+                '\t' if skip_tabs != SkipTabs::No => {
+                    encountered_tab = true;
+                    self.skip_blank();
+                }
+                // YAML comments must be preceded by whitespace.
+                '#' if !encountered_tab && !has_yaml_ws => {
+                    return Err(ScanError::from_kind(
+                        self.mark,
+                        ScanErrorKind::CommentWithoutWhitespace,
+                    ));
+                }
+                '#' => self.skip_comment()?,
+                _ => break,
+            }
+        }
+        Ok(SkipTabs::Result(encountered_tab, has_yaml_ws))
+    }
+
+    /// Skip over a comment, up to (but excluding) the next breakz.
+    ///
+    /// If comments are kept, the comment is stored in [`Self::pending_comments`].
+    ///
+    /// # Errors
+    /// Returns an error if the input exceeds [`ParserLimits::max_input_len`].
+    fn skip_comment(&mut self) -> ScanResult {
+        if !self.keep_comments {
+            let comment_length = if self.limits.input_len.is_some() {
+                self.skip_while_non_breakz_checked()?
+            } else {
+                self.input.skip_while_non_breakz()
+            };
+            self.mark.index += comment_length;
+            self.mark.byte_offset = self.input.byte_offset();
+            self.mark.col += comment_length;
+            return Ok(());
+        }
+
+        let start_mark = self.mark;
+        let trailing = !self.leading_whitespace;
+        // Skip over the `#`.
+        self.input.skip();
+        let start_offset = self.borrow_start();
+        let mut text = String::new();
+        let mut comment_length = 1;
+        if start_offset.is_some() {
+            comment_length += self.input.skip_while_non_breakz();
+        } else {
+            while !is_breakz(self.input.look_ch()) {
+                text.push(self.input.peek());
+                self.input.skip();
+                comment_length += 1;
+                self.check_input_len()?;
+            }
+        }
+        let text = self.borrow_since(start_offset, text);
+        self.mark.index += comment_length;
+        self.mark.byte_offset = self.input.byte_offset();
+        self.mark.col += comment_length;
+        self.pending_comments.push(Token(
+            Span::new(start_mark, self.mark),
+            TokenType::Comment(text, trailing),
+        ));
+        Ok(())
+    }
+
+    /// Same as [`Input::skip_while_non_breakz`], but checks [`ParserLimits::max_input_len`]
+    /// after each character.
+    ///
+    /// [`Input::skip_while_non_breakz`]: crate::input::Input::skip_while_non_breakz
+    fn skip_while_non_breakz_checked(&mut self) -> Result<usize, ScanError> {
+        let mut n_chars = 0;
+        while !is_breakz(self.input.look_ch()) {
+            self.input.skip();
+            n_chars += 1;
+            self.check_input_len()?;
+        }
+        Ok(n_chars)
+    }
+
+    /// Return the current byte offset if the input supports borrowing, `None` otherwise.
+    fn borrow_start(&self) -> Option<usize> {
+        let offset = self.input.byte_offset();
+        self.input.slice_bytes(offset, offset).map(|_| offset)
+    }
+
+    /// Return the input between the given byte offsets, or `owned` if it cannot be borrowed.
+    ///
+    /// `range` is `None` when the input does not support borrowing or when the contents we scanned
+    /// do not appear verbatim in the input (e.g. when escape sequences were processed). In that
+    /// case, `owned` must hold the contents.
+    fn borrow_or(&self, range: Option<(usize, usize)>, owned: String) -> Cow<'input, str> {
+        match range.and_then(|(start, end)| self.input.slice_bytes(start, end)) {
+            Some(slice) => Cow::Borrowed(slice),
+            None => Cow::Owned(owned),
+        }
+    }
+
+    /// Return the input between the byte offset `start` and the current position.
+    ///
+    /// See [`Self::borrow_or`].
+    fn borrow_since(&self, start: Option<usize>, owned: String) -> Cow<'input, str> {
+        self.borrow_or(start.map(|start| (start, self.input.byte_offset())), owned)
+    }
+
+    /// Stop borrowing the scalar being scanned and copy its contents so far into `string`.
+    ///
+    /// This must be called before pushing into `string` contents that do not appear verbatim in
+    /// the input.
+    fn materialize_scalar(&self, borrowed: &mut Option<(usize, usize)>, string: &mut String) {
+        if let Some((start, end)) = borrowed.take() {
+            string.push_str(self.input.slice_bytes(start, end).unwrap_or_default());
+        }
+    }
+
+    /// Same as [`Self::materialize_scalar`], but the borrowed part ends at the current position.
+    fn materialize_scalar_at_cursor(
+        &self,
+        borrowed: &mut Option<(usize, usize)>,
+        string: &mut String,
+    ) {
+        if let Some((_, end)) = borrowed.as_mut() {
+            *end = self.input.byte_offset();
+        }
+        self.materialize_scalar(borrowed, string);
+    }
+
+    /// Return the length, in bytes, of the scalar scanned so far.
+    ///
+    /// This is the length of `string`, or that of the `borrowed` range if the scalar is still
+    /// borrowed from the input.
+    fn scalar_len(string: &str, borrowed: Option<(usize, usize)>) -> usize {
+        string.len() + borrowed.map_or(0, |(start, end)| end - start)
+    }
+
+    /// Push the comments from [`Self::pending_comments`] into [`Self::tokens`].
+    fn flush_pending_comments(&mut self) {
+        self.tokens.extend(self.pending_comments.drain(..));
     }
 
     fn fetch_stream_start(&mut self) {
@@ -923,7 +1677,10 @@ impl<T: Input> Scanner<T> {
         // had. If one was required, however, that was an error and we must propagate it.
         for sk in &mut self.simple_keys {
             if sk.required && sk.possible {
-                return Err(ScanError::new_str(self.mark, "simple key expected"));
+                return Err(ScanError::from_kind(
+                    self.mark,
+                    ScanErrorKind::SimpleKeyExpected,
+                ));
             }
             sk.possible = false;
         }
@@ -949,7 +1706,7 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
-    fn scan_directive(&mut self) -> Result<Token, ScanError> {
+    fn scan_directive(&mut self) -> Result<Token<'input>, ScanError> {
         let start_mark = self.mark;
         self.skip_non_blank();
 
@@ -957,16 +1714,21 @@ impl<T: Input> Scanner<T> {
         let tok = match name.as_ref() {
             "YAML" => self.scan_version_directive_value(&start_mark)?,
             "TAG" => self.scan_tag_directive_value(&start_mark)?,
-            // XXX This should be a warning instead of an error
             _ => {
                 // skip current line
                 let line_len = self.input.skip_while_non_breakz();
                 self.mark.index += line_len;
+                self.mark.byte_offset = self.input.byte_offset();
                 self.mark.col += line_len;
+                self.warn(
+                    WarningKind::UnknownDirective,
+                    Span::new(start_mark, self.mark),
+                    || format!("ignoring unknown directive `%{name}`"),
+                );
                 // XXX return an empty TagDirective token
                 Token(
                     Span::new(start_mark, self.mark),
-                    TokenType::TagDirective(String::new(), String::new()),
+                    TokenType::TagDirective(Cow::Borrowed(""), Cow::Borrowed("")),
                 )
                 // return Err(ScanError::new_str(start_mark,
                 //     "while scanning a directive, found unknown directive name"))
@@ -980,24 +1742,25 @@ impl<T: Input> Scanner<T> {
             self.skip_linebreak();
             Ok(tok)
         } else {
-            Err(ScanError::new_str(
+            Err(ScanError::from_kind(
                 start_mark,
-                "while scanning a directive, did not find expected comment or line break",
+                ScanErrorKind::DirectiveMissingLineBreak,
             ))
         }
     }
 
-    fn scan_version_directive_value(&mut self, mark: &Marker) -> Result<Token, ScanError> {
+    fn scan_version_directive_value(&mut self, mark: &Marker) -> Result<Token<'input>, ScanError> {
         let n_blanks = self.input.skip_while_blank();
         self.mark.index += n_blanks;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += n_blanks;
 
         let major = self.scan_version_directive_number(mark)?;
 
         if self.input.peek() != '.' {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 *mark,
-                "while scanning a YAML directive, did not find expected digit or '.' character",
+                ScanErrorKind::VersionDirectiveMissingDigitOrDot,
             ));
         }
         self.skip_non_blank();
@@ -1016,19 +1779,20 @@ impl<T: Input> Scanner<T> {
 
         let n_chars = self.input.fetch_while_is_alpha(&mut string);
         self.mark.index += n_chars;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += n_chars;
 
         if string.is_empty() {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 start_mark,
-                "while scanning a directive, could not find expected directive name",
+                ScanErrorKind::DirectiveMissingName,
             ));
         }
 
         if !is_blank_or_breakz(self.input.peek()) {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 start_mark,
-                "while scanning a directive, found unexpected non-alphabetical character",
+                ScanErrorKind::DirectiveNameNonAlphabetical,
             ));
         }
 
@@ -1040,9 +1804,9 @@ impl<T: Input> Scanner<T> {
         let mut length = 0usize;
         while let Some(digit) = self.input.look_ch().to_digit(10) {
             if length + 1 > 9 {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     *mark,
-                    "while scanning a YAML directive, found extremely long version number",
+                    ScanErrorKind::VersionNumberTooLong,
                 ));
             }
             length += 1;
@@ -1051,24 +1815,26 @@ impl<T: Input> Scanner<T> {
         }
 
         if length == 0 {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 *mark,
-                "while scanning a YAML directive, did not find expected version number",
+                ScanErrorKind::VersionDirectiveMissingNumber,
             ));
         }
 
         Ok(val)
     }
 
-    fn scan_tag_directive_value(&mut self, mark: &Marker) -> Result<Token, ScanError> {
+    fn scan_tag_directive_value(&mut self, mark: &Marker) -> Result<Token<'input>, ScanError> {
         let n_blanks = self.input.skip_while_blank();
         self.mark.index += n_blanks;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += n_blanks;
 
         let handle = self.scan_tag_handle(true, mark)?;
 
         let n_blanks = self.input.skip_while_blank();
         self.mark.index += n_blanks;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += n_blanks;
 
         let prefix = self.scan_tag_prefix(mark)?;
@@ -1081,9 +1847,9 @@ impl<T: Input> Scanner<T> {
                 TokenType::TagDirective(handle, prefix),
             ))
         } else {
-            Err(ScanError::new_str(
+            Err(ScanError::from_kind(
                 *mark,
-                "while scanning TAG, did not find expected whitespace or line break",
+                ScanErrorKind::TagDirectiveMissingWhitespace,
             ))
         }
     }
@@ -1097,9 +1863,9 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
-    fn scan_tag(&mut self) -> Result<Token, ScanError> {
+    fn scan_tag(&mut self) -> Result<Token<'input>, ScanError> {
         let start_mark = self.mark;
-        let mut handle = String::new();
+        let mut handle = Cow::Borrowed("");
         let mut suffix;
 
         // Check if the tag is in the canonical form (verbatim).
@@ -1109,21 +1875,35 @@ impl<T: Input> Scanner<T> {
             suffix = self.scan_verbatim_tag(&start_mark)?;
         } else {
             // The tag has either the '!suffix' or the '!handle!suffix'
+            let handle_offset = self.borrow_start();
             handle = self.scan_tag_handle(false, &start_mark)?;
             // Check if it is, indeed, handle.
             if handle.len() >= 2 && handle.starts_with('!') && handle.ends_with('!') {
                 // A tag handle starting with "!!" is a secondary tag handle.
                 let is_secondary_handle = handle == "!!";
-                suffix =
-                    self.scan_tag_shorthand_suffix(false, is_secondary_handle, "", &start_mark)?;
+                let suffix_offset = self.borrow_start();
+                suffix = self.scan_tag_shorthand_suffix(
+                    false,
+                    is_secondary_handle,
+                    "",
+                    suffix_offset,
+                    &start_mark,
+                )?;
             } else {
-                suffix = self.scan_tag_shorthand_suffix(false, false, &handle, &start_mark)?;
-                "!".clone_into(&mut handle);
+                // The suffix starts right after the `!` of what we scanned as a handle.
+                suffix = self.scan_tag_shorthand_suffix(
+                    false,
+                    false,
+                    &handle,
+                    handle_offset.map(|offset| offset + 1),
+                    &start_mark,
+                )?;
+                handle = Cow::Borrowed("!");
                 // A special case: the '!' tag.  Set the handle to '' and the
                 // suffix to '!'.
                 if suffix.is_empty() {
-                    handle.clear();
-                    "!".clone_into(&mut suffix);
+                    handle = Cow::Borrowed("");
+                    suffix = Cow::Borrowed("!");
                 }
             }
         }
@@ -1137,40 +1917,54 @@ impl<T: Input> Scanner<T> {
                 TokenType::Tag(handle, suffix),
             ))
         } else {
-            Err(ScanError::new_str(
+            Err(ScanError::from_kind(
                 start_mark,
-                "while scanning a tag, did not find expected whitespace or line break",
+                ScanErrorKind::TagMissingWhitespace,
             ))
         }
     }
 
-    fn scan_tag_handle(&mut self, directive: bool, mark: &Marker) -> Result<String, ScanError> {
+    fn scan_tag_handle(
+        &mut self,
+        directive: bool,
+        mark: &Marker,
+    ) -> Result<Cow<'input, str>, ScanError> {
         let mut string = String::new();
         if self.input.look_ch() != '!' {
-            return Err(ScanError::new_str(
-                *mark,
-                "while scanning a tag, did not find expected '!'",
-            ));
+            return Err(ScanError::from_kind(*mark, ScanErrorKind::TagMissingBang));
         }
 
-        string.push(self.input.peek());
+        let start_offset = self.borrow_start();
+        if start_offset.is_none() {
+            string.push(self.input.peek());
+        }
         self.skip_non_blank();
 
-        let n_chars = self.input.fetch_while_is_alpha(&mut string);
+        let n_chars = if start_offset.is_none() {
+            self.input.fetch_while_is_alpha(&mut string)
+        } else {
+            self.input.skip_while_is_alpha()
+        };
         self.mark.index += n_chars;
+        self.mark.byte_offset = self.input.byte_offset();
         self.mark.col += n_chars;
 
         // Check if the trailing character is '!' and copy it.
-        if self.input.peek() == '!' {
-            string.push(self.input.peek());
+        let trailing_bang = self.input.peek() == '!';
+        if trailing_bang {
+            if start_offset.is_none() {
+                string.push(self.input.peek());
+            }
             self.skip_non_blank();
-        } else if directive && string != "!" {
+        }
+        let string = self.borrow_since(start_offset, string);
+        if !trailing_bang && directive && string != "!" {
             // It's either the '!' tag or not really a tag handle.  If it's a %TAG
             // directive, it's an error.  If it's a tag token, it must be a part of
             // URI.
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 *mark,
-                "while parsing a tag directive, did not find expected '!'",
+                ScanErrorKind::TagDirectiveMissingBang,
             ));
         }
         Ok(string)
@@ -1181,62 +1975,89 @@ impl<T: Input> Scanner<T> {
     /// There are 2 kinds of tag prefixes:
     ///   - Local: Starts with a `!`, contains only URI chars (`!foo`)
     ///   - Global: Starts with a tag char, contains then URI chars (`!foo,2000:app/`)
-    fn scan_tag_prefix(&mut self, start_mark: &Marker) -> Result<String, ScanError> {
+    fn scan_tag_prefix(&mut self, start_mark: &Marker) -> Result<Cow<'input, str>, ScanError> {
         let mut string = String::new();
+        let mut start_offset = self.borrow_start();
 
         if self.input.look_ch() == '!' {
             // If we have a local tag, insert and skip `!`.
-            string.push(self.input.peek());
-            self.skip_non_blank();
+            self.push_tag_char(start_offset, &mut string);
         } else if !is_tag_char(self.input.peek()) {
             // Otherwise, check if the first global tag character is valid.
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 *start_mark,
-                "invalid global tag character",
+                ScanErrorKind::InvalidGlobalTagCharacter,
             ));
         } else if self.input.peek() == '%' {
             // If it is valid and an escape sequence, escape it.
-            string.push(self.scan_uri_escapes(start_mark)?);
+            self.push_uri_escapes(&mut start_offset, &mut string, start_mark)?;
         } else {
             // Otherwise, push the first character.
-            string.push(self.input.peek());
-            self.skip_non_blank();
+            self.push_tag_char(start_offset, &mut string);
         }
 
         while is_uri_char(self.input.look_ch()) {
             if self.input.peek() == '%' {
-                string.push(self.scan_uri_escapes(start_mark)?);
+                self.push_uri_escapes(&mut start_offset, &mut string, start_mark)?;
             } else {
-                string.push(self.input.peek());
-                self.skip_non_blank();
+                self.push_tag_char(start_offset, &mut string);
             }
         }
 
-        Ok(string)
+        Ok(self.borrow_since(start_offset, string))
+    }
+
+    /// Skip the next character of a tag, copying it into `string` unless the tag is borrowed.
+    ///
+    /// `start_offset` is the byte offset of the start of the tag if it is borrowed from the input.
+    fn push_tag_char(&mut self, start_offset: Option<usize>, string: &mut String) {
+        if start_offset.is_none() {
+            string.push(self.input.peek());
+        }
+        self.skip_non_blank();
+    }
+
+    /// Scan a URI escape sequence in a tag and push the character it encodes into `string`.
+    ///
+    /// The decoded character does not appear verbatim in the input. If the tag was borrowed, it
+    /// stops being so and the part scanned so far is copied into `string`.
+    fn push_uri_escapes(
+        &mut self,
+        start_offset: &mut Option<usize>,
+        string: &mut String,
+        mark: &Marker,
+    ) -> Result<(), ScanError> {
+        if let Some(start) = start_offset.take() {
+            let end = self.input.byte_offset();
+            string.push_str(self.input.slice_bytes(start, end).unwrap_or_default());
+        }
+        string.push(self.scan_uri_escapes(mark)?);
+        Ok(())
     }
 
     /// Scan for a verbatim tag.
     ///
     /// The prefixing `!<` must _not_ have been skipped.
-    fn scan_verbatim_tag(&mut self, start_mark: &Marker) -> Result<String, ScanError> {
+    fn scan_verbatim_tag(&mut self, start_mark: &Marker) -> Result<Cow<'input, str>, ScanError> {
         // Eat `!<`
         self.skip_non_blank();
         self.skip_non_blank();
 
         let mut string = String::new();
+        let mut start_offset = self.borrow_start();
         while is_uri_char(self.input.look_ch()) {
             if self.input.peek() == '%' {
-                string.push(self.scan_uri_escapes(start_mark)?);
+                self.push_uri_escapes(&mut start_offset, &mut string, start_mark)?;
             } else {
-                string.push(self.input.peek());
-                self.skip_non_blank();
+                self.push_tag_char(start_offset, &mut string);
             }
         }
+        let string = self.borrow_since(start_offset, string);
 
         if self.input.peek() != '>' {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 *start_mark,
-                "while scanning a verbatim tag, did not find the expected '>'",
+                ScanErrorKind::VerbatimTagMissingClosingBracket,
             ));
         }
         self.skip_non_blank();
@@ -1244,42 +2065,44 @@ impl<T: Input> Scanner<T> {
         Ok(string)
     }
 
+    /// Scan the suffix of a tag shorthand.
+    ///
+    /// `head` is the part of the suffix that was already scanned, including a leading `!`.
+    /// `start_offset` is the byte offset of the start of the suffix (after that `!`) if the input
+    /// supports borrowing.
     fn scan_tag_shorthand_suffix(
         &mut self,
         _directive: bool,
         _is_secondary: bool,
         head: &str,
+        mut start_offset: Option<usize>,
         mark: &Marker,
-    ) -> Result<String, ScanError> {
+    ) -> Result<Cow<'input, str>, ScanError> {
         let mut length = head.len();
         let mut string = String::new();
 
         // Copy the head if needed.
         // Note that we don't copy the leading '!' character.
-        if length > 1 {
+        if length > 1 && start_offset.is_none() {
             string.extend(head.chars().skip(1));
         }
 
         while is_tag_char(self.input.look_ch()) {
             // Check if it is a URI-escape sequence.
             if self.input.peek() == '%' {
-                string.push(self.scan_uri_escapes(mark)?);
+                self.push_uri_escapes(&mut start_offset, &mut string, mark)?;
             } else {
-                string.push(self.input.peek());
-                self.skip_non_blank();
+                self.push_tag_char(start_offset, &mut string);
             }
 
             length += 1;
         }
 
         if length == 0 {
-            return Err(ScanError::new_str(
-                *mark,
-                "while parsing a tag, did not find expected tag URI",
-            ));
+            return Err(ScanError::from_kind(*mark, ScanErrorKind::TagMissingUri));
         }
 
-        Ok(string)
+        Ok(self.borrow_since(start_offset, string))
     }
 
     fn scan_uri_escapes(&mut self, mark: &Marker) -> Result<char, ScanError> {
@@ -1292,10 +2115,7 @@ impl<T: Input> Scanner<T> {
             let nc = self.input.peek_nth(2);
 
             if !(self.input.peek() == '%' && is_hex(c) && is_hex(nc)) {
-                return Err(ScanError::new_str(
-                    *mark,
-                    "while parsing a tag, found an invalid escape sequence",
-                ));
+                return Err(ScanError::from_kind(*mark, ScanErrorKind::InvalidTagEscape));
             }
 
             let byte = (as_hex(c) << 4) + as_hex(nc);
@@ -1306,18 +2126,18 @@ impl<T: Input> Scanner<T> {
                     _ if byte & 0xF0 == 0xE0 => 3,
                     _ if byte & 0xF8 == 0xF0 => 4,
                     _ => {
-                        return Err(ScanError::new_str(
+                        return Err(ScanError::from_kind(
                             *mark,
-                            "while parsing a tag, found an incorrect leading UTF-8 byte",
+                            ScanErrorKind::InvalidTagUtf8LeadingByte,
                         ));
                     }
                 };
                 code = byte;
             } else {
                 if byte & 0xc0 != 0x80 {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::from_kind(
                         *mark,
-                        "while parsing a tag, found an incorrect trailing UTF-8 byte",
+                        ScanErrorKind::InvalidTagUtf8TrailingByte,
                     ));
                 }
                 code = (code << 8) + byte;
@@ -1333,9 +2153,9 @@ impl<T: Input> Scanner<T> {
 
         match char::from_u32(code) {
             Some(ch) => Ok(ch),
-            None => Err(ScanError::new_str(
+            None => Err(ScanError::from_kind(
                 *mark,
-                "while parsing a tag, found an invalid UTF-8 codepoint",
+                ScanErrorKind::InvalidTagUtf8Codepoint,
             )),
         }
     }
@@ -1351,18 +2171,25 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
-    fn scan_anchor(&mut self, alias: bool) -> Result<Token, ScanError> {
+    fn scan_anchor(&mut self, alias: bool) -> Result<Token<'input>, ScanError> {
         let mut string = String::new();
         let start_mark = self.mark;
 
         self.skip_non_blank();
+        let start_offset = self.borrow_start();
         while is_anchor_char(self.input.look_ch()) {
-            string.push(self.input.peek());
+            if start_offset.is_none() {
+                string.push(self.input.peek());
+            }
             self.skip_non_blank();
         }
+        let string = self.borrow_since(start_offset, string);
 
         if string.is_empty() {
-            return Err(ScanError::new_str(start_mark, "while scanning an anchor or alias, did not find expected alphabetic or numeric character"));
+            return Err(ScanError::from_kind(
+                start_mark,
+                ScanErrorKind::InvalidAnchorName,
+            ));
         }
 
         let tok = if alias {
@@ -1373,7 +2200,7 @@ impl<T: Input> Scanner<T> {
         Ok(Token(Span::new(start_mark, self.mark), tok))
     }
 
-    fn fetch_flow_collection_start(&mut self, tok: TokenType) -> ScanResult {
+    fn fetch_flow_collection_start(&mut self, tok: TokenType<'input>) -> ScanResult {
         // The indicators '[' and '{' may start a simple key.
         self.save_simple_key();
 
@@ -1384,6 +2211,7 @@ impl<T: Input> Scanner<T> {
 
         let start_mark = self.mark;
         self.skip_non_blank();
+        let end_mark = self.mark;
 
         if tok == TokenType::FlowMappingStart {
             self.flow_mapping_started = true;
@@ -1394,12 +2222,13 @@ impl<T: Input> Scanner<T> {
 
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
+        // The token spans the indicator, not the blanks after it.
         self.tokens
-            .push_back(Token(Span::new(start_mark, self.mark), tok));
+            .push_back(Token(Span::new(start_mark, end_mark), tok));
         Ok(())
     }
 
-    fn fetch_flow_collection_end(&mut self, tok: TokenType) -> ScanResult {
+    fn fetch_flow_collection_end(&mut self, tok: TokenType<'input>) -> ScanResult {
         self.remove_simple_key()?;
         self.decrease_flow_level();
 
@@ -1413,6 +2242,7 @@ impl<T: Input> Scanner<T> {
 
         let start_mark = self.mark;
         self.skip_non_blank();
+        let end_mark = self.mark;
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
         // A flow collection within a flow mapping can be a key. In that case, the value may be
@@ -1425,7 +2255,7 @@ impl<T: Input> Scanner<T> {
         }
 
         self.tokens
-            .push_back(Token(Span::new(start_mark, self.mark), tok));
+            .push_back(Token(Span::new(start_mark, end_mark), tok));
         Ok(())
     }
 
@@ -1438,21 +2268,20 @@ impl<T: Input> Scanner<T> {
 
         let start_mark = self.mark;
         self.skip_non_blank();
+        let end_mark = self.mark;
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
-        self.tokens.push_back(Token(
-            Span::new(start_mark, self.mark),
-            TokenType::FlowEntry,
-        ));
+        self.tokens
+            .push_back(Token(Span::new(start_mark, end_mark), TokenType::FlowEntry));
         Ok(())
     }
 
     fn increase_flow_level(&mut self) -> ScanResult {
+        self.check_depth(self.mark)?;
         self.simple_keys.push(SimpleKey::new(Marker::new(0, 0, 0)));
-        self.flow_level = self
-            .flow_level
-            .checked_add(1)
-            .ok_or_else(|| ScanError::new_str(self.mark, "recursion limit exceeded"))?;
+        self.flow_level = self.flow_level.checked_add(1).ok_or_else(|| {
+            ScanError::from_kind(self.mark, ScanErrorKind::RecursionLimitExceeded)
+        })?;
         Ok(())
     }
 
@@ -1471,25 +2300,30 @@ impl<T: Input> Scanner<T> {
     fn fetch_block_entry(&mut self) -> ScanResult {
         if self.flow_level > 0 {
             // - * only allowed in block
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                r#""-" is only valid inside a block"#,
+                ScanErrorKind::BlockEntryOutsideBlock,
             ));
         }
         // Check if we are allowed to start a new entry.
         if !self.simple_key_allowed {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                "block sequence entries are not allowed in this context",
+                ScanErrorKind::BlockEntryNotAllowed,
             ));
         }
 
         // ???, fixes test G9HC.
-        if let Some(Token(span, TokenType::Anchor(..) | TokenType::Tag(..))) = self.tokens.back() {
+        if let Some(Token(span, TokenType::Anchor(..) | TokenType::Tag(..))) = self
+            .tokens
+            .iter()
+            .rev()
+            .find(|tok| !matches!(tok.1, TokenType::Comment(..)))
+        {
             if self.mark.col == 0 && span.start.col == 0 && self.indent > -1 {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     span.start,
-                    "invalid indentation for anchor",
+                    ScanErrorKind::InvalidAnchorIndentation,
                 ));
             }
         }
@@ -1499,14 +2333,14 @@ impl<T: Input> Scanner<T> {
         self.skip_non_blank();
 
         // generate BLOCK-SEQUENCE-START if indented
-        self.roll_indent(mark.col, None, TokenType::BlockSequenceStart, mark);
+        self.roll_indent(mark.col, None, TokenType::BlockSequenceStart, mark)?;
         let found_tabs = self.skip_ws_to_eol(SkipTabs::Yes)?.found_tabs();
         self.input.lookahead(2);
         if found_tabs && self.input.next_char_is('-') && is_blank_or_breakz(self.input.peek_nth(1))
         {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                "'-' must be followed by a valid YAML whitespace",
+                ScanErrorKind::BlockEntryMissingWhitespace,
             ));
         }
 
@@ -1525,7 +2359,7 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
-    fn fetch_document_indicator(&mut self, t: TokenType) -> ScanResult {
+    fn fetch_document_indicator(&mut self, t: TokenType<'input>) -> ScanResult {
         self.unroll_indent(-1);
         self.remove_simple_key()?;
         self.disallow_simple_key();
@@ -1543,12 +2377,25 @@ impl<T: Input> Scanner<T> {
         self.allow_simple_key();
         let tok = self.scan_block_scalar(literal)?;
 
+        // A comment on the header line is located before the contents of the scalar. Emit it
+        // first and move the simple key we may have saved along with the scalar token.
+        if !self.pending_comments.is_empty() {
+            let token_number = self.tokens_parsed + self.tokens.len();
+            if let Some(sk) = self
+                .simple_keys
+                .last_mut()
+                .filter(|sk| sk.possible && sk.token_number == token_number)
+            {
+                sk.token_number += self.pending_comments.len();
+            }
+            self.flush_pending_comments();
+        }
         self.tokens.push_back(tok);
         Ok(())
     }
 
     #[allow(clippy::too_many_lines)]
-    fn scan_block_scalar(&mut self, literal: bool) -> Result<Token, ScanError> {
+    fn scan_block_scalar(&mut self, literal: bool) -> Result<Token<'input>, ScanError> {
         let start_mark = self.mark;
         let mut chomping = Chomping::Clip;
         let mut increment: usize = 0;
@@ -1580,9 +2427,9 @@ impl<T: Input> Scanner<T> {
             self.input.lookahead(1);
             if self.input.next_is_digit() {
                 if self.input.peek() == '0' {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::from_kind(
                         start_mark,
-                        "while scanning a block scalar, found an indentation indicator equal to 0",
+                        ScanErrorKind::BlockScalarZeroIndentation,
                     ));
                 }
                 increment = (self.input.peek() as usize) - ('0' as usize);
@@ -1590,9 +2437,9 @@ impl<T: Input> Scanner<T> {
             }
         } else if self.input.next_is_digit() {
             if self.input.peek() == '0' {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     start_mark,
-                    "while scanning a block scalar, found an indentation indicator equal to 0",
+                    ScanErrorKind::BlockScalarZeroIndentation,
                 ));
             }
 
@@ -1614,9 +2461,9 @@ impl<T: Input> Scanner<T> {
         // Check if we are at the end of the line.
         self.input.lookahead(1);
         if !self.input.next_is_breakz() {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 start_mark,
-                "while scanning a block scalar, did not find expected comment or line break",
+                ScanErrorKind::BlockScalarMissingLineBreak,
             ));
         }
 
@@ -1626,9 +2473,9 @@ impl<T: Input> Scanner<T> {
         }
 
         if self.input.look_ch() == '\t' {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 start_mark,
-                "a block scalar content cannot start with a tab",
+                ScanErrorKind::BlockScalarStartsWithTab,
             ));
         }
 
@@ -1642,9 +2489,9 @@ impl<T: Input> Scanner<T> {
 
         // Scan the leading line breaks and determine the indentation level if needed.
         if indent == 0 {
-            self.skip_block_scalar_first_line_indent(&mut indent, &mut trailing_breaks);
+            self.skip_block_scalar_first_line_indent(&mut indent, &mut trailing_breaks)?;
         } else {
-            self.skip_block_scalar_indent(indent, &mut trailing_breaks);
+            self.skip_block_scalar_indent(indent, &mut trailing_breaks, 0)?;
         }
 
         // We have an end-of-stream with no content, e.g.:
@@ -1666,16 +2513,17 @@ impl<T: Input> Scanner<T> {
                 // Otherwise, the newline after chomping is ignored.
                 Chomping::Keep => trailing_breaks,
             };
+            self.check_scalar_len(contents.len(), start_mark)?;
             return Ok(Token(
                 Span::new(start_mark, self.mark),
-                TokenType::Scalar(style, contents),
+                TokenType::Scalar(style, Cow::Owned(contents)),
             ));
         }
 
         if self.mark.col < indent && (self.mark.col as isize) > self.indent {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                "wrongly indented line in block scalar",
+                ScanErrorKind::BlockScalarInvalidIndentation,
             ));
         }
 
@@ -1706,7 +2554,7 @@ impl<T: Input> Scanner<T> {
 
             leading_blank = self.input.next_is_blank();
 
-            self.scan_block_scalar_content_line(&mut string, &mut line_buffer);
+            self.scan_block_scalar_content_line(&mut string, &mut line_buffer, start_mark)?;
 
             // break on EOF
             self.input.lookahead(2);
@@ -1717,7 +2565,8 @@ impl<T: Input> Scanner<T> {
             self.read_break(&mut leading_break);
 
             // Eat the following indentation spaces and line breaks.
-            self.skip_block_scalar_indent(indent, &mut trailing_breaks);
+            let len = string.len() + leading_break.len();
+            self.skip_block_scalar_indent(indent, &mut trailing_breaks, len)?;
         }
 
         // Chomp the tail.
@@ -1734,10 +2583,11 @@ impl<T: Input> Scanner<T> {
         if chomping == Chomping::Keep {
             string.push_str(&trailing_breaks);
         }
+        self.check_scalar_len(string.len(), start_mark)?;
 
         Ok(Token(
             Span::new(start_mark, self.mark),
-            TokenType::Scalar(style, string),
+            TokenType::Scalar(style, Cow::Owned(string)),
         ))
     }
 
@@ -1750,7 +2600,15 @@ impl<T: Input> Scanner<T> {
     ///
     /// This function assumed the first character to read is the first content character in the
     /// line. This function does not consume the line break character(s) after the line.
-    fn scan_block_scalar_content_line(&mut self, string: &mut String, line_buffer: &mut String) {
+    ///
+    /// # Errors
+    /// Returns an error if the scalar starting at `start_mark` exceeds [`Self::limits`].
+    fn scan_block_scalar_content_line(
+        &mut self,
+        string: &mut String,
+        line_buffer: &mut String,
+        start_mark: Marker,
+    ) -> ScanResult {
         // Start by evaluating characters in the buffer.
         while !self.input.buf_is_empty() && !self.input.next_is_breakz() {
             string.push(self.input.peek());
@@ -1772,24 +2630,39 @@ impl<T: Input> Scanner<T> {
             // (using `String::push_str`).
             while let Some(c) = self.input.raw_read_non_breakz_ch() {
                 line_buffer.push(c);
+                self.check_scalar_len(string.len() + line_buffer.len(), start_mark)?;
             }
 
             // We need to manually update our position; we haven't called a `skip` function.
             let n_chars = line_buffer.chars().count();
             self.mark.col += n_chars;
             self.mark.index += n_chars;
+            self.mark.byte_offset = self.input.byte_offset();
 
             // We can now append our bytes to our `string`.
-            string.reserve(line_buffer.as_bytes().len());
+            string.reserve(line_buffer.len());
             string.push_str(line_buffer);
             // This clears the _contents_ without touching the _capacity_.
             line_buffer.clear();
         }
+        self.check_scalar_len(string.len(), start_mark)
     }
 
     /// Skip the block scalar indentation and empty lines.
-    fn skip_block_scalar_indent(&mut self, indent: usize, breaks: &mut String) {
+    ///
+    /// `len` is the length of the contents of the scalar so far, which bounds how many line
+    /// breaks are buffered (see [`Self::may_buffer_scalar_blank`]).
+    ///
+    /// # Errors
+    /// Returns an error if the input exceeds [`ParserLimits::max_input_len`].
+    fn skip_block_scalar_indent(
+        &mut self,
+        indent: usize,
+        breaks: &mut String,
+        len: usize,
+    ) -> ScanResult {
         loop {
+            self.check_input_len()?;
             // Consume all spaces. Tabs cannot be used as indentation.
             if indent < self.input.bufmaxlen() - 2 {
                 self.input.lookahead(self.input.bufmaxlen());
@@ -1817,12 +2690,15 @@ impl<T: Input> Scanner<T> {
                 self.input.lookahead(2);
             }
 
-            // If our current line is empty, skip over the break and continue looping.
-            if self.input.next_is_break() {
+            if !self.input.next_is_break() {
+                // We have a content line. Return control.
+                return Ok(());
+            }
+            // Our current line is empty, skip over the break and continue looping.
+            if self.may_buffer_scalar_blank(len + breaks.len()) {
                 self.read_break(breaks);
             } else {
-                // Otherwise, we have a content line. Return control.
-                break;
+                self.skip_break();
             }
         }
     }
@@ -1831,9 +2707,17 @@ impl<T: Input> Scanner<T> {
     ///
     /// The function skips over whitespace-only lines and sets `indent` to the the longest
     /// whitespace line that was encountered.
-    fn skip_block_scalar_first_line_indent(&mut self, indent: &mut usize, breaks: &mut String) {
+    ///
+    /// # Errors
+    /// Returns an error if the input exceeds [`ParserLimits::max_input_len`].
+    fn skip_block_scalar_first_line_indent(
+        &mut self,
+        indent: &mut usize,
+        breaks: &mut String,
+    ) -> ScanResult {
         let mut max_indent = 0;
         loop {
+            self.check_input_len()?;
             // Consume all spaces. Tabs cannot be used as indentation.
             while self.input.look_ch() == ' ' {
                 self.skip_blank();
@@ -1843,13 +2727,16 @@ impl<T: Input> Scanner<T> {
                 max_indent = self.mark.col;
             }
 
-            if self.input.next_is_break() {
-                // If our current line is empty, skip over the break and continue looping.
-                self.input.lookahead(2);
+            if !self.input.next_is_break() {
+                // We have a content line. Return control.
+                break;
+            }
+            // Our current line is empty, skip over the break and continue looping.
+            self.input.lookahead(2);
+            if self.may_buffer_scalar_blank(breaks.len()) {
                 self.read_break(breaks);
             } else {
-                // Otherwise, we have a content line. Return control.
-                break;
+                self.skip_break();
             }
         }
 
@@ -1865,6 +2752,7 @@ impl<T: Input> Scanner<T> {
         if self.indent > 0 {
             *indent = (*indent).max(1);
         }
+        Ok(())
     }
 
     fn fetch_flow_scalar(&mut self, single: bool) -> ScanResult {
@@ -1883,7 +2771,7 @@ impl<T: Input> Scanner<T> {
     }
 
     #[allow(clippy::too_many_lines)]
-    fn scan_flow_scalar(&mut self, single: bool) -> Result<Token, ScanError> {
+    fn scan_flow_scalar(&mut self, single: bool) -> Result<Token<'input>, ScanError> {
         let start_mark = self.mark;
 
         let mut string = String::new();
@@ -1894,29 +2782,32 @@ impl<T: Input> Scanner<T> {
 
         /* Eat the left quote. */
         self.skip_non_blank();
+        // As long as the scalar appears verbatim in the input, we only keep track of its byte
+        // offsets and leave `string` empty.
+        let mut borrowed = self.borrow_start().map(|offset| (offset, offset));
 
         loop {
             /* Check for a document indicator. */
             self.input.lookahead(4);
 
             if self.mark.col == 0 && self.input.next_is_document_indicator() {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     start_mark,
-                    "while scanning a quoted scalar, found unexpected document indicator",
+                    ScanErrorKind::QuotedScalarDocumentIndicator,
                 ));
             }
 
             if self.input.next_is_z() {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     start_mark,
-                    "while scanning a quoted scalar, found unexpected end of stream",
+                    ScanErrorKind::QuotedScalarUnexpectedEof,
                 ));
             }
 
             if (self.mark.col as isize) < self.indent {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     start_mark,
-                    "invalid indentation in quoted scalar",
+                    ScanErrorKind::QuotedScalarInvalidIndentation,
                 ));
             }
 
@@ -1924,6 +2815,7 @@ impl<T: Input> Scanner<T> {
             self.consume_flow_scalar_non_whitespace_chars(
                 single,
                 &mut string,
+                &mut borrowed,
                 &mut leading_blanks,
                 &start_mark,
             )?;
@@ -1935,26 +2827,36 @@ impl<T: Input> Scanner<T> {
             }
 
             // Consume blank characters.
+            let len = Self::scalar_len(&string, borrowed);
             while self.input.next_is_blank() || self.input.next_is_break() {
+                self.check_input_len()?;
+                let buffered = whitespaces.len() + leading_break.len() + trailing_breaks.len();
+                let may_buffer = self.may_buffer_scalar_blank(len + buffered);
                 if self.input.next_is_blank() {
                     // Consume a space or a tab character.
                     if leading_blanks {
                         if self.input.peek() == '\t' && (self.mark.col as isize) < self.indent {
-                            return Err(ScanError::new_str(
+                            return Err(ScanError::from_kind(
                                 self.mark,
-                                "tab cannot be used as indentation",
+                                ScanErrorKind::TabIndentation,
                             ));
                         }
                         self.skip_blank();
                     } else {
-                        whitespaces.push(self.input.peek());
+                        if borrowed.is_none() && may_buffer {
+                            whitespaces.push(self.input.peek());
+                        }
                         self.skip_blank();
                     }
                 } else {
                     self.input.lookahead(2);
                     // Check if it is a first line break.
                     if leading_blanks {
-                        self.read_break(&mut trailing_breaks);
+                        if may_buffer {
+                            self.read_break(&mut trailing_breaks);
+                        } else {
+                            self.skip_break();
+                        }
                     } else {
                         whitespaces.clear();
                         self.read_break(&mut leading_break);
@@ -1966,6 +2868,8 @@ impl<T: Input> Scanner<T> {
 
             // Join the whitespaces or fold line breaks.
             if leading_blanks {
+                // Line breaks are folded, the scalar no longer matches the input.
+                self.materialize_scalar(&mut borrowed, &mut string);
                 if leading_break.is_empty() {
                     string.push_str(&leading_break);
                     string.push_str(&trailing_breaks);
@@ -1980,6 +2884,8 @@ impl<T: Input> Scanner<T> {
                     }
                     leading_break.clear();
                 }
+            } else if let Some((_, end)) = borrowed.as_mut() {
+                *end = self.input.byte_offset();
             } else {
                 string.push_str(&whitespaces);
                 whitespaces.clear();
@@ -1988,6 +2894,7 @@ impl<T: Input> Scanner<T> {
 
         // Eat the right quote.
         self.skip_non_blank();
+        let end_mark = self.mark;
         // Ensure there is no invalid trailing content.
         self.skip_ws_to_eol(SkipTabs::Yes)?;
         match self.input.peek() {
@@ -2001,9 +2908,9 @@ impl<T: Input> Scanner<T> {
             // Inside a flow context, this is allowed.
             ':' if self.flow_level > 0 => {}
             _ => {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     self.mark,
-                    "invalid trailing content after double-quoted scalar",
+                    ScanErrorKind::QuotedScalarTrailingContent,
                 ));
             }
         }
@@ -2014,8 +2921,8 @@ impl<T: Input> Scanner<T> {
             TScalarStyle::DoubleQuoted
         };
         Ok(Token(
-            Span::new(start_mark, self.mark),
-            TokenType::Scalar(style, string),
+            Span::new(start_mark, end_mark),
+            TokenType::Scalar(style, self.borrow_or(borrowed, string)),
         ))
     }
 
@@ -2025,12 +2932,16 @@ impl<T: Input> Scanner<T> {
     /// of the stream or the closing character for the scalar (`'` for single quoted scalars, `"`
     /// for double quoted scalars).
     ///
+    /// Characters are pushed into `string` unless the scalar is `borrowed`, in which case its end
+    /// offset is updated instead. Escape sequences stop the borrowing.
+    ///
     /// # Errors
     /// Return an error if an invalid escape sequence is found.
     fn consume_flow_scalar_non_whitespace_chars(
         &mut self,
         single: bool,
         string: &mut String,
+        borrowed: &mut Option<(usize, usize)>,
         leading_blanks: &mut bool,
         start_mark: &Marker,
     ) -> Result<(), ScanError> {
@@ -2039,14 +2950,17 @@ impl<T: Input> Scanner<T> {
             match self.input.peek() {
                 // Check for an escaped single quote.
                 '\'' if self.input.peek_nth(1) == '\'' && single => {
+                    self.materialize_scalar_at_cursor(borrowed, string);
                     string.push('\'');
                     self.skip_n_non_blank(2);
+                    self.check_scalar_len(string.len(), *start_mark)?;
                 }
                 // Check for the right quote.
                 '\'' if single => break,
                 '"' if !single => break,
                 // Check for an escaped line break.
                 '\\' if !single && is_break(self.input.peek_nth(1)) => {
+                    self.materialize_scalar_at_cursor(borrowed, string);
                     self.input.lookahead(3);
                     self.skip_non_blank();
                     self.skip_linebreak();
@@ -2055,16 +2969,29 @@ impl<T: Input> Scanner<T> {
                 }
                 // Check for an escape sequence.
                 '\\' if !single => {
+                    self.materialize_scalar_at_cursor(borrowed, string);
                     string.push(self.resolve_flow_scalar_escape_sequence(start_mark)?);
+                    self.check_scalar_len(string.len(), *start_mark)?;
                 }
-                c => {
-                    string.push(c);
-                    self.skip_non_blank();
+                _ => {
+                    let n_chars = if borrowed.is_some() {
+                        self.input.skip_while_quoted_scalar_chars(single)
+                    } else if self.limits_scalars() {
+                        self.fetch_while_checked(string, *start_mark, |input| {
+                            is_quoted_scalar_stop(input.peek(), single)
+                        })?
+                    } else {
+                        self.input.fetch_while_quoted_scalar_chars(single, string)
+                    };
+                    self.advance_non_blank(n_chars);
                 }
             }
             self.input.lookahead(2);
         }
-        Ok(())
+        if let Some((_, end)) = borrowed.as_mut() {
+            *end = self.input.byte_offset();
+        }
+        self.check_scalar_len(Self::scalar_len(string, *borrowed), *start_mark)
     }
 
     /// Escape the sequence we encounter in a flow scalar.
@@ -2106,9 +3033,9 @@ impl<T: Input> Scanner<T> {
             'u' => code_length = 4,
             'U' => code_length = 8,
             _ => {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     *start_mark,
-                    "while parsing a quoted scalar, found unknown escape character",
+                    ScanErrorKind::UnknownEscapeCharacter,
                 ))
             }
         }
@@ -2121,18 +3048,18 @@ impl<T: Input> Scanner<T> {
             for i in 0..code_length {
                 let c = self.input.peek_nth(i);
                 if !is_hex(c) {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::from_kind(
                         *start_mark,
-                        "while parsing a quoted scalar, did not find expected hexadecimal number",
+                        ScanErrorKind::EscapeMissingHexNumber,
                     ));
                 }
                 value = (value << 4) + as_hex(c);
             }
 
             let Some(ch) = char::from_u32(value) else {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     *start_mark,
-                    "while parsing a quoted scalar, found invalid Unicode character escape code",
+                    ScanErrorKind::InvalidUnicodeEscape,
                 ));
             };
             ret = ch;
@@ -2157,19 +3084,26 @@ impl<T: Input> Scanner<T> {
     /// Plain scalars are the most readable but restricted style. They may span multiple lines in
     /// some contexts.
     #[allow(clippy::too_many_lines)]
-    fn scan_plain_scalar(&mut self) -> Result<Token, ScanError> {
+    fn scan_plain_scalar(&mut self) -> Result<Token<'input>, ScanError> {
         self.unroll_non_block_indents();
         let indent = self.indent + 1;
         let start_mark = self.mark;
 
         if self.flow_level > 0 && (start_mark.col as isize) < indent {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 start_mark,
-                "invalid indentation in flow construct",
+                ScanErrorKind::FlowInvalidIndentation,
             ));
         }
 
-        let mut string = String::with_capacity(32);
+        // As long as the scalar appears verbatim in the input, we only keep track of its byte
+        // offsets and leave `string` empty.
+        let mut borrowed = self.borrow_start().map(|offset| (offset, offset));
+        let mut string = if borrowed.is_some() {
+            String::new()
+        } else {
+            String::with_capacity(32)
+        };
         self.buf_whitespaces.clear();
         self.buf_leading_break.clear();
         self.buf_trailing_breaks.clear();
@@ -2184,9 +3118,9 @@ impl<T: Input> Scanner<T> {
             }
 
             if self.flow_level > 0 && self.input.peek() == '-' && is_flow(self.input.peek_nth(1)) {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     self.mark,
-                    "plain scalar cannot start with '-' followed by ,[]{}",
+                    ScanErrorKind::PlainScalarDashFlowIndicator,
                 ));
             }
 
@@ -2194,6 +3128,10 @@ impl<T: Input> Scanner<T> {
                 && self.input.next_can_be_plain_scalar(self.flow_level > 0)
             {
                 if self.leading_whitespace {
+                    if !self.buf_leading_break.is_empty() || !self.buf_trailing_breaks.is_empty() {
+                        // Line breaks are folded, the scalar no longer matches the input.
+                        self.materialize_scalar(&mut borrowed, &mut string);
+                    }
                     if self.buf_leading_break.is_empty() {
                         string.push_str(&self.buf_leading_break);
                         string.push_str(&self.buf_trailing_breaks);
@@ -2210,34 +3148,36 @@ impl<T: Input> Scanner<T> {
                     }
                     self.leading_whitespace = false;
                 } else if !self.buf_whitespaces.is_empty() {
-                    string.push_str(&self.buf_whitespaces);
+                    if borrowed.is_none() {
+                        string.push_str(&self.buf_whitespaces);
+                    }
                     self.buf_whitespaces.clear();
                 }
 
                 // We can unroll the first iteration of the loop.
-                string.push(self.input.peek());
+                if borrowed.is_none() {
+                    string.push(self.input.peek());
+                }
                 self.skip_non_blank();
-                string.reserve(self.input.bufmaxlen());
 
                 // Add content non-blank characters to the scalar.
-                let mut end = false;
-                while !end {
-                    // Fill the buffer once and process all characters in the buffer until the next
-                    // fetch. Note that `next_can_be_plain_scalar` needs 2 lookahead characters,
-                    // hence the `for` loop looping `self.input.bufmaxlen() - 1` times.
-                    self.input.lookahead(self.input.bufmaxlen());
-                    for _ in 0..self.input.bufmaxlen() - 1 {
-                        if self.input.next_is_blank_or_breakz()
-                            || !self.input.next_can_be_plain_scalar(self.flow_level > 0)
-                        {
-                            end = true;
-                            break;
-                        }
-                        string.push(self.input.peek());
-                        self.skip_non_blank();
-                    }
-                }
+                let in_flow = self.flow_level > 0;
+                let n_chars = if borrowed.is_some() {
+                    self.input.skip_while_plain_scalar_chars(in_flow)
+                } else if self.limits_scalars() {
+                    self.fetch_while_checked(&mut string, start_mark, |input| {
+                        input.next_is_blank_or_breakz() || !input.next_can_be_plain_scalar(in_flow)
+                    })?
+                } else {
+                    self.input
+                        .fetch_while_plain_scalar_chars(in_flow, &mut string)
+                };
+                self.advance_non_blank(n_chars);
                 end_mark = self.mark;
+                if let Some((_, end)) = borrowed.as_mut() {
+                    *end = self.input.byte_offset();
+                }
+                self.check_scalar_len(Self::scalar_len(&string, borrowed), start_mark)?;
             }
 
             // We may reach the end of a plain scalar if:
@@ -2249,20 +3189,28 @@ impl<T: Input> Scanner<T> {
             }
 
             // Process blank characters.
+            let len = Self::scalar_len(&string, borrowed);
             self.input.lookahead(2);
             while self.input.next_is_blank_or_break() {
+                self.check_input_len()?;
+                let buffered = self.buf_whitespaces.len()
+                    + self.buf_leading_break.len()
+                    + self.buf_trailing_breaks.len();
+                let may_buffer = self.may_buffer_scalar_blank(len + buffered);
                 if self.input.next_is_blank() {
                     if !self.leading_whitespace {
-                        self.buf_whitespaces.push(self.input.peek());
+                        if may_buffer {
+                            self.buf_whitespaces.push(self.input.peek());
+                        }
                         self.skip_blank();
                     } else if (self.mark.col as isize) < indent && self.input.peek() == '\t' {
                         // Tabs in an indentation columns are allowed if and only if the line is
                         // empty. Skip to the end of the line.
                         self.skip_ws_to_eol(SkipTabs::Yes)?;
                         if !self.input.next_is_breakz() {
-                            return Err(ScanError::new_str(
+                            return Err(ScanError::from_kind(
                                 start_mark,
-                                "while scanning a plain scalar, found a tab",
+                                ScanErrorKind::PlainScalarTab,
                             ));
                         }
                     } else {
@@ -2272,7 +3220,9 @@ impl<T: Input> Scanner<T> {
                     // Check if it is a first line break
                     if self.leading_whitespace {
                         self.skip_break();
-                        self.buf_trailing_breaks.push('\n');
+                        if may_buffer {
+                            self.buf_trailing_breaks.push('\n');
+                        }
                     } else {
                         self.buf_whitespaces.clear();
                         self.skip_break();
@@ -2293,18 +3243,18 @@ impl<T: Input> Scanner<T> {
             self.allow_simple_key();
         }
 
-        if string.is_empty() {
+        if borrowed.map_or(string.is_empty(), |(start, end)| start == end) {
             // `fetch_plain_scalar` must absolutely consume at least one byte. Otherwise,
             // `fetch_next_token` will never stop calling it. An empty plain scalar may happen with
             // erroneous inputs such as "{...".
-            Err(ScanError::new_str(
+            Err(ScanError::from_kind(
                 start_mark,
-                "unexpected end of plain scalar",
+                ScanErrorKind::PlainScalarUnexpectedEnd,
             ))
         } else {
             Ok(Token(
                 Span::new(start_mark, end_mark),
-                TokenType::Scalar(TScalarStyle::Plain, string),
+                TokenType::Scalar(TScalarStyle::Plain, self.borrow_or(borrowed, string)),
             ))
         }
     }
@@ -2314,9 +3264,9 @@ impl<T: Input> Scanner<T> {
         if self.flow_level == 0 {
             // Check if we are allowed to start a new key (not necessarily simple).
             if !self.simple_key_allowed {
-                return Err(ScanError::new_str(
+                return Err(ScanError::from_kind(
                     self.mark,
-                    "mapping keys are not allowed in this context",
+                    ScanErrorKind::MappingKeyNotAllowed,
                 ));
             }
             self.roll_indent(
@@ -2324,7 +3274,7 @@ impl<T: Input> Scanner<T> {
                 None,
                 TokenType::BlockMappingStart,
                 start_mark,
-            );
+            )?;
         } else {
             // The scanner, upon emitting a `Key`, will prepend a `MappingStart` event.
             self.flow_mapping_started = true;
@@ -2341,9 +3291,9 @@ impl<T: Input> Scanner<T> {
         self.skip_non_blank();
         self.skip_yaml_whitespace()?;
         if self.input.peek() == '\t' {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark(),
-                "tabs disallowed in this context",
+                ScanErrorKind::TabNotAllowed,
             ));
         }
         self.tokens
@@ -2373,9 +3323,9 @@ impl<T: Input> Scanner<T> {
         // flow character), but the ']' is not the value. The value is an invisible empty
         // space which is represented as null ('~').
         if self.mark.index != self.adjacent_value_allowed_at && (nc == '[' || nc == '{') {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                "':' may not precede any of `[{` in flow mapping",
+                ScanErrorKind::ColonBeforeFlowCollection,
             ));
         }
 
@@ -2398,9 +3348,9 @@ impl<T: Input> Scanner<T> {
             && !self.skip_ws_to_eol(SkipTabs::Yes)?.has_valid_yaml_ws()
             && (self.input.peek() == '-' || self.input.next_is_alpha())
         {
-            return Err(ScanError::new_str(
+            return Err(ScanError::from_kind(
                 self.mark,
-                "':' must be followed by a valid YAML whitespace",
+                ScanErrorKind::ColonMissingWhitespace,
             ));
         }
 
@@ -2410,9 +3360,9 @@ impl<T: Input> Scanner<T> {
             self.insert_token(sk.token_number - self.tokens_parsed, tok);
             if is_implicit_flow_mapping {
                 if sk.mark.line < start_mark.line {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::from_kind(
                         start_mark,
-                        "illegal placement of ':' indicator",
+                        ScanErrorKind::IllegalColonPlacement,
                     ));
                 }
                 self.insert_token(
@@ -2427,7 +3377,7 @@ impl<T: Input> Scanner<T> {
                 Some(sk.token_number),
                 TokenType::BlockMappingStart,
                 sk.mark,
-            );
+            )?;
             self.roll_one_col_indent();
 
             self.simple_keys.last_mut().unwrap().possible = false;
@@ -2440,9 +3390,9 @@ impl<T: Input> Scanner<T> {
             // The ':' indicator follows a complex key.
             if self.flow_level == 0 {
                 if !self.simple_key_allowed {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::from_kind(
                         start_mark,
-                        "mapping values are not allowed in this context",
+                        ScanErrorKind::MappingValueNotAllowed,
                     ));
                 }
 
@@ -2451,7 +3401,7 @@ impl<T: Input> Scanner<T> {
                     None,
                     TokenType::BlockMappingStart,
                     start_mark,
-                );
+                )?;
             }
             self.roll_one_col_indent();
 
@@ -2472,9 +3422,18 @@ impl<T: Input> Scanner<T> {
     /// An indentation level is added only if:
     ///   - We are not in a flow-style construct (which don't have indentation per-se).
     ///   - The current column is further indented than the last indent we have registered.
-    fn roll_indent(&mut self, col: usize, number: Option<usize>, tok: TokenType, mark: Marker) {
+    ///
+    /// # Errors
+    /// Returns an error if the new block exceeds [`ParserLimits::max_depth`].
+    fn roll_indent(
+        &mut self,
+        col: usize,
+        number: Option<usize>,
+        tok: TokenType<'input>,
+        mark: Marker,
+    ) -> ScanResult {
         if self.flow_level > 0 {
-            return;
+            return Ok(());
         }
 
         // If the last indent was a non-block indent, remove it.
@@ -2490,6 +3449,7 @@ impl<T: Input> Scanner<T> {
         }
 
         if self.indent < col as isize {
+            self.check_depth(mark)?;
             self.indents.push(Indent {
                 indent: self.indent,
                 needs_block_end: true,
@@ -2501,6 +3461,7 @@ impl<T: Input> Scanner<T> {
                 None => self.tokens.push_back(Token(Span::empty(mark), tok)),
             }
         }
+        Ok(())
     }
 
     /// Pop indentation levels from the stack as much as needed.
@@ -2528,7 +3489,7 @@ impl<T: Input> Scanner<T> {
     /// An indentation is not added if we are inside a flow level or if the last indent is already
     /// a non-block indent.
     fn roll_one_col_indent(&mut self) {
-        if self.flow_level == 0 && self.indents.last().map_or(false, |x| x.needs_block_end) {
+        if self.flow_level == 0 && self.indents.last().is_some_and(|x| x.needs_block_end) {
             self.indents.push(Indent {
                 indent: self.indent,
                 needs_block_end: false,
@@ -2567,7 +3528,10 @@ impl<T: Input> Scanner<T> {
     fn remove_simple_key(&mut self) -> ScanResult {
         let last = self.simple_keys.last_mut().unwrap();
         if last.possible && last.required {
-            return Err(ScanError::new_str(self.mark, "simple key expected"));
+            return Err(ScanError::from_kind(
+                self.mark,
+                ScanErrorKind::SimpleKeyExpected,
+            ));
         }
 
         last.possible = false;
@@ -0,0 +1,113 @@
+//! Non-fatal issues found in valid YAML.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::scanner::Span;
+
+/// The number of warnings buffered until they are taken.
+///
+/// The [`Parser`], its [`Scanner`] and loaders each buffer up to this many warnings. Warnings found
+/// past that are dropped, so that an input full of suspicious constructs cannot make the buffers
+/// grow without bound. Taking the warnings makes room for new ones.
+///
+/// [`Parser`]: crate::Parser
+/// [`Scanner`]: crate::Scanner
+pub const MAX_BUFFERED_WARNINGS: usize = 1000;
+
+/// The kind of a [`Warning`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// A `%YAML` directive declares a version other than 1.1 or 1.2.
+    ///
+    /// The document is parsed as YAML 1.2 nonetheless.
+    UnknownVersion,
+    /// A directive other than `%YAML` and `%TAG` was ignored.
+    UnknownDirective,
+    /// An anchor is defined again in the same document.
+    ///
+    /// Aliases that follow refer to the latest definition.
+    RedefinedAnchor,
+    /// An anchor is not referred to by any alias in its document.
+    UnusedAnchor,
+    /// A tab precedes the content of a line.
+    ///
+    /// This is valid where indentation does not matter (e.g. in flow collections), but the tab may
+    /// be mistaken for indentation.
+    Tab,
+    /// A plain scalar has a different type in YAML 1.1 and in YAML 1.2 (e.g. `yes`, `0777`).
+    ///
+    /// Warnings of this kind are emitted by loaders, which resolve the type of scalars.
+    AmbiguousScalar,
+}
+
+/// A non-fatal issue found in the input.
+///
+/// Warnings are collected alongside parsing when enabled with [`Parser::collect_warnings`], and
+/// retrieved with [`Parser::take_warnings`]. They do not affect the events.
+///
+/// [`Parser::collect_warnings`]: crate::Parser::collect_warnings
+/// [`Parser::take_warnings`]: crate::Parser::take_warnings
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct Warning {
+    /// The kind of warning.
+    kind: WarningKind,
+    /// The span of the input the warning is about.
+    span: Span,
+    /// Human-readable details about the warning.
+    info: String,
+}
+
+impl Warning {
+    /// Create a new warning.
+    #[must_use]
+    pub fn new(kind: WarningKind, span: Span, info: String) -> Self {
+        Self { kind, span, info }
+    }
+
+    /// Return the kind of the warning.
+    #[must_use]
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+
+    /// Return the span of the input the warning is about.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Return the information string describing the warning.
+    #[must_use]
+    pub fn info(&self) -> &str {
+        &self.info
+    }
+}
+
+/// Push a new warning to `warnings`, unless [`MAX_BUFFERED_WARNINGS`] are buffered already.
+///
+/// The message is only built if the warning is kept.
+pub(crate) fn push_warning(
+    warnings: &mut Vec<Warning>,
+    kind: WarningKind,
+    span: Span,
+    info: impl FnOnce() -> String,
+) {
+    if warnings.len() < MAX_BUFFERED_WARNINGS {
+        warnings.push(Warning::new(kind, span, info()));
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} at byte {} line {} column {}",
+            self.info,
+            self.span.start.byte_offset(),
+            self.span.start.line(),
+            self.span.start.col() + 1,
+        )
+    }
+}
@@ -0,0 +1,165 @@
+#![cfg(feature = "async")]
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{executor::block_on, io::AsyncRead};
+use saphyr_parser::{AsyncParser, Event, Parser, ParserLimits, ScanError, ScanErrorKind, Span};
+
+/// A reader that returns at most `chunk` bytes per read, is pending every other poll and fails
+/// once `fail_at` bytes were read.
+struct SlowReader<'a> {
+    bytes: &'a [u8],
+    chunk: usize,
+    pending: bool,
+    fail_at: Option<usize>,
+}
+
+impl<'a> SlowReader<'a> {
+    fn new(bytes: &'a [u8], chunk: usize) -> Self {
+        Self {
+            bytes,
+            chunk,
+            pending: false,
+            fail_at: None,
+        }
+    }
+}
+
+impl AsyncRead for SlowReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if self.fail_at == Some(0) {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "connection reset",
+            )));
+        }
+        let mut n = self.bytes.len().min(buf.len()).min(self.chunk);
+        if let Some(fail_at) = self.fail_at {
+            n = n.min(fail_at);
+            self.fail_at = Some(fail_at - n);
+        }
+        buf[..n].copy_from_slice(&self.bytes[..n]);
+        self.bytes = &self.bytes[n..];
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Collect the events of `parser`, stopping at the first error.
+fn run<R: AsyncRead + Unpin>(
+    mut parser: AsyncParser<R>,
+) -> Result<Vec<(Event<'static>, Span)>, ScanError> {
+    block_on(async {
+        let mut events = vec![];
+        while let Some(event) = parser.next_event_async().await {
+            events.push(event?);
+        }
+        Ok(events)
+    })
+}
+
+#[test]
+fn test_same_events_as_sync() {
+    let s = "\
+%YAML 1.2
+--- !!map
+k\u{e9}y: &a [\u{1F600}, \"\u{3042}\\u00e9\"]
+block: |
+  line 1
+  line 2
+plain: multi
+  line
+alias: *a
+...
+# comment
+---
+- {a: b, c: d}
+";
+    let expected = Parser::new_from_str(s)
+        .map(|x| x.map(|(ev, span)| (ev.into_owned(), span)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    for chunk in [1, 2, 3, 7, 1024] {
+        let parser = AsyncParser::new(SlowReader::new(s.as_bytes(), chunk));
+        assert_eq!(run(parser).unwrap(), expected, "chunks of {chunk} bytes");
+    }
+
+    // Inputs larger than the read buffer.
+    let s = "- abc\u{e9}\n".repeat(4096);
+    let parser = AsyncParser::new(s.as_bytes());
+    assert_eq!(run(parser).unwrap().len(), 4096 + 6);
+}
+
+#[test]
+fn test_many_anchors() {
+    let s: String = (0..2000)
+        .map(|i| format!("--- &doc{i}\nkey: &val{i} value\nlist: [&item{i} a, *val{i}]\n"))
+        .collect();
+    let expected = Parser::new_from_str(&s)
+        .map(|x| x.map(|(ev, span)| (ev.into_owned(), span)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let events = run(AsyncParser::new(SlowReader::new(s.as_bytes(), 256))).unwrap();
+    assert_eq!(events.len(), 2000 * 11 + 2);
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn test_options() {
+    let s = "a: [b]\n# comment\n";
+    let parser = AsyncParser::new(SlowReader::new(s.as_bytes(), 1)).keep_comments(true);
+    let events = run(parser).unwrap();
+    assert!(events
+        .iter()
+        .any(|(ev, _)| matches!(ev, Event::Comment(..))));
+
+    let parser = AsyncParser::new(s.as_bytes()).limits(ParserLimits::new().max_depth(1));
+    assert_eq!(
+        run(parser).unwrap_err().kind(),
+        ScanErrorKind::NestingTooDeep
+    );
+}
+
+#[test]
+fn test_input_errors() {
+    let parser = AsyncParser::new(SlowReader::new(b"a: \"b\xFFc\"", 1));
+    let err = run(parser).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::InvalidUtf8);
+    assert_eq!(err.marker().byte_offset(), 5);
+
+    // Truncated sequences at the end of the input.
+    let parser = AsyncParser::new(SlowReader::new(b"a: b\xE3\x81", 1));
+    let err = run(parser).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::InvalidUtf8);
+    assert_eq!(err.info(), "invalid UTF-8 sequence starting with byte 0xE3");
+
+    let mut reader = SlowReader::new(b"a: b\nc: d\n", 2);
+    reader.fail_at = Some(7);
+    let err = run(AsyncParser::new(reader)).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::ReadFailed);
+    assert_eq!(
+        err.info(),
+        "failed to read from the input: connection reset"
+    );
+    assert_eq!(err.marker().byte_offset(), 7);
+}
+
+#[test]
+fn test_send() {
+    fn assert_send<T: Send>(_: &T) {}
+    let mut parser = AsyncParser::new(&b"a: b"[..]);
+    assert_send(&parser);
+    assert_send(&parser.next_event_async());
+}
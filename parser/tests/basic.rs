@@ -1,17 +1,29 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr_parser::{Event, Parser, ScanError, TScalarStyle};
+use std::{borrow::Cow, io::Read};
+
+use saphyr_parser::{
+    cst::{self, GreenToken, SyntaxKind, SyntaxNode},
+    event_notation::{format_events, parse_events},
+    path::{PathSegment, PathTracker, SubtreeExtractor},
+    receiver::{DepthTracker, EventBuffer, FilterMap, Tee},
+    Diagnostic, Directives, Event, IncrementalParser, LexemeKind, Lexer, Marker, Parser,
+    ParserLimits, Progress, RenderStyle, ScanError, ScanErrorKind, Span, SpannedEventReceiver,
+    TScalarStyle, TokenType, WarningKind, MAX_BUFFERED_WARNINGS,
+};
 
 /// Run the parser through the string.
 ///
 /// # Returns
 /// This functions returns the events if parsing succeeds, the error the parser returned otherwise.
-fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
+fn run_parser(input: &str) -> Result<Vec<Event<'_>>, ScanError> {
     let mut str_events = vec![];
     let mut str_error = None;
     let mut iter_events = vec![];
     let mut iter_error = None;
+    let mut reader_events = vec![];
+    let mut reader_error = None;
 
     for x in Parser::new_from_str(input) {
         match x {
@@ -32,6 +44,16 @@ fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
         }
     }
 
+    for x in Parser::new_from_reader(input.as_bytes()) {
+        match x {
+            Ok(event) => reader_events.push(event),
+            Err(e) => {
+                reader_error = Some(e);
+                break;
+            }
+        }
+    }
+
     // eprintln!("str_events");
     // for x in &str_events {
     //     eprintln!("\t{x:?}");
@@ -43,6 +65,8 @@ fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
 
     assert_eq!(str_events, iter_events);
     assert_eq!(str_error, iter_error);
+    assert_eq!(str_events, reader_events);
+    assert_eq!(str_error, reader_error);
 
     if let Some(err) = str_error {
         Err(err)
@@ -81,8 +105,8 @@ fn test_empty_doc() {
         run_parser("---").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(true),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(true, Directives::default()),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -95,10 +119,10 @@ fn test_utf() {
         run_parser("a: 你好").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::MappingStart(0, None),
-            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("你好".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("你好".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::DocumentEnd,
             Event::StreamEnd,
@@ -119,10 +143,10 @@ a: b # This is another comment
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::MappingStart(0, None),
-            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("b".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::DocumentEnd,
             Event::StreamEnd,
@@ -142,11 +166,11 @@ fn test_quoting() {
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::SequenceStart(0, None),
-            Event::Scalar("plain".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("squote".to_string(), TScalarStyle::SingleQuoted, 0, None),
-            Event::Scalar("dquote".to_string(), TScalarStyle::DoubleQuoted, 0, None),
+            Event::Scalar("plain".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("squote".into(), TScalarStyle::SingleQuoted, 0, None),
+            Event::Scalar("dquote".into(), TScalarStyle::DoubleQuoted, 0, None),
             Event::SequenceEnd,
             Event::DocumentEnd,
             Event::StreamEnd,
@@ -167,14 +191,14 @@ a scalar
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
-            Event::Scalar("a scalar".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(false, Directives::default()),
+            Event::Scalar("a scalar".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
-            Event::DocumentStart(true),
-            Event::Scalar("a scalar".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(true, Directives::default()),
+            Event::Scalar("a scalar".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
-            Event::DocumentStart(true),
-            Event::Scalar("a scalar".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(true, Directives::default()),
+            Event::Scalar("a scalar".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -188,8 +212,8 @@ fn test_github_27() {
         run_parser("&a").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
-            Event::Scalar(String::new(), TScalarStyle::Plain, 1, None),
+            Event::DocumentStart(false, Directives::default()),
+            Event::Scalar("".into(), TScalarStyle::Plain, 1, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -230,8 +254,14 @@ foobar";
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(true),
-            Event::Scalar("foobar".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(
+                true,
+                Directives {
+                    version: Some((1, 2)),
+                    tags: vec![],
+                }
+            ),
+            Event::Scalar("foobar".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -254,10 +284,10 @@ a: |-
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::MappingStart(0, None),
-            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("a\n    b".to_string(), TScalarStyle::Literal, 0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("a\n    b".into(), TScalarStyle::Literal, 0, None),
             Event::MappingEnd,
             Event::DocumentEnd,
             Event::StreamEnd,
@@ -272,8 +302,8 @@ fn test_bad_docstart() {
         run_parser("----").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
-            Event::Scalar("----".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(false, Directives::default()),
+            Event::Scalar("----".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -283,8 +313,8 @@ fn test_bad_docstart() {
         run_parser("--- #comment").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(true),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(true, Directives::default()),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -294,8 +324,8 @@ fn test_bad_docstart() {
         run_parser("---- #comment").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
-            Event::Scalar("----".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentStart(false, Directives::default()),
+            Event::Scalar("----".into(), TScalarStyle::Plain, 0, None),
             Event::DocumentEnd,
             Event::StreamEnd,
         ]
@@ -363,3 +393,1057 @@ fn test_recursion_depth_check_arrays() {
     let s = "[".repeat(10_000) + &"]".repeat(10_000);
     assert!(run_parser(&s).is_err());
 }
+
+#[test]
+fn test_comments_are_skipped_by_default() {
+    let events = run_parser("# header\na: b # trailing\n").unwrap();
+    assert!(!events.iter().any(|ev| matches!(ev, Event::Comment(..))));
+}
+
+#[test]
+fn test_keep_comments() {
+    let s = "# header
+a: b # trailing
+# own line
+c:
+  - d # seq
+  # inner
+e: | # block
+  text
+f: [x, # flow
+  y]
+";
+    let comments: Vec<_> = Parser::new_from_str(s)
+        .keep_comments(true)
+        .map(|x| x.unwrap().0)
+        .filter_map(|ev| match ev {
+            Event::Comment(text, trailing) => Some((text, trailing)),
+            _ => None,
+        })
+        .collect();
+    let comments: Vec<_> = comments.iter().map(|(s, t)| (s.as_ref(), *t)).collect();
+    assert_eq!(
+        comments,
+        [
+            (" header", false),
+            (" trailing", true),
+            (" own line", false),
+            (" seq", true),
+            (" inner", false),
+            (" block", true),
+            (" flow", true),
+        ]
+    );
+}
+
+#[test]
+fn test_keep_comments_order() {
+    let events: Vec<_> = Parser::new_from_str("a:\n  - b # b\n# end\nc: d\n")
+        .keep_comments(true)
+        .map(|x| x.unwrap().0)
+        .collect();
+    assert_eq!(
+        events,
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false, Directives::default()),
+            Event::MappingStart(0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
+            Event::SequenceStart(0, None),
+            Event::Scalar("b".into(), TScalarStyle::Plain, 0, None),
+            Event::Comment(" b".into(), true),
+            Event::Comment(" end".into(), false),
+            Event::SequenceEnd,
+            Event::Scalar("c".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("d".into(), TScalarStyle::Plain, 0, None),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+}
+
+#[test]
+fn test_borrowed_scalars() {
+    let s = r#"
+plain: a b  c
+quoted: 'a  b'
+double: "a b"
+escaped: "a\tb"
+quote: 'it''s'
+folded: a
+  b
+&anchor k: *anchor
+"#;
+    let scalars: Vec<_> = Parser::new_from_str(s)
+        .map(|x| x.unwrap().0)
+        .filter_map(|ev| match ev {
+            Event::Scalar(value, ..) => Some(value),
+            _ => None,
+        })
+        .collect();
+    let borrowed: Vec<_> = scalars
+        .iter()
+        .map(|value| (value.as_ref(), matches!(value, Cow::Borrowed(_))))
+        .collect();
+    assert_eq!(
+        borrowed,
+        [
+            ("plain", true),
+            ("a b  c", true),
+            ("quoted", true),
+            ("a  b", true),
+            ("double", true),
+            ("a b", true),
+            ("escaped", true),
+            ("a\tb", false),
+            ("quote", true),
+            ("it's", false),
+            ("folded", true),
+            ("a b", false),
+            ("k", true),
+        ]
+    );
+
+    // Scalars read from inputs that cannot be sliced are owned.
+    assert!(Parser::new_from_iter(s.chars())
+        .map(|x| x.unwrap().0)
+        .all(|ev| !matches!(ev, Event::Scalar(Cow::Borrowed(_), ..))));
+}
+
+#[test]
+fn test_borrowed_tags() {
+    let s = r#"
+%TAG !e! tag:example.com,2000:
+---
+- !local a
+- !e!thing b
+- !!str c
+- !<tag:x.com,2000:y> d
+- !e!esc%41ped e
+- ! f
+"#;
+    let tags: Vec<_> = Parser::new_from_str(s)
+        .map(|x| x.unwrap().0)
+        .filter_map(|ev| match ev {
+            Event::Scalar(_, _, _, Some(tag)) => Some(tag),
+            _ => None,
+        })
+        .collect();
+    let borrowed: Vec<_> = tags
+        .iter()
+        .map(|tag| {
+            (
+                tag.handle.as_ref(),
+                tag.suffix.as_ref(),
+                matches!(tag.handle, Cow::Borrowed(_)),
+                matches!(tag.suffix, Cow::Borrowed(_)),
+            )
+        })
+        .collect();
+    assert_eq!(
+        borrowed,
+        [
+            ("!", "local", true, true),
+            ("tag:example.com,2000:", "thing", true, true),
+            ("tag:yaml.org,2002:", "str", true, true),
+            ("", "tag:x.com,2000:y", true, true),
+            ("tag:example.com,2000:", "escAped", true, false),
+            ("", "!", true, true),
+        ]
+    );
+
+    // So are the handles and prefixes of `%TAG` directives.
+    let directives = Parser::new_from_str(s)
+        .find_map(|x| match x.unwrap().0 {
+            Event::DocumentStart(_, directives) => Some(directives),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(
+        directives.tags.as_slice(),
+        [(Cow::Borrowed("!e!"), Cow::Borrowed("tag:example.com,2000:"))]
+    ));
+    let owned = directives.clone().into_owned();
+    assert!(matches!(owned.tags[0], (Cow::Owned(_), Cow::Owned(_))));
+    assert_eq!(owned, directives);
+
+    // Tags read from inputs that cannot be sliced are owned, except for the `!` tag.
+    assert!(Parser::new_from_iter(s.chars())
+        .map(|x| x.unwrap().0)
+        .all(|ev| match ev {
+            Event::Scalar(_, _, _, Some(tag)) => {
+                tag.suffix == "!" || matches!(tag.suffix, Cow::Owned(_))
+            }
+            _ => true,
+        }));
+}
+
+/// Run the parser through the string with the given limits, returning the error message, if any.
+fn run_parser_with_limits(input: &str, limits: ParserLimits) -> Option<(String, usize)> {
+    Parser::new_from_str(input)
+        .limits(limits)
+        .find_map(Result::err)
+        .map(|e| (e.info().to_owned(), e.marker().line()))
+}
+
+#[test]
+fn test_limits() {
+    let s = "
+a: [1, {b: c}]
+d: long scalar
+---
+- 1
+- 2
+- 3
+";
+    assert_eq!(run_parser_with_limits(s, ParserLimits::new()), None);
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_depth(3)),
+        None
+    );
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_depth(2)),
+        Some(("exceeded the maximum nesting depth of 2".to_owned(), 2))
+    );
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_scalar_len(10)),
+        Some((
+            "exceeded the maximum scalar length of 10 bytes".to_owned(),
+            3
+        ))
+    );
+    // The first document has 14 events, the second 7. The count is reset between documents.
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_events_per_document(14)),
+        None
+    );
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_events_per_document(13)),
+        Some((
+            "exceeded the maximum number of events per document (13)".to_owned(),
+            4
+        ))
+    );
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_input_len(s.len())),
+        None
+    );
+    assert_eq!(
+        run_parser_with_limits(s, ParserLimits::new().max_input_len(20)),
+        Some((
+            "exceeded the maximum input length of 20 bytes".to_owned(),
+            3
+        ))
+    );
+}
+
+/// Parse an endless input made of `prefix` followed by `fill` repeated, with the given limits.
+///
+/// # Returns
+/// The error the parser returned and the number of bytes it read from the input.
+fn run_endless_input(prefix: &str, fill: u8, limits: ParserLimits) -> (ScanError, usize) {
+    const MAX_READ: u64 = 1 << 30;
+    let mut reader = prefix
+        .as_bytes()
+        .chain(std::io::repeat(fill))
+        .take(MAX_READ);
+    let err = Parser::new_from_reader(&mut reader)
+        .limits(limits)
+        .find_map(Result::err)
+        .unwrap();
+    (err, usize::try_from(MAX_READ - reader.limit()).unwrap())
+}
+
+#[test]
+fn test_limits_are_checked_while_reading() {
+    // The inputs never end. The scanner must stop as soon as a limit is exceeded rather than read
+    // the scalar, comment or whitespace in full.
+    let limits = ParserLimits::new().max_scalar_len(1000);
+    for prefix in ["key: ", "key: \"", "key: '", "key: |\n  ", "key: >\n  "] {
+        let (err, read) = run_endless_input(prefix, b'a', limits);
+        assert_eq!(err.kind(), ScanErrorKind::ScalarTooLong, "{prefix:?}");
+        assert_eq!(err.marker().line(), 1 + usize::from(prefix.contains('\n')));
+        assert!(read < 64 * 1024, "{prefix:?}: read {read} bytes");
+    }
+
+    let limits = ParserLimits::new().max_input_len(1000);
+    for (prefix, fill) in [
+        ("key: ", b'a'),
+        ("key: \"", b' '),
+        ("key: |\n", b'\n'),
+        ("key: x", b' '),
+        ("# ", b'#'),
+        ("", b'\n'),
+    ] {
+        let (err, read) = run_endless_input(prefix, fill, limits);
+        assert_eq!(err.kind(), ScanErrorKind::InputTooLong, "{prefix:?}");
+        assert!(read < 64 * 1024, "{prefix:?}: read {read} bytes");
+    }
+
+    // The incremental parser reports an oversized scalar before the end of it is fed.
+    let mut parser = IncrementalParser::new().limits(ParserLimits::new().max_scalar_len(1000));
+    parser.feed("key: ");
+    let chunk = "a".repeat(100);
+    let err = loop {
+        parser.feed(&chunk);
+        if let Some(err) = parser.events().find_map(Result::err) {
+            break err;
+        }
+    };
+    assert_eq!(err.kind(), ScanErrorKind::ScalarTooLong);
+    assert_eq!(err.marker().col(), 5);
+
+    // Limits are not errors the parser recovers from.
+    let err = Parser::new_from_str("a: [[x]]")
+        .recover_errors(true)
+        .limits(ParserLimits::new().max_depth(2))
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(err.kind(), ScanErrorKind::NestingTooDeep);
+    assert_eq!(err.marker().col(), 4);
+}
+
+#[test]
+fn test_recover_errors() {
+    let s = "
+a:
+  b: 1
+  c: - x
+  d: [2
+e: 3
+---
+f: 'g' h
+";
+    let mut parser = Parser::new_from_str(s).recover_errors(true);
+    let events: Vec<_> = parser.by_ref().map(|x| x.unwrap().0).collect();
+    let errors: Vec<_> = parser
+        .take_errors()
+        .iter()
+        .map(|e| (e.marker().line(), e.marker().col()))
+        .collect();
+    assert_eq!(errors, [(4, 5), (6, 1), (8, 7)]);
+    assert_eq!(
+        events,
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false, Directives::default()),
+            Event::MappingStart(0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
+            Event::MappingStart(0, None),
+            Event::Scalar("b".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("1".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("c".into(), TScalarStyle::Plain, 0, None),
+            // Missing value for `c`.
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("d".into(), TScalarStyle::Plain, 0, None),
+            Event::SequenceStart(0, None),
+            // The error is found at `e`, which is skipped. Recovery closes the unterminated
+            // sequence and the mappings.
+            Event::SequenceEnd,
+            Event::MappingEnd,
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::DocumentStart(true, Directives::default()),
+            Event::MappingStart(0, None),
+            Event::Scalar("f".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+
+    // Without recovery, parsing stops at the first error.
+    let mut parser = Parser::new_from_str(s);
+    assert!(parser.any(|x| x.is_err()));
+    assert!(parser.take_errors().is_empty());
+}
+
+#[test]
+fn test_error_kinds() {
+    let kind_of = |s: &str| run_parser(s).unwrap_err().kind();
+    assert_eq!(
+        kind_of("a: 'b' c"),
+        ScanErrorKind::QuotedScalarTrailingContent
+    );
+    assert_eq!(kind_of("{a: b"), ScanErrorKind::FlowMappingMissingSeparator);
+    assert_eq!(
+        kind_of("[a, b"),
+        ScanErrorKind::FlowSequenceMissingSeparator
+    );
+    assert_eq!(kind_of("a: *b"), ScanErrorKind::UnknownAnchor);
+    assert_eq!(kind_of("@a"), ScanErrorKind::UnexpectedCharacter);
+
+    let err = run_parser("{a: b").unwrap_err();
+    assert_eq!(err.kind().code(), "E210");
+    assert_eq!(err.kind().expected(), ["','", "'}'"]);
+    assert_eq!(err.info(), err.kind().message());
+    assert!(ScanErrorKind::UnknownAnchor.expected().is_empty());
+
+    let err = run_parser("@a").unwrap_err();
+    assert_eq!(err.info(), "unexpected character: `@'");
+    assert_eq!(err.kind().message(), "unexpected character");
+
+    let err = Parser::new_from_str("[[a]]")
+        .limits(ParserLimits::new().max_depth(1))
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(err.kind(), ScanErrorKind::NestingTooDeep);
+
+    let err = ScanError::new_str(*err.marker(), "custom");
+    assert_eq!(err.kind(), ScanErrorKind::Other);
+}
+
+#[test]
+fn test_diagnostic() {
+    let s = "a:\n  b: \"c\" d\n";
+    let err = run_parser(s).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(&err)
+            .with_origin("in.yaml")
+            .render(s, RenderStyle::Plain),
+        "\
+error[E141]: invalid trailing content after double-quoted scalar
+ --> in.yaml:2:10
+  |
+2 |   b: \"c\" d
+  |          ^
+"
+    );
+    assert_eq!(
+        Diagnostic::from(&err).render(s, RenderStyle::Ansi),
+        "\x1B[1;31merror\x1B[0m\x1B[1;31m[E141]\x1B[0m\x1B[1m: invalid trailing content after \
+         double-quoted scalar\x1B[0m
+ \x1B[1;34m-->\x1B[0m 2:10
+  \x1B[1;34m|\x1B[0m
+\x1B[1;34m2 |\x1B[0m   b: \"c\" d
+\x1B[1;34m  |\x1B[0m          \x1B[1;31m^\x1B[0m
+"
+    );
+
+    // Lines may also be broken with `\r` or `\r\n`.
+    for s in ["a:\r  b: \"c\" d\r", "a:\r\n  b: \"c\" d\r\n"] {
+        let err = run_parser(s).unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&err).render(s, RenderStyle::Plain),
+            "\
+error[E141]: invalid trailing content after double-quoted scalar
+ --> 2:10
+  |
+2 |   b: \"c\" d
+  |          ^
+"
+        );
+    }
+
+    // Multi-line spans show their first and last lines.
+    let s = "key: [a,\n  b,\n  c]\n";
+    let span = Span::new(Marker::new(5, 1, 5), Marker::new(18, 3, 4));
+    assert_eq!(
+        Diagnostic::new("custom")
+            .with_span(span)
+            .with_note("a note")
+            .render(s, RenderStyle::Plain),
+        "\
+error: custom
+ --> 1:6
+  |
+1 | key: [a,
+  |      ^~~
+...
+3 |   c]
+  | ^~~~
+  = note: a note
+"
+    );
+
+    // Diagnostics without a span have no snippet.
+    assert_eq!(
+        Diagnostic::new("oops")
+            .with_origin("in.yaml")
+            .render("", RenderStyle::Plain),
+        "error: oops\n--> in.yaml\n"
+    );
+}
+
+/// A reader that returns at most one byte per read, interrupting every other read, and fails once
+/// `fail_at` bytes were read.
+struct TrickleReader<'a> {
+    bytes: &'a [u8],
+    interrupt: bool,
+    fail_at: Option<usize>,
+}
+
+impl std::io::Read for TrickleReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+        if self.fail_at == Some(0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "disk on fire",
+            ));
+        }
+        let Some((&first, rest)) = self.bytes.split_first() else {
+            return Ok(0);
+        };
+        buf[0] = first;
+        self.bytes = rest;
+        self.fail_at = self.fail_at.map(|n| n - 1);
+        Ok(1)
+    }
+}
+
+#[test]
+fn test_reader_input() {
+    let run = |bytes: &[u8], fail_at: Option<usize>| {
+        let reader = TrickleReader {
+            bytes,
+            interrupt: false,
+            fail_at,
+        };
+        Parser::new_from_reader(reader)
+            .map(|x| x.map(|(ev, _)| ev))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    // Multi-byte characters are split across reads.
+    let s = "k\u{e9}y: [\u{1F600}, \"\u{3042}\"]\n";
+    assert_eq!(run(s.as_bytes(), None).unwrap(), run_parser(s).unwrap());
+
+    // Inputs larger than the read buffer.
+    let s = "- abc\u{e9}\n".repeat(4096);
+    assert_eq!(
+        Parser::new_from_reader(s.as_bytes())
+            .map(|x| x.unwrap().0)
+            .collect::<Vec<_>>(),
+        run_parser(&s).unwrap()
+    );
+
+    let err = run(b"a: \"b\xFFc\"", None).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::InvalidUtf8);
+    assert_eq!(err.info(), "invalid UTF-8 sequence starting with byte 0xFF");
+    assert_eq!(
+        (
+            err.marker().byte_offset(),
+            err.marker().line(),
+            err.marker().col()
+        ),
+        (5, 1, 5)
+    );
+
+    let err = run(b"a:\n  - b\xC3", None).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::InvalidUtf8);
+    assert_eq!(
+        (
+            err.marker().byte_offset(),
+            err.marker().line(),
+            err.marker().col()
+        ),
+        (8, 2, 5)
+    );
+
+    let err = run(b"a: b\nc: d\n", Some(7)).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::ReadFailed);
+    assert_eq!(err.info(), "failed to read from the input: disk on fire");
+    assert_eq!(err.marker().byte_offset(), 7);
+
+    // Errors that occur before the input fails are reported as usual.
+    let err = run(b"a: \"b\" c\n\xFF", None).unwrap_err();
+    assert_eq!(err.kind(), ScanErrorKind::QuotedScalarTrailingContent);
+}
+
+#[test]
+fn test_incremental_parser() {
+    let mut parser = IncrementalParser::new();
+    assert!(matches!(
+        parser.next_event(),
+        Ok(Progress::Event(Event::StreamStart, _))
+    ));
+    assert_eq!(parser.next_event(), Ok(Progress::NeedMoreInput));
+
+    // `a` may be a scalar or a key until we know what follows it.
+    parser.feed("- a");
+    let events: Vec<_> = parser.events().map(|x| x.unwrap().0).collect();
+    assert_eq!(
+        events,
+        [
+            Event::DocumentStart(false, Directives::default()),
+            Event::SequenceStart(0, None),
+        ]
+    );
+    assert_eq!(parser.next_event(), Ok(Progress::NeedMoreInput));
+
+    parser.feed(": b\n- c");
+    let events: Vec<_> = parser.events().map(|x| x.unwrap().0).collect();
+    assert_eq!(
+        events,
+        [
+            Event::MappingStart(0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("b".into(), TScalarStyle::Plain, 0, None),
+            Event::MappingEnd,
+        ]
+    );
+
+    parser.finish();
+    assert!(parser.is_finished());
+    let events: Vec<_> = parser.events().map(|x| x.unwrap().0).collect();
+    assert_eq!(
+        events,
+        [
+            Event::Scalar("c".into(), TScalarStyle::Plain, 0, None),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+    assert_eq!(parser.next_event(), Ok(Progress::Finished));
+
+    // Errors are reported once the input that causes them is fed.
+    let mut parser = IncrementalParser::new().limits(ParserLimits::new().max_depth(1));
+    parser.feed("- - a");
+    let err = parser.events().find_map(Result::err).unwrap();
+    assert_eq!(err.kind(), ScanErrorKind::NestingTooDeep);
+    assert_eq!(err.marker().col(), 2);
+}
+
+#[test]
+fn test_incremental_parser_rolls_back_anchors() {
+    // Feeding one byte at a time makes the parser roll back in the middle of most events.
+    let s = "a: &x 1\nb: &x [*x]\nc: &y [*x]\n---\nd: &x 2\ne: *x\n";
+    let mut expected = Parser::new_from_str(s).collect_warnings(true);
+    let expected_events: Vec<_> = expected.by_ref().map(|x| x.unwrap().0).collect();
+    let mut parser = IncrementalParser::new().collect_warnings(true);
+    let mut events = vec![];
+    for c in s.chars() {
+        parser.feed(c.encode_utf8(&mut [0; 4]));
+        events.extend(parser.events().map(|x| x.unwrap().0));
+    }
+    parser.finish();
+    events.extend(parser.events().map(|x| x.unwrap().0));
+    assert_eq!(events, expected_events);
+    let warnings = parser.take_warnings();
+    assert_eq!(warnings.len(), 3);
+    assert_eq!(warnings, expected.take_warnings());
+    for id in 0..6 {
+        assert_eq!(parser.anchor_name(id), expected.anchor_name(id));
+    }
+}
+
+#[test]
+fn test_documents() {
+    let s = "a: 1\n---\nb\n...\n# comment\n%YAML 1.2\n--- !!str\nc\n";
+    let docs = Parser::new_from_str(s)
+        .documents()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let texts: Vec<_> = docs.iter().map(|doc| &s[doc.span.byte_range()]).collect();
+    assert_eq!(texts, ["a: 1\n", "---\nb\n...", "--- !!str\nc\n"]);
+    assert_eq!(
+        docs.iter()
+            .map(|doc| doc.explicit_start)
+            .collect::<Vec<_>>(),
+        [false, true, true]
+    );
+    assert_eq!(docs[1].directives, Directives::default());
+    assert_eq!(docs[2].directives.version, Some((1, 2)));
+
+    // Skipping documents and reading events can be interleaved.
+    let mut parser = Parser::new_from_str(s);
+    assert!(parser.next_document().unwrap().is_ok());
+    let events: Vec<_> = parser.by_ref().take(3).map(|x| x.unwrap().0).collect();
+    assert_eq!(
+        events,
+        [
+            Event::DocumentStart(true, Directives::default()),
+            Event::Scalar("b".into(), TScalarStyle::Plain, 0, None),
+            Event::DocumentEnd,
+        ]
+    );
+    assert_eq!(parser.documents().count(), 1);
+    assert!(parser.next_document().is_none());
+    assert!(parser.next_event().is_none());
+
+    let mut parser = Parser::new_from_str("a\n---\n[b\n---\nc\n");
+    let mut docs = parser.documents();
+    assert!(docs.next().unwrap().is_ok());
+    assert!(docs.next().unwrap().is_err());
+    assert!(docs.next().is_none());
+}
+
+#[test]
+fn test_warnings() {
+    fn warnings(s: &str) -> Vec<(WarningKind, &str)> {
+        let mut parser = Parser::new_from_str(s).collect_warnings(true);
+        for event in parser.by_ref() {
+            event.unwrap();
+        }
+        parser
+            .take_warnings()
+            .iter()
+            .map(|w| (w.kind(), &s[w.span().byte_range()]))
+            .collect()
+    }
+
+    assert_eq!(
+        warnings("%YAML 1.2\n---\na: &x [*x]\n\t\n# \tcomment\n"),
+        []
+    );
+    assert_eq!(
+        warnings("%YAML 1.3\n--- a\n...\n%FOO bar\n--- b\n"),
+        [
+            (WarningKind::UnknownVersion, "%YAML 1.3"),
+            (WarningKind::UnknownDirective, "%FOO bar"),
+        ]
+    );
+    assert_eq!(
+        warnings("a: &x 1\nb: &y 2\nc: &x 3\nd: *x\n---\ne: *x\nf: &x 4\n"),
+        [
+            (WarningKind::UnusedAnchor, "&x"),
+            (WarningKind::UnusedAnchor, "&y"),
+            (WarningKind::RedefinedAnchor, "&x"),
+            (WarningKind::UnusedAnchor, "&x"),
+        ]
+    );
+    assert_eq!(warnings("[a,\n\t b]\n"), [(WarningKind::Tab, "\t ")]);
+
+    // Warnings are only returned once.
+    let mut parser = Parser::new_from_str("%YAML 1.3\n--- a\n").collect_warnings(true);
+    for event in parser.by_ref() {
+        event.unwrap();
+    }
+    assert_eq!(parser.take_warnings().len(), 1);
+    assert!(parser.take_warnings().is_empty());
+
+    // Warnings are not collected unless asked for.
+    let mut parser = Parser::new_from_str("%YAML 1.3\n--- &x a\n");
+    for event in parser.by_ref() {
+        event.unwrap();
+    }
+    assert!(parser.take_warnings().is_empty());
+
+    // The warnings that were not taken are capped.
+    let s = "- &x a\n".repeat(MAX_BUFFERED_WARNINGS * 2);
+    let mut parser = Parser::new_from_str(&s).collect_warnings(true);
+    for event in parser.by_ref() {
+        event.unwrap();
+    }
+    let warnings = parser.take_warnings();
+    assert_eq!(warnings.len(), MAX_BUFFERED_WARNINGS);
+    assert!(warnings
+        .iter()
+        .all(|w| w.kind() == WarningKind::RedefinedAnchor));
+}
+
+#[test]
+fn test_event_notation() {
+    let s = "--- !!map\n&a key: !local \"a\\tb\\\\c\"\n&a seq: [*a, 'x']\nlit: |\n  l\n...\n";
+    let mut parser = Parser::new_from_str(s);
+    let events = parser.by_ref().map(Result::unwrap).collect::<Vec<_>>();
+    let notation = format_events(events.clone(), |id| parser.anchor_name(id));
+    assert_eq!(
+        notation,
+        "+STR\n +DOC ---\n  +MAP <tag:yaml.org,2002:map>\n   =VAL &a :key\n   \
+         =VAL <!local> \"a\\tb\\\\c\n   =VAL &a :seq\n   +SEQ\n    =ALI *a\n    =VAL 'x\n   \
+         -SEQ\n   =VAL :lit\n   =VAL |l\\n\n  -MAP\n -DOC\n-STR\n"
+    );
+
+    // Parsing the notation gives the events back, but for the directives.
+    let parsed = parse_events(&notation).unwrap();
+    assert_eq!(
+        parsed,
+        events.iter().map(|(ev, _)| ev.clone()).collect::<Vec<_>>()
+    );
+
+    // Without anchor names, anchors are rendered with their ID.
+    let notation = format_events(events, |_| None);
+    assert!(notation.contains("   =VAL &2 :seq\n   +SEQ\n    =ALI *2\n"));
+
+    // Indentation, explicit document ends and flow styles are accepted.
+    assert_eq!(
+        parse_events("+STR\n +DOC\n  +SEQ [] &x\n   =ALI *x\n  -SEQ\n -DOC ...\n-STR").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false, Directives::default()),
+            Event::SequenceStart(1, None),
+            Event::Alias(1),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+
+    let err = parse_events("+STR\n+DOC\n\n=ALI *x\n").unwrap_err();
+    assert_eq!(err.line(), 4);
+    assert_eq!(err.to_string(), "invalid event `=ALI *x` at line 4");
+    assert_eq!(parse_events("=VAL :a\\q").unwrap_err().line(), 1);
+}
+
+#[test]
+fn test_lexer() {
+    let s = "\u{FEFF}%YAML 1.2 # v\r\n--- !t\r\n- [a, \"b\"]\r\n-\t&x |-  # h\r\n  l\r\n# c\r\n";
+    let lexemes = Lexer::new(s).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(lexemes.iter().map(|l| l.text).collect::<String>(), s);
+
+    let kinds = lexemes
+        .iter()
+        .filter(|l| !l.text.is_empty())
+        .map(|l| (l.text, l.kind.clone()))
+        .collect::<Vec<_>>();
+    let token = LexemeKind::Token;
+    assert_eq!(
+        kinds,
+        [
+            ("\u{FEFF}", LexemeKind::Bom),
+            ("%YAML 1.2", token(TokenType::VersionDirective(1, 2))),
+            (" ", LexemeKind::Whitespace),
+            ("# v", LexemeKind::Comment),
+            ("\r\n", LexemeKind::LineBreak),
+            ("---", token(TokenType::DocumentStart)),
+            (" ", LexemeKind::Whitespace),
+            ("!t", token(TokenType::Tag("!".into(), "t".into()))),
+            ("\r\n", LexemeKind::LineBreak),
+            ("-", token(TokenType::BlockEntry)),
+            (" ", LexemeKind::Whitespace),
+            ("[", token(TokenType::FlowSequenceStart)),
+            (
+                "a",
+                token(TokenType::Scalar(TScalarStyle::Plain, "a".into()))
+            ),
+            (",", token(TokenType::FlowEntry)),
+            (" ", LexemeKind::Whitespace),
+            (
+                "\"b\"",
+                token(TokenType::Scalar(TScalarStyle::DoubleQuoted, "b".into()))
+            ),
+            ("]", token(TokenType::FlowSequenceEnd)),
+            ("\r\n", LexemeKind::LineBreak),
+            ("-", token(TokenType::BlockEntry)),
+            ("\t", LexemeKind::Whitespace),
+            ("&x", token(TokenType::Anchor("x".into()))),
+            (" ", LexemeKind::Whitespace),
+            (
+                "|-  # h\r\n  l\r\n",
+                token(TokenType::Scalar(TScalarStyle::Literal, "l".into()))
+            ),
+            ("# c", LexemeKind::Comment),
+            ("\r\n", LexemeKind::LineBreak),
+        ]
+    );
+
+    // The byte order mark is not a column.
+    let dash = lexemes.iter().find(|l| l.text == "-").unwrap();
+    assert_eq!(dash.span.start, Marker::new(24, 3, 0).with_byte_offset(26));
+
+    // Lexemes are yielded up to the error, then nothing.
+    let mut lexer = Lexer::new("a: \"b");
+    let texts = lexer
+        .by_ref()
+        .map_while(Result::ok)
+        .map(|l| l.text)
+        .collect::<String>();
+    assert_eq!(texts, "a:");
+    assert!(lexer.next().is_none());
+}
+
+#[test]
+fn test_cst() {
+    let s = "# head\n---\nkey: &a [b, 'c'] # tail\nseq:\n- !t d\n...\n";
+    let root = cst::parse(s).unwrap();
+    assert_eq!(root.to_string(), s);
+    assert_eq!(root.kind(), SyntaxKind::Stream);
+    assert_eq!(root.text_range(), 0..s.len());
+
+    let document = root.children().next().unwrap();
+    assert_eq!(document.kind(), SyntaxKind::Document);
+    assert_eq!(
+        document.to_string(),
+        "---\nkey: &a [b, 'c'] # tail\nseq:\n- !t d\n..."
+    );
+    let mapping = document.children().next().unwrap();
+    assert_eq!(mapping.kind(), SyntaxKind::BlockMapping);
+    let entries = mapping.children().collect::<Vec<_>>();
+    assert_eq!(
+        entries.iter().map(SyntaxNode::kind).collect::<Vec<_>>(),
+        [SyntaxKind::MappingEntry, SyntaxKind::MappingEntry]
+    );
+    assert_eq!(
+        entries[1]
+            .descendants()
+            .map(|node| node.kind())
+            .collect::<Vec<_>>(),
+        [
+            SyntaxKind::MappingEntry,
+            SyntaxKind::Scalar,
+            SyntaxKind::BlockSequence,
+            SyntaxKind::SequenceEntry,
+            SyntaxKind::Scalar,
+        ]
+    );
+
+    // Tokens keep their text, and trivia is kept where it was.
+    let tokens = entries[0]
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .map(|token| (token.kind(), token.text().to_owned()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens
+            .iter()
+            .map(|(kind, text)| (*kind, text.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (SyntaxKind::PlainScalar, "key"),
+            (SyntaxKind::Value, ":"),
+            (SyntaxKind::Whitespace, " "),
+            (SyntaxKind::Anchor, "&a"),
+            (SyntaxKind::Whitespace, " "),
+            (SyntaxKind::FlowSequenceStart, "["),
+            (SyntaxKind::PlainScalar, "b"),
+            (SyntaxKind::FlowEntry, ","),
+            (SyntaxKind::Whitespace, " "),
+            (SyntaxKind::SingleQuotedScalar, "'c'"),
+            (SyntaxKind::FlowSequenceEnd, "]"),
+            (SyntaxKind::Whitespace, " "),
+            (SyntaxKind::Comment, "# tail"),
+            (SyntaxKind::LineBreak, "\n"),
+        ]
+    );
+
+    // Replacing a token gives a new tree and leaves the old one untouched.
+    let b = root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| token.text() == "b")
+        .unwrap();
+    assert_eq!(b.text_range(), 20..21);
+    assert_eq!(
+        b.parent()
+            .ancestors()
+            .map(|node| node.kind())
+            .collect::<Vec<_>>(),
+        [
+            SyntaxKind::Scalar,
+            SyntaxKind::FlowSequence,
+            SyntaxKind::MappingEntry,
+            SyntaxKind::BlockMapping,
+            SyntaxKind::Document,
+            SyntaxKind::Stream,
+        ]
+    );
+    let edited = SyntaxNode::new_root(
+        b.replace_with(GreenToken::new(SyntaxKind::DoubleQuotedScalar, "\"B\"")),
+    );
+    assert_eq!(
+        edited.to_string(),
+        "# head\n---\nkey: &a [\"B\", 'c'] # tail\nseq:\n- !t d\n...\n"
+    );
+    assert_eq!(root.to_string(), s);
+
+    assert!(cst::parse("a: \"b").is_err());
+}
+
+#[test]
+fn test_receivers() {
+    let s = "a: [b, {c: d}]\n---\n- e\n";
+    let events = Parser::new_from_str(s)
+        .collect::<Result<EventBuffer, ScanError>>()
+        .unwrap();
+    assert_eq!(events.len(), 19);
+
+    // A tee gives both receivers every event.
+    let mut tee = Tee::new(EventBuffer::new(), DepthTracker::new(EventBuffer::new()));
+    Parser::new_from_str(s).load(&mut tee, true).unwrap();
+    let (first, second) = tee.into_inner();
+    assert_eq!(first, events);
+    assert_eq!(second.depth(), 0);
+    assert_eq!(second.max_depth(), 3);
+    assert_eq!(second.into_inner(), events);
+
+    // Scalars are uppercased, everything but scalars is dropped.
+    let mut filter_map = FilterMap::new(EventBuffer::new(), |ev, span| match ev {
+        Event::Scalar(value, style, anchor, tag) => Some((
+            Event::Scalar(value.to_uppercase().into(), style, anchor, tag),
+            span,
+        )),
+        _ => None,
+    });
+    events.replay(&mut filter_map);
+    let scalars: Vec<_> = filter_map
+        .into_inner()
+        .into_iter()
+        .map(|(ev, _)| match ev {
+            Event::Scalar(value, ..) => value.into_owned(),
+            ev => panic!("unexpected event {ev:?}"),
+        })
+        .collect();
+    assert_eq!(scalars, ["A", "B", "C", "D", "E"]);
+
+    // Replaying an owned copy of the events gives the same events.
+    let owned: EventBuffer<'static> = events.clone().into_owned();
+    let mut replayed = EventBuffer::new();
+    owned.replay_into(&mut replayed);
+    assert_eq!(replayed, events);
+
+    // Depth is tracked across documents, and never goes below 0.
+    let mut depth = DepthTracker::new(EventBuffer::new());
+    depth.on_event(Event::SequenceEnd, Span::default());
+    assert_eq!(depth.depth(), 0);
+    events.events()[..5]
+        .iter()
+        .for_each(|(ev, span)| depth.on_event(ev.clone(), *span));
+    assert_eq!((depth.depth(), depth.max_depth()), (2, 2));
+}
+
+#[test]
+fn test_path_tracker() {
+    let s = "a: &x [b, {c: d}]\n? [e, {f: g}]\n: h\n*x : i\n---\n- j\n- - k\n";
+    let mut tracker = PathTracker::new();
+    let mut paths = vec![];
+    for event in Parser::new_from_str(s) {
+        let (ev, _) = event.unwrap();
+        let path = tracker.on_event(&ev).to_vec();
+        let in_key = tracker.in_key();
+        match ev {
+            Event::Scalar(value, ..) => paths.push((value.into_owned(), path, in_key)),
+            Event::Alias(_) => paths.push(("*".into(), path, in_key)),
+            _ => {}
+        }
+    }
+
+    let key = |k: &'static str| PathSegment::from(k);
+    assert_eq!(
+        paths,
+        [
+            ("a".into(), vec![], true),
+            ("b".into(), vec![key("a"), 0.into()], false),
+            ("c".into(), vec![key("a"), 1.into()], true),
+            ("d".into(), vec![key("a"), 1.into(), key("c")], false),
+            ("e".into(), vec![], true),
+            ("f".into(), vec![], true),
+            ("g".into(), vec![], true),
+            ("h".into(), vec![PathSegment::ComplexKey], false),
+            ("*".into(), vec![], true),
+            ("i".into(), vec![PathSegment::ComplexKey], false),
+            ("j".into(), vec![0.into()], false),
+            ("k".into(), vec![1.into(), 0.into()], false),
+        ]
+    );
+}
+
+#[test]
+fn test_subtree_extractor() {
+    let s = "base: &b {x: 1}\nspec: {a: [*b, 2], b: 3}\n---\nspec: {a: []}\n---\nother: 4\n";
+    let mut extractor = SubtreeExtractor::new(EventBuffer::new(), ["spec".into(), "a".into()]);
+    Parser::new_from_str(s).load(&mut extractor, true).unwrap();
+    assert_eq!(extractor.matches(), 2);
+    assert_eq!(
+        format_events(extractor.into_inner(), |_| None),
+        "+STR\n +DOC\n  +SEQ\n   +MAP &1\n    =VAL :x\n    =VAL :1\n   -MAP\n   =VAL :2\n  -SEQ\n \
+         -DOC\n +DOC\n  +SEQ\n  -SEQ\n -DOC\n-STR\n"
+    );
+}
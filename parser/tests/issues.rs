@@ -1,4 +1,4 @@
-use saphyr_parser::{Event, Parser, ScanError, TScalarStyle};
+use saphyr_parser::{Directives, Event, Parser, ScanError, TScalarStyle};
 
 /// Run the parser through the string.
 ///
@@ -11,7 +11,7 @@ use saphyr_parser::{Event, Parser, ScanError, TScalarStyle};
 /// # Panics
 /// This function panics if there is a mismatch between the 2 parser invocations with the different
 /// input traits.
-fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
+fn run_parser(input: &str) -> Result<Vec<Event<'_>>, ScanError> {
     let mut str_events = vec![];
     let mut str_error = None;
     let mut iter_events = vec![];
@@ -77,12 +77,12 @@ fn test_issue1() {
 
     let expected = [
         Event::StreamStart,
-        Event::DocumentStart(false),
+        Event::DocumentStart(false, Directives::default()),
         Event::SequenceStart(0, None),
         Event::MappingStart(0, None),
-        Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+        Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
         Event::SequenceStart(0, None),
-        Event::Scalar("42".to_string(), TScalarStyle::Plain, 0, None),
+        Event::Scalar("42".into(), TScalarStyle::Plain, 0, None),
         Event::SequenceEnd,
         Event::MappingEnd,
         Event::SequenceEnd,
@@ -100,17 +100,17 @@ fn test_issue1() {
         run_parser("[foo: [bar]]: baz").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::MappingStart(0, None),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("foo".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("foo".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceStart(0, None),
-            Event::Scalar("bar".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("bar".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceEnd,
             Event::MappingEnd,
             Event::SequenceEnd,
-            Event::Scalar("baz".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("baz".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::DocumentEnd,
             Event::StreamEnd,
@@ -122,11 +122,11 @@ fn test_issue1() {
         run_parser("[:]").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::SequenceEnd,
             Event::DocumentEnd,
@@ -139,14 +139,14 @@ fn test_issue1() {
         run_parser("[: [:]]").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::SequenceEnd,
             Event::MappingEnd,
@@ -166,16 +166,16 @@ fn test_issue1() {
         // third nested sequences, but not the second.
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("a".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceStart(0, None),
             // No `MappingStart` here.
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("b".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("~".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::SequenceEnd,
             // No `MappingEnd` here.
@@ -196,10 +196,10 @@ fn test_issue1() {
         run_parser(r#"["a":[]]"#).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("a".to_string(), TScalarStyle::DoubleQuoted, 0, None),
+            Event::Scalar("a".into(), TScalarStyle::DoubleQuoted, 0, None),
             Event::SequenceStart(0, None),
             Event::SequenceEnd,
             Event::MappingEnd,
@@ -216,9 +216,9 @@ fn test_pr12() {
         run_parser("---\n- |\n  a").unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(true),
+            Event::DocumentStart(true, Directives::default()),
             Event::SequenceStart(0, None),
-            Event::Scalar("a\n".to_string(), TScalarStyle::Literal, 0, None),
+            Event::Scalar("a\n".into(), TScalarStyle::Literal, 0, None),
             Event::SequenceEnd,
             Event::DocumentEnd,
             Event::StreamEnd,
@@ -272,23 +272,23 @@ array:
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(true),
+            Event::DocumentStart(true, Directives::default()),
             Event::MappingStart(0, None),
-            Event::Scalar("array".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("array".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("object".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("object".into(), TScalarStyle::Plain, 0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("array".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("array".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("object".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("object".into(), TScalarStyle::Plain, 0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("array".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("array".into(), TScalarStyle::Plain, 0, None),
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
-            Event::Scalar("text".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("Line 1 Line 2".to_string(), TScalarStyle::Folded, 0, None),
+            Event::Scalar("text".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("Line 1 Line 2".into(), TScalarStyle::Folded, 0, None),
             Event::MappingEnd,
             Event::SequenceEnd,
             Event::MappingEnd,
@@ -313,10 +313,10 @@ fn test_issue22() {
         run_parser(s).unwrap(),
         [
             Event::StreamStart,
-            Event::DocumentStart(false),
+            Event::DocumentStart(false, Directives::default()),
             Event::MappingStart(0, None),
-            Event::Scalar("comment".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("hello ... world".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("comment".into(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("hello ... world".into(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::DocumentEnd,
             Event::StreamEnd
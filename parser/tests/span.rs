@@ -12,7 +12,7 @@ fn run_parser_and_deref_scalar_spans(input: &str) -> Result<Vec<(String, String)
             let start = x.1.start.index();
             let end = x.1.end.index();
             let input_s = input.chars().skip(start).take(end - start).collect();
-            events.push((s, input_s));
+            events.push((s.into_owned(), input_s));
         }
     }
     Ok(events)
@@ -164,3 +164,77 @@ fn test_block_utf8() {
         ],
     );
 }
+
+#[test]
+fn test_comment_spans() {
+    let input = "# a\nkey: value # b\n";
+    let spans: Vec<String> = Parser::new_from_str(input)
+        .keep_comments(true)
+        .map(Result::unwrap)
+        .filter(|(ev, _)| matches!(ev, Event::Comment(..)))
+        .map(|(_, span)| {
+            input
+                .chars()
+                .skip(span.start.index())
+                .take(span.end.index() - span.start.index())
+                .collect()
+        })
+        .collect();
+    assert_eq!(spans, ["# a", "# b"]);
+}
+
+#[test]
+fn test_byte_range() {
+    let input = "# コメント\nキー: 'välue' # 😀\nlist: [ä, \"ö\\u00e9\"]\n";
+    for events in [
+        Parser::new_from_str(input).collect::<Result<Vec<_>, _>>(),
+        Parser::new_from_iter(input.chars()).collect::<Result<Vec<_>, _>>(),
+    ] {
+        let scalars: Vec<&str> = events
+            .unwrap()
+            .into_iter()
+            .filter(|(ev, _)| matches!(ev, Event::Scalar(..)))
+            .map(|(_, span)| &input[span.byte_range()])
+            .collect();
+        assert_eq!(scalars, ["キー", "'välue'", "list", "ä", "\"ö\\u00e9\""]);
+    }
+}
+
+#[test]
+fn test_flow_collection_spans() {
+    let input = "{ a: [b ,  c ]  # x\n,  d: e }  # y\n";
+    let spans: Vec<(Event, &str)> = Parser::new_from_str(input)
+        .map(Result::unwrap)
+        .filter(|(ev, _)| {
+            matches!(
+                ev,
+                Event::SequenceStart(..)
+                    | Event::SequenceEnd
+                    | Event::MappingStart(..)
+                    | Event::MappingEnd
+            )
+        })
+        .map(|(ev, span)| (ev, &input[span.byte_range()]))
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            (Event::MappingStart(0, None), "{"),
+            (Event::SequenceStart(0, None), "["),
+            (Event::SequenceEnd, "]"),
+            (Event::MappingEnd, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_empty_document_spans() {
+    // The null scalar of an empty document does not span the start of the next one.
+    let input = "---\n--- a\n";
+    let spans: Vec<_> = Parser::new_from_str(input)
+        .map(Result::unwrap)
+        .filter(|(ev, _)| matches!(ev, Event::Scalar(..)))
+        .map(|(_, span)| span.byte_range())
+        .collect();
+    assert_eq!(spans, [4..4, 8..9]);
+}
@@ -23,10 +23,10 @@ struct YamlChecker {
     pub evs: Vec<TestEvent>,
 }
 
-impl EventReceiver for YamlChecker {
+impl EventReceiver<'_> for YamlChecker {
     fn on_event(&mut self, ev: Event) {
         let tev = match ev {
-            Event::DocumentStart(_) => TestEvent::OnDocumentStart,
+            Event::DocumentStart(..) => TestEvent::OnDocumentStart,
             Event::DocumentEnd => TestEvent::OnDocumentEnd,
             Event::SequenceStart(..) => TestEvent::OnSequenceStart,
             Event::SequenceEnd => TestEvent::OnSequenceEnd,
@@ -4,7 +4,10 @@ use libtest_mimic::{run_tests, Arguments, Outcome, Test};
 
 use saphyr::{Hash, Yaml};
 use saphyr_parser::{
-    Event, Marker, Parser, ScanError, Span, SpannedEventReceiver, TScalarStyle, Tag,
+    cst,
+    event_notation::{format_event, parse_events},
+    Event, IncrementalParser, LexemeKind, Lexer, Marker, Parser, Progress, ScanError, Span,
+    SpannedEventReceiver, TScalarStyle, TokenType,
 };
 
 type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
@@ -136,7 +139,7 @@ fn load_tests_from_file(entry: &DirEntry) -> Result<Vec<Test<YamlTest>>> {
     Ok(result)
 }
 
-fn parse_to_events(source: &str) -> Result<EventReporter, ScanError> {
+fn parse_to_events(source: &str) -> Result<EventReporter<'_>, ScanError> {
     let mut str_events = vec![];
     let mut str_error = None;
     let mut iter_events = vec![];
@@ -167,6 +170,51 @@ fn parse_to_events(source: &str) -> Result<EventReporter, ScanError> {
     assert_eq!(str_events, iter_events);
     // Or the same error.
     assert_eq!(str_error, iter_error);
+
+    // Feeding the input one character at a time must yield the same events.
+    let (incremental_events, incremental_error) = parse_incremental(source);
+    assert_eq!(str_events, incremental_events);
+    assert_eq!(str_error, incremental_error);
+
+    // Parsing asynchronously must yield the same events, even when bytes come one at a time.
+    #[cfg(feature = "async")]
+    {
+        let (async_events, async_error) = parse_async(source);
+        assert_eq!(str_events, async_events);
+        assert_eq!(str_error, async_error);
+    }
+
+    // Keeping comments must not alter the other events.
+    let mut comment_events = vec![];
+    let mut comment_error = None;
+    for x in Parser::new_from_str(source).keep_comments(true) {
+        match x {
+            Ok((Event::Comment(..), _)) => {}
+            Ok(event) => comment_events.push(event),
+            Err(e) => {
+                comment_error = Some(e);
+                break;
+            }
+        }
+    }
+    assert_eq!(str_events, comment_events);
+    assert_eq!(str_error, comment_error);
+
+    // Recovering from errors must not alter the events of valid input. Otherwise, the first error
+    // must be the same and the events must still be well-formed.
+    let mut parser = Parser::new_from_str(source).recover_errors(true);
+    let recovered_events = parser
+        .by_ref()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("the parser must recover from all errors");
+    assert_eq!(parser.take_errors().first(), str_error.as_ref());
+    if str_error.is_none() {
+        assert_eq!(str_events, recovered_events);
+    } else {
+        assert_well_formed(&recovered_events);
+    }
+    assert_lossless(source, str_error.is_none());
+
     // If we had an error, return it so the test fails.
     if let Some(err) = str_error {
         return Err(err);
@@ -180,16 +228,156 @@ fn parse_to_events(source: &str) -> Result<EventReporter, ScanError> {
     Ok(reporter)
 }
 
+/// Check that the [`Lexer`] reports every byte of `source`, in order and with contiguous spans.
+///
+/// If `source` is invalid YAML, the lexer may fail earlier than the parser. It must then report
+/// every byte up to where it failed.
+fn assert_lossless(source: &str, valid: bool) {
+    let mut end = Marker::new(0, 1, 0);
+    for lexeme in Lexer::new(source) {
+        let Ok(lexeme) = lexeme else {
+            assert!(!valid, "the lexer failed on valid input");
+            return;
+        };
+        assert_eq!(lexeme.span.start, end, "lexemes must be contiguous");
+        assert_eq!(&source[lexeme.span.byte_range()], lexeme.text);
+        match &lexeme.kind {
+            LexemeKind::Whitespace => {
+                assert!(lexeme.text.chars().all(|c| c == ' ' || c == '\t'));
+            }
+            // Only scalars and directives may span whitespace.
+            LexemeKind::Token(
+                TokenType::Scalar(..)
+                | TokenType::VersionDirective(..)
+                | TokenType::TagDirective(..),
+            ) => {}
+            LexemeKind::Token(token) => assert!(
+                !lexeme.text.contains(char::is_whitespace),
+                "{token:?} spans {:?}",
+                lexeme.text
+            ),
+            _ => {}
+        }
+        end = lexeme.span.end;
+    }
+    assert_eq!(
+        end.byte_offset(),
+        source.len(),
+        "the lexer must reach the end of the input"
+    );
+
+    // The concrete syntax tree, built from the lexer, must be lossless as well.
+    let root = cst::parse(source).unwrap();
+    assert_eq!(root.to_string(), source);
+}
+
+/// Parse `source` with an [`IncrementalParser`] that is fed one character at a time.
+///
+/// [`IncrementalParser`]: saphyr_parser::IncrementalParser
+fn parse_incremental(source: &str) -> (Vec<(Event<'static>, Span)>, Option<ScanError>) {
+    let mut parser = IncrementalParser::new();
+    let mut chars = source.char_indices();
+    let mut events = vec![];
+    loop {
+        match parser.next_event() {
+            Ok(Progress::Event(event, span)) => events.push((event, span)),
+            Ok(Progress::NeedMoreInput) => match chars.next() {
+                Some((idx, c)) => parser.feed(&source[idx..idx + c.len_utf8()]),
+                None => parser.finish(),
+            },
+            Ok(Progress::Finished) => return (events, None),
+            Err(e) => return (events, Some(e)),
+        }
+    }
+}
+
+/// Parse `source` with an [`AsyncParser`] that is fed one byte at a time.
+///
+/// [`AsyncParser`]: saphyr_parser::AsyncParser
+#[cfg(feature = "async")]
+fn parse_async(source: &str) -> (Vec<(Event<'static>, Span)>, Option<ScanError>) {
+    /// A reader returning one byte per read.
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl futures::io::AsyncRead for ByteReader<'_> {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            std::task::Poll::Ready(Ok(n))
+        }
+    }
+
+    futures::executor::block_on(async {
+        let mut parser = saphyr_parser::AsyncParser::new(ByteReader(source.as_bytes()));
+        let mut events = vec![];
+        while let Some(x) = parser.next_event_async().await {
+            match x {
+                Ok(event) => events.push(event),
+                Err(e) => return (events, Some(e)),
+            }
+        }
+        (events, None)
+    })
+}
+
+/// Check that the events form a valid YAML stream.
+///
+/// Documents and collections must be properly nested and closed, and mappings must have as many
+/// keys as values.
+fn assert_well_formed(events: &[(Event, Span)]) {
+    assert_eq!(events.first().map(|x| &x.0), Some(&Event::StreamStart));
+    assert_eq!(events.last().map(|x| &x.0), Some(&Event::StreamEnd));
+    let mut in_document = false;
+    // Whether each nested collection is a mapping, and its number of nodes.
+    let mut collections: Vec<(bool, usize)> = vec![];
+    for (ev, _) in &events[1..events.len() - 1] {
+        match ev {
+            Event::DocumentStart(..) => {
+                assert!(!in_document);
+                in_document = true;
+            }
+            Event::DocumentEnd => {
+                assert!(in_document && collections.is_empty());
+                in_document = false;
+            }
+            Event::Scalar(..) | Event::Alias(..) => {
+                assert!(in_document);
+                if let Some(parent) = collections.last_mut() {
+                    parent.1 += 1;
+                }
+            }
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                assert!(in_document);
+                if let Some(parent) = collections.last_mut() {
+                    parent.1 += 1;
+                }
+                collections.push((matches!(ev, Event::MappingStart(..)), 0));
+            }
+            Event::SequenceEnd => assert!(matches!(collections.pop(), Some((false, _)))),
+            Event::MappingEnd => {
+                assert!(matches!(collections.pop(), Some((true, n)) if n % 2 == 0));
+            }
+            _ => panic!("unexpected event {ev:?}"),
+        }
+    }
+    assert!(!in_document);
+}
+
 #[derive(Default)]
 /// A [`SpannedEventReceiver`] checking for inconsistencies in event [`Spans`].
-pub struct EventReporter {
+pub struct EventReporter<'input> {
     pub events: Vec<String>,
-    last_span: Option<(Event, Span)>,
+    last_span: Option<(Event<'input>, Span)>,
     pub span_failures: Vec<(String, Span)>,
 }
 
-impl SpannedEventReceiver for EventReporter {
-    fn on_event(&mut self, ev: Event, span: Span) {
+impl<'input> SpannedEventReceiver<'input> for EventReporter<'input> {
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
         if let Some((last_ev, last_span)) = self.last_span.take() {
             if span.start.index() < last_span.start.index()
                 || span.end.index() < last_span.end.index()
@@ -202,73 +390,9 @@ impl SpannedEventReceiver for EventReporter {
         }
         self.last_span = Some((ev.clone(), span));
 
-        let line: String = match ev {
-            Event::StreamStart => "+STR".into(),
-            Event::StreamEnd => "-STR".into(),
-
-            Event::DocumentStart(_) => "+DOC".into(),
-            Event::DocumentEnd => "-DOC".into(),
-
-            Event::SequenceStart(idx, tag) => {
-                format!("+SEQ{}{}", format_index(idx), format_tag(&tag))
-            }
-            Event::SequenceEnd => "-SEQ".into(),
-
-            Event::MappingStart(idx, tag) => {
-                format!("+MAP{}{}", format_index(idx), format_tag(&tag))
-            }
-            Event::MappingEnd => "-MAP".into(),
-
-            Event::Scalar(ref text, style, idx, ref tag) => {
-                let kind = match style {
-                    TScalarStyle::Plain => ":",
-                    TScalarStyle::SingleQuoted => "'",
-                    TScalarStyle::DoubleQuoted => r#"""#,
-                    TScalarStyle::Literal => "|",
-                    TScalarStyle::Folded => ">",
-                };
-                format!(
-                    "=VAL{}{} {}{}",
-                    format_index(idx),
-                    format_tag(tag),
-                    kind,
-                    escape_text(text)
-                )
-            }
-            Event::Alias(idx) => format!("=ALI *{idx}"),
-            Event::Nothing => return,
-        };
-        self.events.push(line);
-    }
-}
-
-fn format_index(idx: usize) -> String {
-    if idx > 0 {
-        format!(" &{idx}")
-    } else {
-        String::new()
-    }
-}
-
-fn escape_text(text: &str) -> String {
-    let mut text = text.to_owned();
-    for (ch, replacement) in [
-        ('\\', r"\\"),
-        ('\n', "\\n"),
-        ('\r', "\\r"),
-        ('\x08', "\\b"),
-        ('\t', "\\t"),
-    ] {
-        text = text.replace(ch, replacement);
-    }
-    text
-}
-
-fn format_tag(tag: &Option<Tag>) -> String {
-    if let Some(tag) = tag {
-        format!(" <{}{}>", tag.handle, tag.suffix)
-    } else {
-        String::new()
+        if let Some(line) = format_event(&ev, |_| None) {
+            self.events.push(line);
+        }
     }
 }
 
@@ -295,64 +419,22 @@ fn events_differ(actual: &[String], expected: &str) -> Option<String> {
     unreachable!()
 }
 
-/// Convert the snippets from "visual" to "actual" representation
-fn visual_to_raw(yaml: &str) -> String {
-    let mut yaml = yaml.to_owned();
-    for (pat, replacement) in [
-        ("␣", " "),
-        ("»", "\t"),
-        ("—", ""), // Tab line continuation ——»
-        ("←", "\r"),
-        ("⇔", "\u{FEFF}"),
-        ("↵", ""), // Trailing newline marker
-        ("∎\n", ""),
-    ] {
-        yaml = yaml.replace(pat, replacement);
-    }
-    yaml
-}
+include!("visual_to_raw.rs.inc");
 
 /// Adapt the expectations to the yaml-rust reasonable limitations
 ///
 /// Drop information on node styles (flow/block) and anchor names.
 /// Both are things that can be omitted according to spec.
 fn expected_events(expected_tree: &str) -> Vec<String> {
-    let mut anchors = vec![];
-    expected_tree
-        .split('\n')
-        .map(|s| s.trim_start().to_owned())
-        .filter(|s| !s.is_empty())
-        .map(|mut s| {
-            // Anchor name-to-number conversion
-            if let Some(start) = s.find('&') {
-                if s[..start].find(':').is_none() {
-                    let len = s[start..].find(' ').unwrap_or(s[start..].len());
-                    anchors.push(s[start + 1..start + len].to_owned());
-                    s = s.replace(&s[start..start + len], &format!("&{}", anchors.len()));
-                }
-            }
-            // Alias nodes name-to-number
-            if s.starts_with("=ALI") {
-                let start = s.find('*').unwrap();
-                let name = &s[start + 1..];
-                let idx = anchors
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, v)| v == &name)
-                    .last()
-                    .unwrap()
-                    .0;
-                s = s.replace(&s[start..], &format!("*{}", idx + 1));
-            }
-            // Dropping style information
-            match &*s {
-                "+DOC ---" => "+DOC".into(),
-                "-DOC ..." => "-DOC".into(),
-                s if s.starts_with("+SEQ []") => s.replacen("+SEQ []", "+SEQ", 1),
-                s if s.starts_with("+MAP {}") => s.replacen("+MAP {}", "+MAP", 1),
-                "=VAL :" => "=VAL :~".into(), // FIXME: known bug
-                s => s.into(),
+    parse_events(expected_tree)
+        .unwrap()
+        .iter()
+        .map(|ev| match ev {
+            // FIXME: known bug
+            Event::Scalar(value, TScalarStyle::Plain, 0, None) if value.is_empty() => {
+                "=VAL :~".into()
             }
+            ev => format_event(ev, |_| None).unwrap(),
         })
         .collect()
 }
@@ -2,23 +2,34 @@ use std::env;
 use std::fs::File;
 use std::io::prelude::*;
 
-use saphyr_parser::{Event, Parser, Span, SpannedEventReceiver};
+use saphyr_parser::{
+    event_notation::format_events, Event, Parser, Span, SpannedEventReceiver, StrInput,
+};
 
 #[derive(Debug)]
-struct EventSink {
-    events: Vec<(Event, Span)>,
+struct EventSink<'input> {
+    events: Vec<(Event<'input>, Span)>,
+    /// Whether to print events as they are received.
+    verbose: bool,
 }
 
-impl SpannedEventReceiver for EventSink {
-    fn on_event(&mut self, ev: Event, span: Span) {
-        eprintln!("      \x1B[;34m\u{21B3} {:?}\x1B[;m", &ev);
+impl<'input> SpannedEventReceiver<'input> for EventSink<'input> {
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        if self.verbose {
+            eprintln!("      \x1B[;34m\u{21B3} {:?}\x1B[;m", &ev);
+        }
         self.events.push((ev, span));
     }
 }
 
-fn str_to_events(yaml: &str) -> Vec<(Event, Span)> {
-    let mut sink = EventSink { events: Vec::new() };
-    let mut parser = Parser::new_from_str(yaml);
+fn str_to_events<'input>(
+    parser: &mut Parser<'input, StrInput<'input>>,
+    verbose: bool,
+) -> Vec<(Event<'input>, Span)> {
+    let mut sink = EventSink {
+        events: Vec::new(),
+        verbose,
+    };
     // Load events using our sink as the receiver.
     parser.load(&mut sink, true).unwrap();
     sink.events
@@ -26,10 +37,15 @@ fn str_to_events(yaml: &str) -> Vec<(Event, Span)> {
 
 fn main() {
     let args: Vec<_> = env::args().collect();
+    let notation = args[2..].iter().any(|arg| arg == "--notation");
     let mut f = File::open(&args[1]).unwrap();
     let mut s = String::new();
     f.read_to_string(&mut s).unwrap();
 
     // dbg!(str_to_events(&s));
-    str_to_events(&s);
+    let mut parser = Parser::new_from_str(&s);
+    let events = str_to_events(&mut parser, !notation);
+    if notation {
+        print!("{}", format_events(events, |id| parser.anchor_name(id)));
+    }
 }
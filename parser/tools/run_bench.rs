@@ -6,7 +6,7 @@ use std::{env, fs::File, io::prelude::*};
 /// A sink which discards any event sent.
 struct NullSink {}
 
-impl SpannedEventReceiver for NullSink {
+impl SpannedEventReceiver<'_> for NullSink {
     fn on_event(&mut self, _: Event, _: Span) {}
 }
 
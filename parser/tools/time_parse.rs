@@ -7,7 +7,7 @@ use saphyr_parser::{Event, Parser, Span, SpannedEventReceiver};
 /// A sink which discards any event sent.
 struct NullSink {}
 
-impl SpannedEventReceiver for NullSink {
+impl SpannedEventReceiver<'_> for NullSink {
     fn on_event(&mut self, _: Event, _: Span) {}
 }
 
@@ -3,11 +3,14 @@
 //! This is set aside so as to not clutter `annotated.rs`.
 
 use hashlink::LinkedHashMap;
-use saphyr_parser::{BufferedInput, Input, Parser, ScanError, Span};
+use saphyr_parser::{
+    path::{PathSegment, SubtreeExtractor},
+    BorrowedInput, BufferedInput, Parser, ScanError, Span,
+};
 
-use crate::{LoadableYamlNode, Yaml, YamlData, YamlLoader};
+use crate::{LoadError, LoadableYamlNode, Yaml, YamlData, YamlLoader};
 
-/// A YAML node with [`Span`]s pointing to the start of the node.
+/// A YAML node with the [`Span`] of the node in the source.
 ///
 /// This structure does not implement functions to operate on the YAML object. To access those,
 /// refer to the [`Self::data`] field.
@@ -15,6 +18,10 @@ use crate::{LoadableYamlNode, Yaml, YamlData, YamlLoader};
 pub struct MarkedYaml {
     /// The span indicating where in the input stream the object is.
     ///
+    /// The span covers the whole node: collections span up to the end of their last child, or to
+    /// their closing bracket for flow collections. Aliases span the alias itself, not the node
+    /// they refer to.
+    ///
     /// The markers are relative to the start of the input stream that was given to the parser, not
     /// to the start of the document within the input stream.
     pub span: Span,
@@ -56,11 +63,79 @@ impl MarkedYaml {
     /// Returns `ScanError` when loading fails.
     ///
     /// [`load_from_str`]: `Yaml::load_from_str`
-    pub fn load_from_parser<I: Input>(parser: &mut Parser<I>) -> Result<Vec<Self>, ScanError> {
+    pub fn load_from_parser<'input, I: BorrowedInput<'input>>(
+        parser: &mut Parser<'input, I>,
+    ) -> Result<Vec<Self>, ScanError> {
         let mut loader = YamlLoader::<Self>::default();
         parser.load(&mut loader, true)?;
         Ok(loader.into_documents())
     }
+
+    /// Load the nodes at `path` in the documents of the given string.
+    ///
+    /// See the function [`load_subtree_from_str`] for more details. The markers are relative to
+    /// the start of `source`.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    ///
+    /// [`load_subtree_from_str`]: `Yaml::load_subtree_from_str`
+    pub fn load_subtree_from_str(
+        source: &str,
+        path: &[PathSegment],
+    ) -> Result<Vec<Self>, ScanError> {
+        let mut extractor =
+            SubtreeExtractor::new(YamlLoader::<Self>::default(), path.iter().cloned());
+        Parser::new_from_str(source).load(&mut extractor, true)?;
+        Ok(extractor.into_inner().into_documents())
+    }
+
+    /// Load the given string as an array of YAML documents, bounding alias expansion.
+    ///
+    /// See the function [`safe_load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError` when loading fails or when the budget is exceeded.
+    ///
+    /// [`safe_load_from_str`]: `Yaml::safe_load_from_str`
+    pub fn safe_load_from_str(source: &str, alias_budget: usize) -> Result<Vec<Self>, LoadError> {
+        Self::safe_load_from_iter(source.chars(), alias_budget)
+    }
+
+    /// Load the contents of the given iterator as an array of YAML documents, bounding alias
+    /// expansion.
+    ///
+    /// See the function [`safe_load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError` when loading fails or when the budget is exceeded.
+    ///
+    /// [`safe_load_from_str`]: `Yaml::safe_load_from_str`
+    pub fn safe_load_from_iter<I: Iterator<Item = char>>(
+        source: I,
+        alias_budget: usize,
+    ) -> Result<Vec<Self>, LoadError> {
+        let mut parser = Parser::new(BufferedInput::new(source));
+        Self::safe_load_from_parser(&mut parser, alias_budget)
+    }
+
+    /// Load the contents from the specified [`Parser`] as an array of YAML documents, bounding
+    /// alias expansion.
+    ///
+    /// See the function [`safe_load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `LoadError` when loading fails or when the budget is exceeded.
+    ///
+    /// [`safe_load_from_str`]: `Yaml::safe_load_from_str`
+    pub fn safe_load_from_parser<'input, I: BorrowedInput<'input>>(
+        parser: &mut Parser<'input, I>,
+        alias_budget: usize,
+    ) -> Result<Vec<Self>, LoadError> {
+        YamlLoader::<Self>::default()
+            .alias_budget(alias_budget)
+            .load_from_parser(parser)
+    }
 }
 
 impl PartialEq for MarkedYaml {
@@ -35,6 +35,7 @@ pub type YAMLDecodingTrapFn = fn(
 
 /// The behavior [`YamlDecoder`] must have when an decoding error occurs.
 #[derive(Copy, Clone, PartialEq, Eq)]
+#[allow(unknown_lints, unpredictable_function_pointer_comparisons)]
 pub enum YAMLDecodingTrap {
     /// Ignore the offending bytes, remove them from the output.
     Ignore,
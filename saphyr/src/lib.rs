@@ -39,6 +39,10 @@
 //! Enables encoding-aware decoding of Yaml documents.
 //!
 //! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `parallel`
+//! Enables `Yaml::load_from_str_parallel` and `MarkedYaml::load_from_str_parallel`, which split
+//! multi-document streams between documents and load them on several threads.
 
 #![warn(missing_docs, clippy::pedantic)]
 
@@ -50,17 +54,20 @@ mod char_traits;
 mod emitter;
 mod loader;
 mod yaml;
+mod yaml11;
 
 // Re-export main components.
 pub use crate::annotated::{
     marked_yaml::MarkedYaml, AnnotatedArray, AnnotatedHash, AnnotatedYamlIter, YamlData,
 };
 pub use crate::emitter::YamlEmitter;
-pub use crate::loader::{LoadableYamlNode, YamlLoader};
+pub use crate::loader::{LoadError, LoadableYamlNode, Yaml11Mode, YamlLoader};
 pub use crate::yaml::{Array, Hash, Yaml, YamlIter};
 
 #[cfg(feature = "encoding")]
 mod encoding;
+#[cfg(feature = "parallel")]
+mod parallel;
 #[cfg(feature = "encoding")]
 pub use crate::encoding::{YAMLDecodingTrap, YAMLDecodingTrapFn, YamlDecoder};
 
@@ -69,3 +76,9 @@ pub use crate::encoding::{YAMLDecodingTrap, YAMLDecodingTrapFn, YamlDecoder};
 pub use saphyr_parser::ScanError;
 // Re-export [`Marker`] which is used for annotated YAMLs.
 pub use saphyr_parser::Marker;
+// Re-export the diagnostic renderer so that `LoadError`s can be displayed with a source snippet.
+pub use saphyr_parser::{Diagnostic, RenderStyle};
+// Re-export `Warning`s, which the `YamlLoader` reports.
+pub use saphyr_parser::{Warning, WarningKind, MAX_BUFFERED_WARNINGS};
+// Re-export `PathSegment`, which names the nodes to load with `load_subtree_from_str`.
+pub use saphyr_parser::path::PathSegment;
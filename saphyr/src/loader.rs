@@ -3,9 +3,12 @@
 use std::{collections::BTreeMap, sync::Arc};
 
 use hashlink::LinkedHashMap;
-use saphyr_parser::{Event, ScanError, Span, SpannedEventReceiver, TScalarStyle, Tag};
+use saphyr_parser::{
+    BorrowedInput, Diagnostic, Event, Parser, ScanError, Span, SpannedEventReceiver, TScalarStyle,
+    Tag, Warning, WarningKind, MAX_BUFFERED_WARNINGS,
+};
 
-use crate::{Hash, Yaml};
+use crate::{yaml11, Hash, Yaml};
 
 /// Main structure for parsing YAML.
 ///
@@ -14,6 +17,25 @@ use crate::{Hash, Yaml};
 ///
 /// Each node must implement [`LoadableYamlNode`]. The methods are required for the loader to
 /// manipulate and populate the `Node`.
+///
+/// # Aliases
+/// Each alias is replaced by a copy of the node it refers to. A small document with nested
+/// aliases can thus expand to a huge number of nodes (the "billion laughs" attack). When loading
+/// untrusted input, set an [`alias_budget`] to bound the number of nodes aliases may expand to.
+///
+/// # YAML 1.1
+/// Plain scalars are resolved following the YAML 1.2 Core schema (see [`Yaml::from_str`]). Inputs
+/// written for YAML 1.1 may rely on its types instead (e.g. `yes` being a boolean). Set a
+/// [`yaml11_mode`] to resolve them following YAML 1.1 (see [`Yaml::from_str_1_1`]).
+///
+/// Either way, if [`collect_warnings`] is set, plain scalars that YAML 1.1 and YAML 1.2 resolve
+/// differently are reported as [`WarningKind::AmbiguousScalar`] warnings, which can be retrieved
+/// with [`take_warnings`].
+///
+/// [`alias_budget`]: Self::alias_budget
+/// [`yaml11_mode`]: Self::yaml11_mode
+/// [`collect_warnings`]: Self::collect_warnings
+/// [`take_warnings`]: Self::take_warnings
 #[allow(clippy::module_name_repetitions)]
 pub struct YamlLoader<Node>
 where
@@ -22,10 +44,41 @@ where
     /// The different YAML documents that are loaded.
     docs: Vec<Node>,
     // states
-    // (current node, anchor_id) tuple
-    doc_stack: Vec<(Node, usize)>,
+    // (current node, anchor_id, node count, span) tuple
+    //
+    // The span of collections covers their children loaded so far.
+    doc_stack: Vec<(Node, usize, usize, Span)>,
     key_stack: Vec<Node>,
-    anchor_map: BTreeMap<usize, Node>,
+    // anchor_id -> (node, node count)
+    anchor_map: BTreeMap<usize, (Node, usize)>,
+    /// The maximum number of nodes aliases may expand to, if any.
+    alias_budget: Option<usize>,
+    /// The number of nodes aliases have expanded to so far.
+    expanded_nodes: usize,
+    /// When to resolve scalars following YAML 1.1.
+    yaml11_mode: Yaml11Mode,
+    /// Whether scalars of the current document are resolved following YAML 1.1.
+    yaml11: bool,
+    /// Whether warnings are collected into [`Self::warnings`].
+    collect_warnings: bool,
+    /// The warnings that were found so far.
+    warnings: Vec<Warning>,
+    /// The error that stopped the loading, if any.
+    error: Option<LoadError>,
+}
+
+/// When a [`YamlLoader`] resolves plain scalars following YAML 1.1 rather than YAML 1.2.
+///
+/// See [`YamlLoader::yaml11_mode`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Yaml11Mode {
+    /// Always follow YAML 1.2, regardless of the `%YAML` directive.
+    #[default]
+    Never,
+    /// Follow YAML 1.1 in documents with a `%YAML 1.1` (or `%YAML 1.0`) directive.
+    FromDirective,
+    /// Always follow YAML 1.1, even in documents with a `%YAML 1.2` directive.
+    Always,
 }
 
 // For some reason, rustc wants `Node: Default` if I `#[derive(Default)]`.
@@ -39,17 +92,36 @@ where
             doc_stack: vec![],
             key_stack: vec![],
             anchor_map: BTreeMap::new(),
+            alias_budget: None,
+            expanded_nodes: 0,
+            yaml11_mode: Yaml11Mode::default(),
+            yaml11: false,
+            collect_warnings: false,
+            warnings: vec![],
+            error: None,
         }
     }
 }
 
-impl<Node> SpannedEventReceiver for YamlLoader<Node>
+impl<'input, Node> SpannedEventReceiver<'input> for YamlLoader<Node>
 where
     Node: LoadableYamlNode,
 {
-    fn on_event(&mut self, ev: Event, span: Span) {
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        if self.error.is_some() {
+            return;
+        }
         match ev {
-            Event::DocumentStart(_) | Event::Nothing | Event::StreamStart | Event::StreamEnd => {
+            Event::DocumentStart(_, directives) => {
+                self.yaml11 = match self.yaml11_mode {
+                    Yaml11Mode::Never => false,
+                    Yaml11Mode::FromDirective => {
+                        matches!(directives.version, Some((1, minor)) if minor < 2)
+                    }
+                    Yaml11Mode::Always => true,
+                };
+            }
+            Event::Nothing | Event::StreamStart | Event::StreamEnd | Event::Comment(..) => {
                 // do nothing
             }
             Event::DocumentEnd => {
@@ -66,70 +138,49 @@ where
                 self.doc_stack.push((
                     Node::from_bare_yaml(Yaml::Array(Vec::new())).with_span(span),
                     aid,
+                    1,
+                    span,
                 ));
             }
             Event::SequenceEnd => {
                 let node = self.doc_stack.pop().unwrap();
-                self.insert_new_node(node);
+                self.insert_new_node(Self::end_collection(node, span));
             }
             Event::MappingStart(aid, _) => {
                 self.doc_stack.push((
                     Node::from_bare_yaml(Yaml::Hash(Hash::new())).with_span(span),
                     aid,
+                    1,
+                    span,
                 ));
                 self.key_stack.push(Node::from_bare_yaml(Yaml::BadValue));
             }
             Event::MappingEnd => {
                 self.key_stack.pop().unwrap();
                 let node = self.doc_stack.pop().unwrap();
-                self.insert_new_node(node);
+                self.insert_new_node(Self::end_collection(node, span));
             }
             Event::Scalar(v, style, aid, tag) => {
-                let node = if style != TScalarStyle::Plain {
-                    Yaml::String(v)
-                } else if let Some(Tag {
-                    ref handle,
-                    ref suffix,
-                }) = tag
-                {
-                    if handle == "tag:yaml.org,2002:" {
-                        match suffix.as_ref() {
-                            "bool" => {
-                                // "true" or "false"
-                                match v.parse::<bool>() {
-                                    Err(_) => Yaml::BadValue,
-                                    Ok(v) => Yaml::Boolean(v),
-                                }
-                            }
-                            "int" => match v.parse::<i64>() {
-                                Err(_) => Yaml::BadValue,
-                                Ok(v) => Yaml::Integer(v),
-                            },
-                            "float" => match parse_f64(&v) {
-                                Some(_) => Yaml::Real(v),
-                                None => Yaml::BadValue,
-                            },
-                            "null" => match v.as_ref() {
-                                "~" | "null" => Yaml::Null,
-                                _ => Yaml::BadValue,
-                            },
-                            _ => Yaml::String(v),
-                        }
-                    } else {
-                        Yaml::String(v)
-                    }
-                } else {
-                    // Datatype is not specified, or unrecognized
-                    Yaml::from_str(&v)
-                };
-                self.insert_new_node((Node::from_bare_yaml(node).with_span(span), aid));
+                let node = self.resolve_scalar(&v, style, tag.as_ref());
+                if self.collect_warnings && style == TScalarStyle::Plain && tag.is_none() {
+                    self.check_ambiguous_scalar(&v, &node, span);
+                }
+                self.insert_new_node((Node::from_bare_yaml(node).with_span(span), aid, 1, span));
             }
             Event::Alias(id) => {
+                let count = self.anchor_map.get(&id).map_or(1, |(_, count)| *count);
+                if let Some(budget) = self.alias_budget {
+                    self.expanded_nodes = self.expanded_nodes.saturating_add(count);
+                    if self.expanded_nodes > budget {
+                        self.error = Some(LoadError::AliasBudgetExceeded(span));
+                        return;
+                    }
+                }
                 let n = match self.anchor_map.get(&id) {
-                    Some(v) => v.clone(),
+                    Some((v, _)) => v.clone(),
                     None => Node::from_bare_yaml(Yaml::BadValue),
                 };
-                self.insert_new_node((n.with_span(span), 0));
+                self.insert_new_node((n.with_span(span), 0, count, span));
             }
         }
     }
@@ -139,12 +190,195 @@ impl<Node> YamlLoader<Node>
 where
     Node: LoadableYamlNode,
 {
-    fn insert_new_node(&mut self, node: (Node, usize)) {
+    /// Set the maximum number of nodes aliases may expand to.
+    ///
+    /// Each time an alias is encountered, the number of nodes in the anchored node (itself
+    /// included) is added to a counter. Once the counter exceeds `budget`, loading stops with
+    /// [`LoadError::AliasBudgetExceeded`]. By default, there is no budget.
+    #[must_use]
+    pub fn alias_budget(mut self, budget: usize) -> Self {
+        self.alias_budget = Some(budget);
+        self
+    }
+
+    /// Set when to resolve plain scalars following YAML 1.1.
+    ///
+    /// By default, scalars are resolved following YAML 1.2, as with [`Yaml11Mode::Never`].
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, Yaml11Mode, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().yaml11_mode(Yaml11Mode::FromDirective);
+    /// let mut parser = Parser::new_from_str("%YAML 1.1\n---\n[yes, 010]\n---\n[yes, 010]\n");
+    /// parser.load(&mut loader, true).unwrap();
+    /// let docs = loader.into_documents();
+    /// assert_eq!(docs[0][0], Yaml::Boolean(true));
+    /// assert_eq!(docs[0][1], Yaml::Integer(8));
+    /// assert_eq!(docs[1][0], Yaml::String("yes".into()));
+    /// assert_eq!(docs[1][1], Yaml::Integer(10));
+    /// ```
+    #[must_use]
+    pub fn yaml11_mode(mut self, mode: Yaml11Mode) -> Self {
+        self.yaml11_mode = mode;
+        self
+    }
+
+    /// Whether to collect warnings, to be retrieved with [`Self::take_warnings`].
+    ///
+    /// By default, no warning is collected and scalars are not checked for ambiguity.
+    #[must_use]
+    pub fn collect_warnings(mut self, value: bool) -> Self {
+        self.collect_warnings = value;
+        self
+    }
+
+    /// Return the error that stopped the loading, if any.
+    ///
+    /// When an error occurs, the loader ignores any further event and the documents it holds are
+    /// incomplete.
+    #[must_use]
+    pub fn error(&self) -> Option<&LoadError> {
+        self.error.as_ref()
+    }
+
+    /// Return the warnings found so far, leaving none in their place.
+    ///
+    /// The loader only reports [`WarningKind::AmbiguousScalar`]s. Other warnings are found by the
+    /// parser (see [`Parser::take_warnings`]). Warnings are only collected if enabled with
+    /// [`Self::collect_warnings`], and at most [`MAX_BUFFERED_WARNINGS`] are kept until they are
+    /// taken.
+    ///
+    /// ```
+    /// # use saphyr::{WarningKind, Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().collect_warnings(true);
+    /// Parser::new_from_str("[on, 'off', 1]").load(&mut loader, true).unwrap();
+    /// let warnings = loader.take_warnings();
+    /// assert_eq!(warnings.len(), 1);
+    /// assert_eq!(warnings[0].kind(), WarningKind::AmbiguousScalar);
+    /// ```
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Load all the documents from `parser`.
+    ///
+    /// Contrary to [`Parser::load`], this stops as soon as the loader encounters an error.
+    pub(crate) fn load_from_parser<'input, I: BorrowedInput<'input>>(
+        mut self,
+        parser: &mut Parser<'input, I>,
+    ) -> Result<Vec<Node>, LoadError> {
+        for event in parser {
+            let (ev, span) = event.map_err(LoadError::Scan)?;
+            self.on_event(ev, span);
+            if let Some(error) = self.error {
+                return Err(error);
+            }
+        }
+        Ok(self.docs)
+    }
+
+    /// Resolve the value of a scalar from its style and tag.
+    fn resolve_scalar(&self, v: &str, style: TScalarStyle, tag: Option<&Tag>) -> Yaml {
+        if style != TScalarStyle::Plain {
+            return Yaml::String(v.to_owned());
+        }
+        let Some(Tag { handle, suffix }) = tag else {
+            // Datatype is not specified
+            return if self.yaml11 {
+                Yaml::from_str_1_1(v)
+            } else {
+                Yaml::from_str(v)
+            };
+        };
+        if handle != "tag:yaml.org,2002:" {
+            return Yaml::String(v.to_owned());
+        }
+        match suffix.as_ref() {
+            "bool" => {
+                let value = if self.yaml11 {
+                    yaml11::parse_bool(v)
+                } else {
+                    // "true" or "false"
+                    v.parse::<bool>().ok()
+                };
+                value.map_or(Yaml::BadValue, Yaml::Boolean)
+            }
+            "int" => {
+                let value = if self.yaml11 {
+                    yaml11::parse_int(v)
+                } else {
+                    v.parse::<i64>().ok()
+                };
+                value.map_or(Yaml::BadValue, Yaml::Integer)
+            }
+            "float" => {
+                let value = if self.yaml11 {
+                    yaml11::parse_float(v)
+                } else {
+                    parse_f64(v).map(|_| v.to_owned())
+                };
+                value.map_or(Yaml::BadValue, Yaml::Real)
+            }
+            "null" => {
+                let null = if self.yaml11 {
+                    yaml11::is_null(v)
+                } else {
+                    matches!(v, "~" | "null")
+                };
+                if null {
+                    Yaml::Null
+                } else {
+                    Yaml::BadValue
+                }
+            }
+            _ => Yaml::String(v.to_owned()),
+        }
+    }
+
+    /// Warn if the untagged plain scalar `v`, resolved as `node`, would be resolved differently
+    /// with the other YAML version.
+    fn check_ambiguous_scalar(&mut self, v: &str, node: &Yaml, span: Span) {
+        let other = if self.yaml11 {
+            Yaml::from_str(v)
+        } else {
+            Yaml::from_str_1_1(v)
+        };
+        if other != *node && self.warnings.len() < MAX_BUFFERED_WARNINGS {
+            self.warnings.push(Warning::new(
+                WarningKind::AmbiguousScalar,
+                span,
+                format!("`{v}` is resolved differently in YAML 1.1 and YAML 1.2"),
+            ));
+        }
+    }
+
+    /// Give a collection whose end event spans `end_span` the span of the whole collection.
+    ///
+    /// The end event of a flow collection spans its closing bracket, that of a block collection is
+    /// empty and placed where the next token starts (i.e. after blank lines and comments). Block
+    /// collections thus end where their last child ends.
+    fn end_collection(
+        (node, aid, count, span): (Node, usize, usize, Span),
+        end_span: Span,
+    ) -> (Node, usize, usize, Span) {
+        let end = if end_span.start.index() == end_span.end.index() {
+            span.end
+        } else {
+            end_span.end
+        };
+        let span = Span::new(span.start, end);
+        (node.with_span(span), aid, count, span)
+    }
+
+    fn insert_new_node(&mut self, node: (Node, usize, usize, Span)) {
         // valid anchor id starts from 1
         if node.1 > 0 {
-            self.anchor_map.insert(node.1, node.0.clone());
+            self.anchor_map.insert(node.1, (node.0.clone(), node.2));
         }
         if let Some(parent) = self.doc_stack.last_mut() {
+            parent.2 = parent.2.saturating_add(node.2);
+            parent.3.end = node.3.end;
             let parent_node = &mut parent.0;
             if parent_node.is_array() {
                 parent_node.array_mut().push(node.0);
@@ -165,10 +399,38 @@ where
     }
 
     /// Return the document nodes from `self`, consuming it in the process.
+    ///
+    /// If loading stopped on an [`error`], the documents are incomplete and the error is lost. Use
+    /// [`try_into_documents`] to have it reported.
+    ///
+    /// [`error`]: Self::error
+    /// [`try_into_documents`]: Self::try_into_documents
     #[must_use]
     pub fn into_documents(self) -> Vec<Node> {
         self.docs
     }
+
+    /// Return the document nodes from `self`, or the error that stopped the loading.
+    ///
+    /// ```
+    /// # use saphyr::{LoadError, Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().alias_budget(2);
+    /// Parser::new_from_str("a: &a [x, y]\nb: *a\n").load(&mut loader, true).unwrap();
+    /// assert!(matches!(
+    ///     loader.try_into_documents(),
+    ///     Err(LoadError::AliasBudgetExceeded(_))
+    /// ));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the [`LoadError`] that stopped the loading, if any.
+    pub fn try_into_documents(self) -> Result<Vec<Node>, LoadError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.docs),
+        }
+    }
 }
 
 /// An error that happened when loading a YAML document.
@@ -180,6 +442,10 @@ pub enum LoadError {
     Scan(ScanError),
     /// A decoding error (e.g.: Invalid UTF-8).
     Decode(std::borrow::Cow<'static, str>),
+    /// Aliases expanded to more nodes than the [`YamlLoader::alias_budget`] allows.
+    ///
+    /// The span is that of the alias at which the budget was exceeded.
+    AliasBudgetExceeded(Span),
 }
 
 impl From<std::io::Error> for LoadError {
@@ -193,7 +459,7 @@ impl std::error::Error for LoadError {
         Some(match &self {
             LoadError::IO(e) => e,
             LoadError::Scan(e) => e,
-            LoadError::Decode(_) => return None,
+            LoadError::Decode(_) | LoadError::AliasBudgetExceeded(_) => return None,
         })
     }
 }
@@ -204,6 +470,25 @@ impl std::fmt::Display for LoadError {
             LoadError::IO(e) => e.fmt(f),
             LoadError::Scan(e) => e.fmt(f),
             LoadError::Decode(e) => e.fmt(f),
+            LoadError::AliasBudgetExceeded(span) => write!(
+                f,
+                "alias expansion budget exceeded at byte {} line {} column {}",
+                span.start.byte_offset(),
+                span.start.line(),
+                span.start.col() + 1,
+            ),
+        }
+    }
+}
+
+impl From<&LoadError> for Diagnostic {
+    fn from(error: &LoadError) -> Self {
+        match error {
+            LoadError::Scan(e) => Diagnostic::from(e),
+            LoadError::AliasBudgetExceeded(span) => {
+                Diagnostic::new("alias expansion budget exceeded").with_span(*span)
+            }
+            LoadError::IO(_) | LoadError::Decode(_) => Diagnostic::new(error.to_string()),
         }
     }
 }
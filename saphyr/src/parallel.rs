@@ -0,0 +1,417 @@
+//! Parallel loading of multi-document streams. Available only with the `parallel` feature.
+//!
+//! The input is first split into chunks of whole documents by a line-based pre-scan. Chunks are
+//! then loaded on a pool of scoped threads, and their documents put back in order.
+//!
+//! Splitting must never separate what the parser would read as one document. The pre-scan only
+//! splits the input before a `---` at the start of a line, which starts a document everywhere but
+//! in a block scalar at the root of a document whose content is not indented. When in doubt, it
+//! does not split. Invalid inputs may still be split in the middle of a node (e.g. a quoted scalar
+//! spanning a `---` line); this is caught by the chunk failing to load.
+
+use std::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+use saphyr_parser::{Event, Marker, Parser, ScanError, Span, SpannedEventReceiver};
+
+use crate::{LoadableYamlNode, MarkedYaml, Yaml, YamlLoader};
+
+/// The minimum number of bytes of a chunk, below which spawning threads costs more than it saves.
+const MIN_CHUNK_LEN: usize = 64 * 1024;
+
+/// The number of chunks the input is split into for each thread.
+///
+/// Documents may take different times to load. Splitting the input into more chunks than there
+/// are threads keeps threads that loaded their chunks early busy.
+const CHUNKS_PER_THREAD: usize = 4;
+
+impl Yaml {
+    /// Load the given string as an array of YAML documents, on several threads.
+    ///
+    /// This returns the same documents as [`Self::load_from_str`]. The input is split between
+    /// documents, and documents are loaded on as many threads as
+    /// [`std::thread::available_parallelism`] reports. Inputs too small to be worth splitting are
+    /// loaded on the current thread.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let docs = Yaml::load_from_str_parallel("a: 1\n---\n- b\n---\nc\n").unwrap();
+    /// assert_eq!(docs.len(), 3);
+    /// assert_eq!(docs[1][0].as_str(), Some("b"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails. The error is the same as that of
+    /// [`Self::load_from_str`]: should a chunk fail to load, the whole input is loaded again on the
+    /// current thread.
+    pub fn load_from_str_parallel(source: &str) -> Result<Vec<Self>, ScanError> {
+        load_from_str_parallel(source, thread_count(), MIN_CHUNK_LEN)
+    }
+}
+
+impl MarkedYaml {
+    /// Load the given string as an array of YAML documents, on several threads.
+    ///
+    /// See the function [`load_from_str_parallel`] for more details. The markers are relative to
+    /// the start of `source`, as with [`load_from_str`].
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    ///
+    /// [`load_from_str_parallel`]: `Yaml::load_from_str_parallel`
+    /// [`load_from_str`]: `MarkedYaml::load_from_str`
+    pub fn load_from_str_parallel(source: &str) -> Result<Vec<Self>, ScanError> {
+        load_from_str_parallel(source, thread_count(), MIN_CHUNK_LEN)
+    }
+}
+
+/// Return the number of threads to load documents on.
+fn thread_count() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Load `source` on `threads` threads, in chunks of at least `min_chunk_len` bytes.
+fn load_from_str_parallel<Node>(
+    source: &str,
+    threads: usize,
+    min_chunk_len: usize,
+) -> Result<Vec<Node>, ScanError>
+where
+    Node: LoadableYamlNode + Send,
+{
+    if threads <= 1 || source.len() < 2 * min_chunk_len {
+        return load_chunk(source, Marker::new(0, 1, 0));
+    }
+    let chunks = split_chunks(source, threads * CHUNKS_PER_THREAD, min_chunk_len);
+    if chunks.len() <= 1 {
+        return load_chunk(source, Marker::new(0, 1, 0));
+    }
+
+    let next_chunk = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    // There is no use in more threads than chunks, or than the machine can run at once.
+    let workers = threads.min(chunks.len()).min(thread_count());
+    let mut loaded: Vec<(usize, Vec<Node>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut loaded = vec![];
+                    while !failed.load(Ordering::Relaxed) {
+                        let idx = next_chunk.fetch_add(1, Ordering::Relaxed);
+                        let Some(&(text, start)) = chunks.get(idx) else {
+                            break;
+                        };
+                        if let Ok(docs) = load_chunk(text, start) {
+                            loaded.push((idx, docs));
+                        } else {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    loaded
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+
+    if failed.into_inner() {
+        // The input is invalid and may have been split in the middle of a node. Only loading it as
+        // a whole reports the error `load_from_str` would.
+        return load_chunk(source, Marker::new(0, 1, 0));
+    }
+    loaded.sort_unstable_by_key(|&(idx, _)| idx);
+    Ok(loaded.into_iter().flat_map(|(_, docs)| docs).collect())
+}
+
+/// Load the documents of a chunk starting at `start` in the whole input.
+fn load_chunk<Node: LoadableYamlNode>(text: &str, start: Marker) -> Result<Vec<Node>, ScanError> {
+    let mut receiver = ShiftedReceiver {
+        loader: YamlLoader::default(),
+        start,
+    };
+    Parser::new_from_str(text).load(&mut receiver, true)?;
+    Ok(receiver.loader.into_documents())
+}
+
+/// A receiver that moves the spans of events by the start of their chunk before loading them.
+struct ShiftedReceiver<Node: LoadableYamlNode> {
+    /// The loader of the chunk.
+    loader: YamlLoader<Node>,
+    /// The position of the start of the chunk in the whole input.
+    start: Marker,
+}
+
+impl<Node: LoadableYamlNode> ShiftedReceiver<Node> {
+    /// Move a marker relative to the chunk to the same position in the whole input.
+    ///
+    /// Chunks start at the start of a line, so columns are left as is.
+    fn shift(&self, mark: Marker) -> Marker {
+        Marker::new(
+            self.start.index() + mark.index(),
+            self.start.line() + mark.line() - 1,
+            mark.col(),
+        )
+        .with_byte_offset(self.start.byte_offset() + mark.byte_offset())
+    }
+}
+
+impl<'input, Node: LoadableYamlNode> SpannedEventReceiver<'input> for ShiftedReceiver<Node> {
+    fn on_event(&mut self, ev: Event<'input>, span: Span) {
+        let span = Span::new(self.shift(span.start), self.shift(span.end));
+        self.loader.on_event(ev, span);
+    }
+}
+
+/// Split `source` into about `count` chunks of whole documents, of at least `min_len` bytes.
+///
+/// Each chunk is returned along with the position of its start in `source`.
+fn split_chunks(source: &str, count: usize, min_len: usize) -> Vec<(&str, Marker)> {
+    let target_len = (source.len() / count.max(1)).max(min_len);
+    let mut chunks = vec![];
+    let mut start = Marker::new(0, 1, 0);
+    for (offset, line) in document_starts(source) {
+        if offset - start.byte_offset() >= target_len {
+            let text = &source[start.byte_offset()..offset];
+            chunks.push((text, start));
+            start =
+                Marker::new(start.index() + text.chars().count(), line, 0).with_byte_offset(offset);
+        }
+    }
+    chunks.push((&source[start.byte_offset()..], start));
+    chunks
+}
+
+/// Where the pre-scan of [`document_starts`] is in the input.
+#[derive(Clone, Copy)]
+enum ScanState {
+    /// At the start of the input or after a document end marker (`...`), where directives may
+    /// appear. Holds the byte offset and line of the first directive, if any.
+    Directives(Option<(usize, usize)>),
+    /// In a document, before its root node.
+    Root,
+    /// After the header of a block scalar at the root of a document, before its first non-empty
+    /// line.
+    BlockScalarHeader,
+    /// In a block scalar at the root of a document whose content is not indented.
+    ///
+    /// Lines starting with `---` are part of the scalar. Only a document end marker ends it.
+    UnindentedBlockScalar,
+    /// In a document, past the start of its root node.
+    Content,
+}
+
+/// Return the byte offsets and lines of the starts of documents at which `source` can be split.
+///
+/// A document starts at its directives, if any, or at its `---` marker. Documents that do not
+/// start with a marker cannot be told apart from the previous one without parsing, and are left
+/// out.
+fn document_starts(source: &str) -> Vec<(usize, usize)> {
+    let mut starts = vec![];
+    let mut state = ScanState::Directives(None);
+    let mut offset = 0;
+    let mut line = 1;
+    while offset < source.len() {
+        let rest = &source[offset..];
+        let text_len = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        let text = &rest[..text_len];
+        let break_len = if rest[text_len..].starts_with("\r\n") {
+            2
+        } else {
+            usize::from(text_len < rest.len())
+        };
+
+        state = match state {
+            ScanState::UnindentedBlockScalar if is_marker(text, "...") => {
+                ScanState::Directives(None)
+            }
+            ScanState::UnindentedBlockScalar => state,
+            ScanState::BlockScalarHeader => match text.find(|c| c != ' ') {
+                None => state,
+                Some(0) => ScanState::UnindentedBlockScalar,
+                Some(_) => ScanState::Content,
+            },
+            _ if is_marker(text, "...") => ScanState::Directives(None),
+            _ if is_marker(text, "---") => {
+                let start = match state {
+                    ScanState::Directives(Some(directives)) => directives,
+                    _ => (offset, line),
+                };
+                if start.0 > 0 {
+                    starts.push(start);
+                }
+                root_state(&text[3..])
+            }
+            ScanState::Directives(first) if text.starts_with('%') => {
+                ScanState::Directives(first.or(Some((offset, line))))
+            }
+            ScanState::Directives(_) if matches!(root_state(text), ScanState::Root) => state,
+            ScanState::Directives(_) | ScanState::Root => root_state(text),
+            ScanState::Content => state,
+        };
+
+        offset += text_len + break_len;
+        line += 1;
+    }
+    starts
+}
+
+/// Return whether the line `text` is the document marker `marker` (`---` or `...`).
+fn is_marker(text: &str, marker: &str) -> bool {
+    text.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+/// Return the state of the pre-scan after `text`, which is on a line before the root node of a
+/// document.
+fn root_state(text: &str) -> ScanState {
+    let mut rest = text.trim_start_matches(['\u{FEFF}', ' ', '\t']);
+    // Skip the tag and anchor of the root node.
+    while rest.starts_with(['!', '&']) {
+        rest = rest
+            .trim_start_matches(|c| !matches!(c, ' ' | '\t'))
+            .trim_start_matches([' ', '\t']);
+    }
+    match rest.chars().next() {
+        None | Some('#') => ScanState::Root,
+        Some('|' | '>') => {
+            let header = rest[1..].split([' ', '\t']).next().unwrap_or_default();
+            // With an indentation indicator, the content of the scalar is indented.
+            if header.contains(|c: char| c.is_ascii_digit()) {
+                ScanState::Content
+            } else {
+                ScanState::BlockScalarHeader
+            }
+        }
+        Some(_) => ScanState::Content,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use saphyr_parser::Span;
+
+    use super::{document_starts, load_from_str_parallel};
+    use crate::{MarkedYaml, Yaml, YamlData};
+
+    include!("../../parser/tests/visual_to_raw.rs.inc");
+
+    /// Collect the spans of a node and of its children.
+    fn collect_spans(node: &MarkedYaml, spans: &mut Vec<Span>) {
+        spans.push(node.span);
+        match &node.data {
+            YamlData::Array(array) => {
+                for node in array {
+                    collect_spans(node, spans);
+                }
+            }
+            YamlData::Hash(hash) => {
+                for (key, value) in hash {
+                    collect_spans(key, spans);
+                    collect_spans(value, spans);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check that loading `source` in parallel gives the same result as loading it at once.
+    fn check_same_as_sequential(source: &str) {
+        let parallel = load_from_str_parallel::<Yaml>(source, 4, 1);
+        assert_eq!(parallel, Yaml::load_from_str(source), "{source:?}");
+
+        let spans = |docs: Vec<MarkedYaml>| {
+            let mut spans = vec![];
+            for doc in &docs {
+                collect_spans(doc, &mut spans);
+            }
+            spans
+        };
+        let parallel = load_from_str_parallel::<MarkedYaml>(source, 4, 1).map(spans);
+        assert_eq!(
+            parallel,
+            MarkedYaml::load_from_str(source).map(spans),
+            "{source:?}"
+        );
+    }
+
+    #[test]
+    fn test_document_starts() {
+        let source = "a\n---\nb\n...\n%YAML 1.2\n---\nc\n--- |\nd\n---\n...\n--- |\n  e\n---\r\nf";
+        let starts: Vec<_> = document_starts(source)
+            .into_iter()
+            .map(|(offset, line)| (&source[offset..offset + 3], line))
+            .collect();
+        assert_eq!(
+            starts,
+            [("---", 2), ("%YA", 5), ("---", 8), ("---", 12), ("---", 14)]
+        );
+    }
+
+    #[test]
+    fn test_same_as_sequential() {
+        for source in [
+            "",
+            "# only a comment\n",
+            "a: 1\n---\n- b\n- 'c'\n---\nd: [e, f]\n",
+            "--- é\n--- ü\r\n--- |\n  ---\n--- >\n\n  x\n---\n",
+            "\u{FEFF}first\n---\nsecond\n",
+            "--- |\nfoo\n%YAML 1.2\n---\nbar\n",
+            "a\n%YAML 1.2\n---\nb\n",
+            "a\n...\n%YAML 1.2\n%TAG !e! tag:example.com:\n---\n!e!b c\n",
+            "--- !!str\n|\nfoo\n---\nbar\n...\n--- baz\n",
+            "--- &a |-\n---\n...\n---\n*a\n",
+            "--- |1\n foo\n---\nbar\n",
+            "---\nkey: |\n  text\n---\n- [1, 2]\n",
+            "---\n\"unterminated\n---\nb\"\n",
+            "---\n[a,\n---\n]\n",
+            "--- |\n \n---\nbar\n",
+            "---\na: 1\n---\n*unknown\n",
+        ] {
+            check_same_as_sequential(source);
+        }
+    }
+
+    #[test]
+    fn test_same_as_sequential_on_yaml_test_suite() {
+        let dir = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../parser/tests/yaml-test-suite/src"
+        );
+        let mut n_cases = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let tests = fs::read_to_string(entry.unwrap().path()).unwrap();
+            let tests = Yaml::load_from_str(&tests).unwrap();
+            // Test fields are inherited from the previous test of the file.
+            let mut yaml = String::new();
+            for test in tests[0].as_vec().unwrap() {
+                if let Some(visual) = test["yaml"].as_str() {
+                    yaml = visual_to_raw(visual);
+                }
+                // Invalid inputs with a document marker are included, since they may be split in
+                // the middle of a node.
+                let is_multi_document = match Yaml::load_from_str(&yaml) {
+                    Ok(docs) => docs.len() > 1,
+                    Err(_) => yaml.lines().any(|line| line.starts_with("---")),
+                };
+                if is_multi_document {
+                    check_same_as_sequential(&yaml);
+                    n_cases += 1;
+                }
+            }
+        }
+        assert!(n_cases > 50, "found {n_cases} cases");
+    }
+}
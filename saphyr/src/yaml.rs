@@ -5,9 +5,12 @@
 use std::{convert::TryFrom, ops::Index, ops::IndexMut};
 
 use hashlink::LinkedHashMap;
-use saphyr_parser::{BufferedInput, Input, Parser, ScanError};
+use saphyr_parser::{
+    path::{PathSegment, SubtreeExtractor},
+    BorrowedInput, BufferedInput, Parser, ScanError,
+};
 
-use crate::{loader::parse_f64, YamlLoader};
+use crate::{loader::parse_f64, yaml11, LoadError, YamlLoader};
 
 /// A YAML node is stored as this `Yaml` enumeration, which provides an easy way to
 /// access your YAML document.
@@ -107,12 +110,99 @@ impl Yaml {
     ///
     /// # Errors
     /// Returns `ScanError` when loading fails.
-    pub fn load_from_parser<I: Input>(parser: &mut Parser<I>) -> Result<Vec<Yaml>, ScanError> {
+    pub fn load_from_parser<'input, I: BorrowedInput<'input>>(
+        parser: &mut Parser<'input, I>,
+    ) -> Result<Vec<Yaml>, ScanError> {
         let mut loader = YamlLoader::default();
         parser.load(&mut loader, true)?;
         Ok(loader.into_documents())
     }
 
+    /// Load the nodes at `path` in the documents of the given string.
+    ///
+    /// The whole `source` is parsed, but only the nodes at `path` are built. There is one node per
+    /// document that has the path. See [`SubtreeExtractor`] for how aliases to anchors outside of
+    /// the nodes are loaded.
+    ///
+    /// ```
+    /// use saphyr::Yaml;
+    ///
+    /// let source = "spec:\n  template:\n    spec:\n      containers: [app, sidecar]\n";
+    /// let path = ["spec".into(), "template".into(), "spec".into(), "containers".into()];
+    /// let containers = Yaml::load_subtree_from_str(source, &path).unwrap();
+    /// assert_eq!(containers.len(), 1);
+    /// assert_eq!(containers[0][1].as_str(), Some("sidecar"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_subtree_from_str(
+        source: &str,
+        path: &[PathSegment],
+    ) -> Result<Vec<Self>, ScanError> {
+        let mut extractor = SubtreeExtractor::new(YamlLoader::default(), path.iter().cloned());
+        Parser::new_from_str(source).load(&mut extractor, true)?;
+        Ok(extractor.into_inner().into_documents())
+    }
+
+    /// Load the given string as an array of YAML documents, bounding alias expansion.
+    ///
+    /// This is the same as [`Self::load_from_str`], except that aliases may expand to at most
+    /// `alias_budget` nodes in total (see [`YamlLoader::alias_budget`]). Use this when loading
+    /// untrusted input:
+    ///
+    /// ```
+    /// use saphyr::{LoadError, Yaml};
+    ///
+    /// let laughs = "
+    /// a: &a [lol, lol, lol]
+    /// b: &b [*a, *a, *a]
+    /// c: [*b, *b, *b]
+    /// ";
+    /// assert!(Yaml::safe_load_from_str(laughs, 100).is_ok());
+    /// assert!(matches!(
+    ///     Yaml::safe_load_from_str(laughs, 10),
+    ///     Err(LoadError::AliasBudgetExceeded(_))
+    /// ));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `LoadError` when loading fails or when the budget is exceeded.
+    pub fn safe_load_from_str(source: &str, alias_budget: usize) -> Result<Vec<Self>, LoadError> {
+        Self::safe_load_from_iter(source.chars(), alias_budget)
+    }
+
+    /// Load the contents of the given iterator as an array of YAML documents, bounding alias
+    /// expansion.
+    ///
+    /// See [`Self::safe_load_from_str`] for details.
+    ///
+    /// # Errors
+    /// Returns `LoadError` when loading fails or when the budget is exceeded.
+    pub fn safe_load_from_iter<I: Iterator<Item = char>>(
+        source: I,
+        alias_budget: usize,
+    ) -> Result<Vec<Yaml>, LoadError> {
+        let mut parser = Parser::new(BufferedInput::new(source));
+        Self::safe_load_from_parser(&mut parser, alias_budget)
+    }
+
+    /// Load the contents from the specified [`Parser`] as an array of YAML documents, bounding
+    /// alias expansion.
+    ///
+    /// See [`Self::safe_load_from_str`] for details.
+    ///
+    /// # Errors
+    /// Returns `LoadError` when loading fails or when the budget is exceeded.
+    pub fn safe_load_from_parser<'input, I: BorrowedInput<'input>>(
+        parser: &mut Parser<'input, I>,
+        alias_budget: usize,
+    ) -> Result<Vec<Yaml>, LoadError> {
+        YamlLoader::default()
+            .alias_budget(alias_budget)
+            .load_from_parser(parser)
+    }
+
     define_as!(as_bool, bool, Boolean);
     define_as!(as_i64, i64, Integer);
 
@@ -239,6 +329,41 @@ impl Yaml {
             }
         }
     }
+
+    /// Convert a string to a [`Yaml`] node, following the YAML 1.1 types.
+    ///
+    /// This is the same as [`Yaml::from_str`], except for the forms of booleans, integers, floats
+    /// and nulls that are recognized. Those are the forms of YAML 1.1, as implemented by `PyYAML`:
+    /// booleans include `yes`/`no`/`on`/`off`, integers may be binary (`0b`), octal (`0`-prefixed)
+    /// or base 60 (`1:30`) and digits may be separated by `_`. Floats are stored in a form
+    /// [`Yaml::as_f64`] understands.
+    ///
+    /// # Examples
+    /// ```
+    /// # use saphyr::Yaml;
+    /// assert!(matches!(Yaml::from_str_1_1("yes"), Yaml::Boolean(true)));
+    /// assert!(matches!(Yaml::from_str_1_1("Off"), Yaml::Boolean(false)));
+    /// assert!(matches!(Yaml::from_str_1_1("0b101010"), Yaml::Integer(42)));
+    /// assert!(matches!(Yaml::from_str_1_1("052"), Yaml::Integer(42)));
+    /// assert!(matches!(Yaml::from_str_1_1("1:30"), Yaml::Integer(90)));
+    /// assert!(matches!(Yaml::from_str_1_1("1_000"), Yaml::Integer(1000)));
+    /// assert_eq!(Yaml::from_str_1_1("1_000.5").as_f64(), Some(1000.5));
+    /// assert!(matches!(Yaml::from_str_1_1("0o52"), Yaml::String(_)));
+    /// ```
+    #[must_use]
+    pub fn from_str_1_1(v: &str) -> Yaml {
+        if yaml11::is_null(v) {
+            Yaml::Null
+        } else if let Some(b) = yaml11::parse_bool(v) {
+            Yaml::Boolean(b)
+        } else if let Some(i) = yaml11::parse_int(v) {
+            Yaml::Integer(i)
+        } else if let Some(f) = yaml11::parse_float(v) {
+            Yaml::Real(f)
+        } else {
+            Yaml::String(v.to_owned())
+        }
+    }
 }
 
 static BAD_VALUE: Yaml = Yaml::BadValue;
@@ -0,0 +1,145 @@
+//! Resolution of plain scalars following the YAML 1.1 types.
+//!
+//! YAML 1.1 recognizes more forms than the YAML 1.2 Core schema: `yes`/`no`/`on`/`off` booleans,
+//! binary, `0`-prefixed octal and base 60 integers and floats, and `_` separators between digits.
+//! The forms are those of the YAML 1.1 [type repository](https://yaml.org/type/), as implemented
+//! by `PyYAML`. In particular, `y` and `n` are not booleans.
+
+/// Return whether `v` is a YAML 1.1 null.
+pub(crate) fn is_null(v: &str) -> bool {
+    matches!(v, "" | "~" | "null" | "Null" | "NULL")
+}
+
+/// Parse a YAML 1.1 boolean.
+pub(crate) fn parse_bool(v: &str) -> Option<bool> {
+    match v {
+        "yes" | "Yes" | "YES" | "true" | "True" | "TRUE" | "on" | "On" | "ON" => Some(true),
+        "no" | "No" | "NO" | "false" | "False" | "FALSE" | "off" | "Off" | "OFF" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a YAML 1.1 integer.
+///
+/// Returns `None` if `v` is not an integer or if it does not fit in an `i64`.
+pub(crate) fn parse_int(v: &str) -> Option<i64> {
+    let (negative, v) = split_sign(v);
+    let magnitude = if let Some(digits) = v.strip_prefix("0b") {
+        parse_digits(digits, 2)?
+    } else if let Some(digits) = v.strip_prefix("0x") {
+        parse_digits(digits, 16)?
+    } else if v.contains(':') {
+        let (first, rest) = v.split_once(':')?;
+        if !first.starts_with(|c: char| matches!(c, '1'..='9')) {
+            return None;
+        }
+        rest.split(':')
+            .try_fold(parse_digits(first, 10)?, |acc, part| {
+                acc.checked_mul(60)?.checked_add(parse_base60_digit(part)?)
+            })?
+    } else if v == "0" {
+        0
+    } else if let Some(digits) = v.strip_prefix('0') {
+        parse_digits(digits, 8)?
+    } else if v.starts_with(|c: char| c.is_ascii_digit()) {
+        parse_digits(v, 10)?
+    } else {
+        return None;
+    };
+    if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    }
+}
+
+/// Parse a YAML 1.1 float.
+///
+/// Returns the float in a form [`crate::Yaml::as_f64`] understands, i.e. with no `_` separators
+/// and base 60 floats converted to base 10.
+pub(crate) fn parse_float(v: &str) -> Option<String> {
+    match v {
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" | "-.inf" | "-.Inf" | "-.INF" => {
+            return Some(v.to_owned())
+        }
+        ".nan" | ".NaN" | ".NAN" => return Some(".nan".to_owned()),
+        _ => {}
+    }
+
+    let (negative, unsigned) = split_sign(v);
+    let (integral, fractional) = unsigned.split_once('.')?;
+    if integral.contains(':') {
+        // Base 60, e.g. `190:20:30.15`.
+        let (first, rest) = integral.split_once(':')?;
+        if !first.starts_with(|c: char| c.is_ascii_digit()) || !is_digits(fractional, 10) {
+            return None;
+        }
+        let value = rest
+            .split(':')
+            .try_fold(parse_digits(first, 10)?, |acc, part| {
+                acc.checked_mul(60)?.checked_add(parse_base60_digit(part)?)
+            })?;
+        #[allow(clippy::cast_precision_loss)]
+        let value = value as f64
+            + format!("0.{}", fractional.replace('_', ""))
+                .parse::<f64>()
+                .ok()?;
+        let value = if negative { -value } else { value };
+        return Some(format!("{value:?}"));
+    }
+
+    let (fractional, exponent) = match fractional.split_once(['e', 'E']) {
+        Some((fractional, exponent)) => (fractional, Some(exponent)),
+        None => (fractional, None),
+    };
+    let valid_integral = if integral.is_empty() {
+        fractional.starts_with(|c: char| c.is_ascii_digit())
+    } else {
+        integral.starts_with(|c: char| c.is_ascii_digit()) && is_digits(integral, 10)
+    };
+    let valid_exponent = exponent.map_or(true, |exponent| {
+        exponent.len() > 1
+            && exponent.starts_with(['+', '-'])
+            && exponent[1..].bytes().all(|b| b.is_ascii_digit())
+    });
+    if valid_integral && is_digits(fractional, 10) && valid_exponent {
+        Some(v.replace('_', ""))
+    } else {
+        None
+    }
+}
+
+/// Split the optional sign in front of a number.
+///
+/// Returns whether the number is negative and the number without its sign.
+fn split_sign(v: &str) -> (bool, &str) {
+    if let Some(v) = v.strip_prefix('-') {
+        (true, v)
+    } else {
+        (false, v.strip_prefix('+').unwrap_or(v))
+    }
+}
+
+/// Return whether `v` is made only of digits of the given radix and `_` separators.
+fn is_digits(v: &str, radix: u32) -> bool {
+    v.chars().all(|c| c == '_' || c.is_digit(radix))
+}
+
+/// Parse a non-empty sequence of digits of the given radix, which may be separated by `_`.
+fn parse_digits(v: &str, radix: u32) -> Option<u64> {
+    if !is_digits(v, radix) {
+        return None;
+    }
+    u64::from_str_radix(&v.replace('_', ""), radix).ok()
+}
+
+/// Parse a base 60 digit (`[0-5]?[0-9]`).
+fn parse_base60_digit(v: &str) -> Option<u64> {
+    match v.as_bytes() {
+        [units @ b'0'..=b'9'] => Some(u64::from(units - b'0')),
+        [tens @ b'0'..=b'5', units @ b'0'..=b'9'] => {
+            Some(u64::from(tens - b'0') * 10 + u64::from(units - b'0'))
+        }
+        _ => None,
+    }
+}
@@ -1,7 +1,11 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr::{Yaml, YamlEmitter};
+use saphyr::{
+    Diagnostic, LoadError, MarkedYaml, RenderStyle, WarningKind, Yaml, Yaml11Mode, YamlEmitter,
+    YamlLoader,
+};
+use saphyr_parser::Parser;
 
 #[test]
 fn test_api() {
@@ -83,6 +87,169 @@ a1: &DEFAULT
     assert_eq!(doc["a1"]["b2"], Yaml::BadValue);
 }
 
+#[test]
+fn test_alias_budget() {
+    // `a` has 3 nodes, `b` has 1 + 2 * 3 = 7 nodes. The aliases in the document expand to
+    // 2 * 3 + 3 * 7 = 27 nodes.
+    let s = "
+a: &a [x, y]
+b: &b [*a, *a]
+c: [*b, *b, *b]
+";
+    let out = Yaml::safe_load_from_str(s, 27).unwrap();
+    assert_eq!(out, Yaml::load_from_str(s).unwrap());
+    assert_eq!(out[0]["c"][2][1][0].as_str(), Some("x"));
+    assert_eq!(MarkedYaml::safe_load_from_str(s, 27).unwrap().len(), 1);
+
+    match Yaml::safe_load_from_str(s, 26) {
+        Err(LoadError::AliasBudgetExceeded(span)) => {
+            assert_eq!((span.start.line(), span.start.col()), (4, 12));
+        }
+        x => panic!("expected an alias budget error, got {x:?}"),
+    }
+    assert!(matches!(
+        MarkedYaml::safe_load_from_str(s, 26),
+        Err(LoadError::AliasBudgetExceeded(_))
+    ));
+    assert!(matches!(
+        Yaml::safe_load_from_str("a: [", 26),
+        Err(LoadError::Scan(_))
+    ));
+
+    // A loader fed by `Parser::load` keeps the error for `try_into_documents`.
+    let mut loader = YamlLoader::<Yaml>::default().alias_budget(26);
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    match loader.try_into_documents() {
+        Err(LoadError::AliasBudgetExceeded(span)) => {
+            assert_eq!((span.start.line(), span.start.col()), (4, 12));
+        }
+        x => panic!("expected an alias budget error, got {x:?}"),
+    }
+    let mut loader = YamlLoader::<Yaml>::default().alias_budget(27);
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    assert_eq!(
+        loader.try_into_documents().unwrap(),
+        Yaml::load_from_str(s).unwrap()
+    );
+}
+
+#[test]
+fn test_load_error_diagnostic() {
+    let s = "a: &a [x, y]\nb: [*a, *a]\n";
+    let error = Yaml::safe_load_from_str(s, 4).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(&error).render(s, RenderStyle::Plain),
+        "\
+error: alias expansion budget exceeded
+ --> 2:9
+  |
+2 | b: [*a, *a]
+  |         ^~
+"
+    );
+}
+
+#[test]
+fn test_yaml11() {
+    let s = "
+- [yes, No, on, OFF, y, n]
+- [0b1010_1010, 0777, -0x_1F, 190:20:30, -1:30, 1_000, 0, 09, 0o17]
+- [1_000.5, 190:20:30.15, -.5, 1.5e+3, 1e3, .NaN, -.Inf]
+- [Null, ~, NULL, '010']
+- [!!bool yes, !!int 0b11, !!float 1_0.0, !!null Null]
+";
+    let load = |s: &str, mode| {
+        let mut loader = YamlLoader::<Yaml>::default().yaml11_mode(mode);
+        Parser::new_from_str(s).load(&mut loader, true).unwrap();
+        loader.into_documents()
+    };
+    let docs = load(s, Yaml11Mode::Always);
+    let doc = &docs[0];
+
+    let bools: Vec<_> = doc[0].as_vec().unwrap().iter().map(Yaml::as_bool).collect();
+    assert_eq!(
+        bools,
+        [Some(true), Some(false), Some(true), Some(false), None, None]
+    );
+
+    let ints: Vec<_> = doc[1].as_vec().unwrap().iter().map(Yaml::as_i64).collect();
+    assert_eq!(
+        ints,
+        [
+            Some(170),
+            Some(511),
+            Some(-31),
+            Some(685_230),
+            Some(-90),
+            Some(1000),
+            Some(0),
+            None,
+            None
+        ]
+    );
+    assert_eq!(doc[1][7], Yaml::String("09".into()));
+
+    let floats: Vec<_> = doc[2].as_vec().unwrap().iter().map(Yaml::as_f64).collect();
+    assert_eq!(
+        floats[..4],
+        [Some(1000.5), Some(685_230.15), Some(-0.5), Some(1500.0)]
+    );
+    assert_eq!(doc[2][4], Yaml::String("1e3".into()));
+    assert!(floats[5].unwrap().is_nan());
+    assert_eq!(floats[6], Some(f64::NEG_INFINITY));
+
+    assert!(doc[3][0].is_null() && doc[3][1].is_null() && doc[3][2].is_null());
+    assert_eq!(doc[3][3], Yaml::String("010".into()));
+
+    assert_eq!(doc[4][0], Yaml::Boolean(true));
+    assert_eq!(doc[4][1], Yaml::Integer(3));
+    assert_eq!(doc[4][2].as_f64(), Some(10.0));
+    assert!(doc[4][3].is_null());
+
+    // By default, YAML 1.1 forms are left alone.
+    assert_eq!(load(s, Yaml11Mode::Never), Yaml::load_from_str(s).unwrap());
+    assert_eq!(
+        load(s, Yaml11Mode::Never)[0][0][0],
+        Yaml::String("yes".into())
+    );
+
+    // The directive applies to its document only.
+    let s = "%YAML 1.1\n--- yes\n...\n%YAML 1.2\n--- yes\n...\n--- yes\n";
+    let docs: Vec<_> = load(s, Yaml11Mode::FromDirective)
+        .iter()
+        .map(Yaml::as_bool)
+        .collect();
+    assert_eq!(docs, [Some(true), None, None]);
+    let docs: Vec<_> = load(s, Yaml11Mode::Always)
+        .iter()
+        .map(Yaml::as_bool)
+        .collect();
+    assert_eq!(docs, [Some(true), Some(true), Some(true)]);
+}
+
+#[test]
+fn test_ambiguous_scalar_warnings() {
+    let s = "[yes, 'no', !!str on, 010, 0x10, 1_000, 1.5, 1e3, foo, ~, Null]";
+    for mode in [Yaml11Mode::Never, Yaml11Mode::Always] {
+        let mut loader = YamlLoader::<Yaml>::default()
+            .yaml11_mode(mode)
+            .collect_warnings(true);
+        Parser::new_from_str(s).load(&mut loader, true).unwrap();
+        let warnings: Vec<_> = loader
+            .take_warnings()
+            .iter()
+            .inspect(|w| assert_eq!(w.kind(), WarningKind::AmbiguousScalar))
+            .map(|w| &s[w.span().byte_range()])
+            .collect();
+        assert_eq!(warnings, ["yes", "010", "1_000", "1e3", "Null"]);
+    }
+
+    // Warnings are not collected unless asked for.
+    let mut loader = YamlLoader::<Yaml>::default();
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    assert!(loader.take_warnings().is_empty());
+}
+
 #[test]
 fn test_plain_datatype() {
     let s = "
@@ -233,3 +400,74 @@ fn test_integer_key() {
     let first = out.into_iter().next().unwrap();
     assert_eq!(first[0]["important"].as_bool().unwrap(), true);
 }
+
+#[test]
+fn test_load_subtree() {
+    let s = "
+defaults: &defaults
+  image: base
+spec:
+  template:
+    spec:
+      containers:
+        - name: app
+          <<: *defaults
+        - name: sidecar
+---
+spec:
+  template:
+    spec:
+      containers: []
+---
+unrelated: true
+";
+    let path = [
+        "spec".into(),
+        "template".into(),
+        "spec".into(),
+        "containers".into(),
+    ];
+    let full = Yaml::load_from_str(s).unwrap();
+    let subtrees = Yaml::load_subtree_from_str(s, &path).unwrap();
+    assert_eq!(
+        subtrees,
+        [
+            full[0]["spec"]["template"]["spec"]["containers"].clone(),
+            full[1]["spec"]["template"]["spec"]["containers"].clone(),
+        ]
+    );
+    assert_eq!(subtrees[0][0]["<<"]["image"].as_str(), Some("base"));
+
+    let name = [path.as_slice(), &[1.into(), "name".into()]].concat();
+    let full = MarkedYaml::load_from_str(s).unwrap();
+    let subtrees = MarkedYaml::load_subtree_from_str(s, &name).unwrap();
+    assert_eq!(subtrees.len(), 1);
+    let expected =
+        &full[0].data["spec"].data["template"].data["spec"].data["containers"].data[1].data["name"];
+    assert_eq!(subtrees[0], *expected);
+    assert_eq!(subtrees[0].span, expected.span);
+
+    assert!(Yaml::load_subtree_from_str(s, &["missing".into()])
+        .unwrap()
+        .is_empty());
+    assert!(Yaml::load_subtree_from_str("a: [b", &["a".into()]).is_err());
+}
+
+#[test]
+fn test_marked_yaml_collection_spans() {
+    let s =
+        "a: [1, 2 ]  # c\nb:\n  - x\n  - {y: z}\n\n# tail\nc: {}\nd:\n  - - e\n  - f\n\n# end\n";
+    let doc = &MarkedYaml::load_from_str(s).unwrap()[0];
+    let text = |node: &MarkedYaml| &s[node.span.byte_range()];
+    let value = |key: &str| &doc.data[key];
+
+    assert_eq!(text(value("a")), "[1, 2 ]");
+    assert_eq!(text(value("b")), "- x\n  - {y: z}");
+    assert_eq!(text(&value("b").data[1]), "{y: z}");
+    assert_eq!(text(value("c")), "{}");
+    assert_eq!(text(value("d")), "- - e\n  - f");
+    assert_eq!(text(&value("d").data[0]), "- e");
+    assert_eq!(text(doc), s.trim_end_matches("\n\n# end\n"));
+    assert_eq!(doc.span.end.line(), 10);
+    assert_eq!(doc.span.end.col(), 5);
+}